@@ -14,6 +14,18 @@ pub const MIN_VOTING_DURATION_SEC: u32 = 300;
 
 pub const GLOBAL_BUCKET_IDENT: &str = "global";
 
+/// Tag category of group member tags.
+pub const GROUP_TAG_CATEGORY: &str = "group";
+
+/// Asset id of the DAO vote token in the asset cache.
+pub const VOTE_TOKEN_ASSET_ID: u8 = 1;
+
+/// Max number of members receiving vote tokens in one distribution.
+pub const MAX_FT_DISTRIBUTE_MEMBERS: usize = 100;
+
+/// Max number of media in the proposal including all description versions.
+pub const MAX_PROPOSAL_MEDIA: usize = 16;
+
 pub const EVENT_CALLER_KEY: &str = "event_caller";
 
 // DAO consts ids.
@@ -1,7 +1,7 @@
 use crate::constants::GLOBAL_BUCKET_IDENT;
 use crate::media::Media;
 use crate::reward::VersionedReward;
-use crate::role::{Roles, UserRoles};
+use crate::role::{VersionedRoles, VersionedUserRoles};
 use crate::settings::{assert_valid_dao_settings, Settings, VersionedSettings};
 use crate::tags::{TagInput, Tags};
 use crate::treasury::{Asset, TreasuryPartitionInput, VersionedTreasuryPartition};
@@ -17,9 +17,11 @@ use library::{FnCallId, MethodName};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap};
 use near_sdk::serde::Serialize;
-use near_sdk::{near_bindgen, require, AccountId, Balance, BorshStorageKey, PanicOnDefault};
+use near_sdk::{
+    env, near_bindgen, require, AccountId, Balance, BorshStorageKey, IntoStorageKey, PanicOnDefault,
+};
 
-use crate::group::{Group, GroupInput};
+use crate::group::{GroupInput, VersionedGroup};
 
 use crate::{proposal::*, StorageKey, TagCategory};
 use crate::{GroupId, ProposalId};
@@ -79,9 +81,9 @@ pub struct Contract {
     /// Delegated token total amount.
     pub total_delegation_amount: Balance,
     /// User's roles in groups.
-    pub user_roles: LookupMap<AccountId, UserRoles>,
+    pub user_roles: LookupMap<AccountId, VersionedUserRoles>,
     /// Group's provided roles.
-    pub group_roles: LookupMap<GroupId, Roles>,
+    pub group_roles: LookupMap<GroupId, VersionedRoles>,
     /// Total amount of minted tokens.
     pub ft_total_supply: u32,
     /// Decimals of token.
@@ -91,7 +93,7 @@ pub struct Contract {
     /// Sum all unique members in groups.
     pub total_members_count: u32,
     pub group_last_id: GroupId,
-    pub groups: UnorderedMap<GroupId, Group>,
+    pub groups: UnorderedMap<GroupId, VersionedGroup>,
     pub settings: LazyOption<VersionedSettings>,
     pub proposal_last_id: u32,
     pub proposals: UnorderedMap<u32, VersionedProposal>,
@@ -202,4 +204,16 @@ impl Contract {
 
         contract
     }
+
+    /// Called on the new version by the migration binary.
+    /// Converts data stored by the previous version and removes downloaded binaries.
+    #[private]
+    #[init(ignore_state)]
+    pub fn deploy_upgrade_bin() -> Self {
+        let mut contract: Contract = env::state_read().expect("failed to read contract state");
+        contract.migrate_unversioned_groups();
+        env::storage_remove(&StorageKeys::NewVersionMigrationBin.into_storage_key());
+        env::storage_remove(&StorageKeys::NewVersionUpgradeBin.into_storage_key());
+        contract
+    }
 }
@@ -84,6 +84,9 @@ impl Contract {
             env::predecessor_account_id() == settings.staking_id,
             ERR_CALLER
         );
+        if prev_account_id == new_account_id {
+            return (amount, self.delegation_total_supply());
+        }
         let prev_account_prev_amount = self
             .delegations
             .get(&prev_account_id)
@@ -96,12 +99,8 @@ impl Contract {
             .checked_sub(amount.0)
             .expect(ERR_STAKING_INTERNAL);
         let new_account_new_amount = new_account_prev_amount + amount.0;
-        if new_account_prev_amount == 0 && new_account_new_amount > 0 {
-            self.total_delegators_count += 1;
-        }
-        if prev_account_prev_amount > 0 && prev_account_new_amount == 0 {
-            self.total_delegators_count -= 1;
-        }
+        self.update_token_holders_count(prev_account_prev_amount, prev_account_new_amount);
+        self.update_token_holders_count(new_account_prev_amount, new_account_new_amount);
         self.delegations
             .insert(&prev_account_id, &prev_account_new_amount);
         self.delegations
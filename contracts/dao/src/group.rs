@@ -1,18 +1,23 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use library::derive_into_versioned;
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
-    require,
+    env, near_bindgen, require,
     serde::{Deserialize, Serialize},
-    AccountId,
+    AccountId, IntoStorageKey,
 };
 
 use crate::{
-    contract::Contract,
+    contract::*,
     internal::utils::current_timestamp_sec,
     reward::Reward,
-    role::{MemberRoles, Roles, UserRoles},
-    GroupId, RewardId, RoleId, TagId,
+    role::{
+        MemberRoles, RoleMetadata, Roles, RolesV1, UserRoles, UserRolesV1, VersionedRoles,
+        VersionedUserRoles,
+    },
+    workflow::InternalDaoActionError,
+    GroupId, RewardId, RoleId, TagId, TimestampSec,
 };
 
 #[derive(Serialize, Deserialize)]
@@ -24,6 +29,9 @@ use crate::{
 pub struct GroupMember {
     pub account_id: AccountId,
     pub tags: Vec<TagId>,
+    /// Timestamp until which is the membership valid.
+    #[serde(default)]
+    pub valid_to: Option<TimestampSec>,
 }
 
 impl GroupMember {
@@ -31,6 +39,7 @@ impl GroupMember {
         Self {
             account_id,
             tags: vec![],
+            valid_to: None,
         }
     }
 }
@@ -45,7 +54,7 @@ pub struct GroupSettings {
     /// Must be included among provided group members.
     pub leader: Option<AccountId>,
     /// Reference to parent group.
-    /// ATM its only evidence value.
+    /// Members of all ancestor groups have `ActivityRight::ParentOf` right over the group.
     /// GroupId = 0 means no parent group.
     pub parent_group: GroupId,
 }
@@ -64,11 +73,80 @@ pub struct GroupInput {
     pub member_roles: Vec<MemberRoles>,
 }
 
+/// Node of the group hierarchy.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct GroupTreeNode {
+    pub id: GroupId,
+    pub name: String,
+    pub children: Vec<GroupTreeNode>,
+}
+
+impl GroupTreeNode {
+    /// Build trees of groups with `parent` as parent group.
+    /// `children` maps parent group id to its direct children.
+    pub fn build(
+        parent: GroupId,
+        children: &mut HashMap<GroupId, Vec<(GroupId, String)>>,
+    ) -> Vec<GroupTreeNode> {
+        let mut nodes = children.remove(&parent).unwrap_or_default();
+        nodes.sort_by_key(|(id, _)| *id);
+        nodes
+            .into_iter()
+            .map(|(id, name)| GroupTreeNode {
+                id,
+                name,
+                children: Self::build(id, children),
+            })
+            .collect()
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum VersionedGroup {
+    V1(GroupV1),
+    V2(Group),
+}
+
+derive_into_versioned!(Group, VersionedGroup, V2);
+
+impl From<VersionedGroup> for Group {
+    fn from(input: VersionedGroup) -> Self {
+        match input {
+            VersionedGroup::V1(group) => group.into(),
+            VersionedGroup::V2(group) => group,
+        }
+    }
+}
+
+/// Group without membership expiration.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct GroupV1 {
+    pub settings: GroupSettings,
+    pub members: HashMap<AccountId, Vec<TagId>>,
+    pub rewards: Vec<(RewardId, RoleId)>,
+}
+
+impl From<GroupV1> for Group {
+    fn from(input: GroupV1) -> Self {
+        Self {
+            settings: input.settings,
+            members: input.members,
+            members_valid_to: HashMap::new(),
+            rewards: input.rewards,
+        }
+    }
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Group {
     pub settings: GroupSettings,
     pub members: HashMap<AccountId, Vec<TagId>>,
+    /// Timestamps until which are the memberships valid.
+    /// Members without record are members forever.
+    pub members_valid_to: HashMap<AccountId, TimestampSec>,
     /// Reward ids of all related rewards.
     pub rewards: Vec<(RewardId, RoleId)>,
 }
@@ -85,22 +163,34 @@ impl Group {
                 "Leader must be contained in group members."
             );
         }
-        let mut hm_members = HashMap::with_capacity(members.len());
-        for i in members.into_iter() {
-            hm_members.insert(i.account_id, i.tags);
-        }
-        Group {
+        let mut group = Group {
             settings,
-            members: hm_members,
+            members: HashMap::with_capacity(members.len()),
+            members_valid_to: HashMap::new(),
             rewards: vec![],
+        };
+        for member in members.into_iter() {
+            group.add_member(member);
         }
+        group
     }
+    /// Return count of not expired members.
     pub fn members_count(&self) -> usize {
-        self.members.len()
+        let current_timestamp = current_timestamp_sec();
+        self.members
+            .keys()
+            .filter(|account_id| !self.is_member_expired(account_id, current_timestamp))
+            .count()
     }
     /// Add member to the group.
     /// Return true if member was overwriten.
     pub fn add_member(&mut self, member: GroupMember) -> bool {
+        if let Some(valid_to) = member.valid_to {
+            self.members_valid_to
+                .insert(member.account_id.clone(), valid_to);
+        } else {
+            self.members_valid_to.remove(&member.account_id);
+        }
         self.members
             .insert(member.account_id, member.tags)
             .is_some()
@@ -114,30 +204,104 @@ impl Group {
                 self.settings.leader = None;
             }
         }
+        self.members_valid_to.remove(account_id);
         self.members.remove(account_id).is_some()
     }
+    /// Return all members including the expired ones.
     pub fn get_members_accounts_refs(&self) -> Vec<&AccountId> {
         self.members.iter().map(|member| member.0).collect()
     }
+    /// Return not expired members.
     pub fn get_members_accounts(&self) -> Vec<AccountId> {
+        let current_timestamp = current_timestamp_sec();
         self.members
-            .clone()
-            .into_iter()
-            .map(|member| member.0)
+            .keys()
+            .filter(|account_id| !self.is_member_expired(account_id, current_timestamp))
+            .cloned()
+            .collect()
+    }
+    /// Return expired members.
+    pub fn get_expired_members(&self, current_timestamp: TimestampSec) -> Vec<AccountId> {
+        self.members_valid_to
+            .iter()
+            .filter(|(_, valid_to)| **valid_to <= current_timestamp)
+            .map(|(account_id, _)| account_id.to_owned())
             .collect()
     }
+    pub fn is_member_expired(
+        &self,
+        account_id: &AccountId,
+        current_timestamp: TimestampSec,
+    ) -> bool {
+        self.members_valid_to
+            .get(account_id)
+            .map(|valid_to| *valid_to <= current_timestamp)
+            .unwrap_or_default()
+    }
+    /// Return true if account is not expired member.
     pub fn is_member(&self, account_id: &AccountId) -> bool {
         self.members.contains_key(account_id)
+            && !self.is_member_expired(account_id, current_timestamp_sec())
+    }
+    pub fn is_member_tagged(&self, account_id: &AccountId, tag: TagId) -> bool {
+        self.is_member(account_id)
+            && self
+                .members
+                .get(account_id)
+                .map(|tags| tags.contains(&tag))
+                .unwrap_or_default()
+    }
+    pub fn get_members_with_tag(&self, tag: TagId) -> Vec<AccountId> {
+        let current_timestamp = current_timestamp_sec();
+        self.members
+            .iter()
+            .filter(|(account_id, tags)| {
+                tags.contains(&tag) && !self.is_member_expired(account_id, current_timestamp)
+            })
+            .map(|(account_id, _)| account_id.to_owned())
+            .collect()
+    }
+    /// Add `tags` to the member.
+    /// Return tags the member did not have before.
+    pub fn add_member_tags(&mut self, account_id: &AccountId, tags: Vec<TagId>) -> Vec<TagId> {
+        let mut added = vec![];
+        if let Some(member_tags) = self.members.get_mut(account_id) {
+            for tag in tags {
+                if !member_tags.contains(&tag) {
+                    member_tags.push(tag);
+                    added.push(tag);
+                }
+            }
+        }
+        added
+    }
+    /// Remove `tags` from the member.
+    /// Return tags which were actually removed.
+    pub fn remove_member_tags(&mut self, account_id: &AccountId, tags: Vec<TagId>) -> Vec<TagId> {
+        let mut removed = vec![];
+        if let Some(member_tags) = self.members.get_mut(account_id) {
+            for tag in tags {
+                if let Some(pos) = member_tags.iter().position(|t| *t == tag) {
+                    member_tags.swap_remove(pos);
+                    removed.push(tag);
+                }
+            }
+        }
+        removed
     }
     pub fn is_account_id_leader(&self, account_id: &AccountId) -> bool {
-        if let Some(ref leader) = self.settings.leader {
+        if let Some(leader) = self.group_leader() {
             *leader == *account_id
         } else {
             false
         }
     }
+    /// Return leader if its membership is not expired.
     pub fn group_leader(&self) -> Option<&AccountId> {
-        self.settings.leader.as_ref()
+        self.settings
+            .leader
+            .as_ref()
+            .filter(|leader| self.is_member(leader))
     }
     pub fn group_reward_ids(&self) -> Vec<(RewardId, RoleId)> {
         self.rewards.to_owned()
@@ -155,11 +319,85 @@ impl Group {
     }
 }
 
+#[near_bindgen]
 impl Contract {
+    /// Remove expired memberships and member roles of the group.
+    /// Anyone can call it.
+    /// Return number of removed members.
+    pub fn group_purge_expired(&mut self, id: GroupId) -> u32 {
+        let mut group = self
+            .groups
+            .get(&id)
+            .map(Group::from)
+            .expect("group not found");
+        let rewards: Vec<(RewardId, RoleId)> = group.group_reward_ids();
+        let current_timestamp = current_timestamp_sec();
+        let expired_members = group.get_expired_members(current_timestamp);
+        for account_id in expired_members.iter() {
+            group.remove_member(account_id);
+            self.remove_user_role_group(account_id, id);
+            self.remove_wallet_reward(account_id, rewards.as_slice(), current_timestamp);
+        }
+        for account_id in group.get_members_accounts_refs() {
+            if let Some(mut user_roles) = self.user_roles.get(account_id).map(UserRoles::from) {
+                let expired_roles = user_roles.remove_expired_group_roles(id, current_timestamp);
+                if expired_roles.is_empty() {
+                    continue;
+                }
+                let rewards_to_remove: Vec<(RewardId, RoleId)> = rewards
+                    .iter()
+                    .filter(|(_, role_id)| expired_roles.contains(role_id))
+                    .copied()
+                    .collect();
+                self.save_user_roles(account_id, &user_roles);
+                self.remove_wallet_reward(
+                    account_id,
+                    rewards_to_remove.as_slice(),
+                    current_timestamp,
+                );
+            }
+        }
+        self.groups.insert(&id, &group.into());
+        expired_members.len() as u32
+    }
+}
+
+impl Contract {
+    /// Wrap groups stored without versioning and user roles of their members as `V1`.
+    /// Already versioned values are skipped.
+    pub fn migrate_unversioned_groups(&mut self) {
+        let mut accounts = HashSet::new();
+        let roles_prefix = StorageKeys::GroupRoles.into_storage_key();
+        for index in 0..self.groups.len() {
+            let value_raw = self.groups.values_as_vector().get_raw(index).unwrap();
+            if let Ok(group) = GroupV1::try_from_slice(&value_raw) {
+                let key_raw = self.groups.keys_as_vector().get_raw(index).unwrap();
+                accounts.extend(group.members.keys().cloned());
+                self.groups
+                    .insert_raw(&key_raw, &VersionedGroup::V1(group).try_to_vec().unwrap());
+                let key = [roles_prefix.as_slice(), &key_raw].concat();
+                let roles =
+                    env::storage_read(&key).and_then(|raw| RolesV1::try_from_slice(&raw).ok());
+                if let Some(roles) = roles {
+                    env::storage_write(&key, &VersionedRoles::V1(roles).try_to_vec().unwrap());
+                }
+            }
+        }
+        let prefix = StorageKeys::UserRoles.into_storage_key();
+        for account_id in accounts {
+            let key = [prefix.as_slice(), &account_id.try_to_vec().unwrap()].concat();
+            let roles =
+                env::storage_read(&key).and_then(|raw| UserRolesV1::try_from_slice(&raw).ok());
+            if let Some(roles) = roles {
+                env::storage_write(&key, &VersionedUserRoles::V1(roles).try_to_vec().unwrap());
+            }
+        }
+    }
     /// Add `group` to the contract.
     /// Also add defined roles to all group users.
     /// Update fatal statistics.
-    pub fn group_add(&mut self, group: GroupInput) {
+    pub fn group_add(&mut self, group: GroupInput) -> Result<(), InternalDaoActionError> {
+        self.assert_valid_parent_group(self.group_last_id + 1, group.settings.parent_group)?;
         self.group_last_id += 1;
         self.internal_add_group_roles(
             self.group_last_id,
@@ -168,8 +406,51 @@ impl Contract {
         );
         self.groups.insert(
             &self.group_last_id,
-            &Group::new(group.settings, group.members),
+            &Group::new(group.settings, group.members).into(),
         );
+        Ok(())
+    }
+    /// Return ids of all ancestors of the group ordered from the parent.
+    pub fn group_ancestors(&self, id: GroupId) -> Vec<GroupId> {
+        let mut ancestors = vec![];
+        let mut current_id = id;
+        while let Some(group) = self.groups.get(&current_id).map(Group::from) {
+            let parent = group.settings.parent_group;
+            if parent == 0 || parent == id || ancestors.contains(&parent) {
+                break;
+            }
+            ancestors.push(parent);
+            current_id = parent;
+        }
+        ancestors
+    }
+    /// Return ids of direct children of the group.
+    pub fn group_children(&self, id: GroupId) -> Vec<GroupId> {
+        self.groups
+            .iter()
+            .filter_map(|(child_id, group)| {
+                (Group::from(group).settings.parent_group == id).then_some(child_id)
+            })
+            .collect()
+    }
+    /// Check that `parent` exists and setting it as parent of group `id` does not create a cycle.
+    fn assert_valid_parent_group(
+        &self,
+        id: GroupId,
+        parent: GroupId,
+    ) -> Result<(), InternalDaoActionError> {
+        if parent == 0 {
+            return Ok(());
+        }
+        if parent == id || self.groups.get(&parent).map(Group::from).is_none() {
+            return Err(InternalDaoActionError("invalid parent group".into()));
+        }
+        if self.group_ancestors(parent).contains(&id) {
+            return Err(InternalDaoActionError(
+                "parent group creates a cycle".into(),
+            ));
+        }
+        Ok(())
     }
     pub fn get_group_members_with_role(
         &self,
@@ -183,7 +464,7 @@ impl Contract {
         }
         let mut result_members = vec![];
         for member in group_members {
-            let member_roles = self.user_roles.get(&member);
+            let member_roles = self.user_roles.get(&member).map(UserRoles::from);
             if let Some(roles) = member_roles {
                 if roles.has_group_role(group_id, role_id) {
                     result_members.push(member);
@@ -206,12 +487,14 @@ impl Contract {
         members: Vec<GroupMember>,
         member_roles: Vec<MemberRoles>,
     ) -> bool {
-        if let Some(mut group) = self.groups.get(&id) {
-            let mut account_roles_cache: HashMap<AccountId, Vec<u16>> = HashMap::new();
+        if let Some(mut group) = self.groups.get(&id).map(Group::from) {
+            let mut account_roles_cache: HashMap<AccountId, Vec<(RoleId, Option<TimestampSec>)>> =
+                HashMap::new();
             if !member_roles.is_empty() {
                 let mut group_roles = self
                     .group_roles
                     .get(&id)
+                    .map(Roles::from)
                     .expect("fatal - group roles not found");
                 for role in member_roles {
                     let role_id =
@@ -222,13 +505,13 @@ impl Contract {
                         };
                     for account_id in role.members {
                         if let Some(roles) = account_roles_cache.get_mut(&account_id) {
-                            roles.push(role_id);
+                            roles.push((role_id, role.valid_to));
                         } else {
-                            account_roles_cache.insert(account_id, vec![role_id]);
+                            account_roles_cache.insert(account_id, vec![(role_id, role.valid_to)]);
                         }
                     }
                 }
-                self.group_roles.insert(&id, &group_roles);
+                self.group_roles.insert(&id, &group_roles.into());
             }
             let current_timestamp = current_timestamp_sec();
             let group_rewards = group.group_reward_ids();
@@ -237,15 +520,22 @@ impl Contract {
                 .map(|(r, _)| (r, self.rewards.get(&r).unwrap().into()))
                 .collect();
             for member in members.into_iter() {
-                let mut user_roles = self.user_roles.get(&member.account_id).unwrap_or_default();
+                let member_rewards = Self::filter_tagged_rewards(&rewards, &member.tags);
+                let mut user_roles = self
+                    .user_roles
+                    .get(&member.account_id)
+                    .map(UserRoles::from)
+                    .unwrap_or_default();
                 user_roles.add_group_role(id, 0);
+                user_roles.set_group_role_valid_to(id, 0, member.valid_to);
                 if let Some(roles) = account_roles_cache.remove(&member.account_id) {
-                    for role_id in roles {
+                    for (role_id, valid_to) in roles {
                         user_roles.add_group_role(id, role_id);
+                        user_roles.set_group_role_valid_to(id, role_id, valid_to);
                     }
                 }
                 self.save_user_roles(&member.account_id, &user_roles);
-                self.add_wallet_rewards(&member.account_id, rewards.clone(), current_timestamp);
+                self.add_wallet_rewards(&member.account_id, member_rewards, current_timestamp);
                 group.add_member(member);
             }
             for (account_id, roles) in account_roles_cache.into_iter() {
@@ -253,22 +543,110 @@ impl Contract {
                     let mut user_roles = self
                         .user_roles
                         .get(&account_id)
+                        .map(UserRoles::from)
                         .expect("fatal - user roles not found");
-                    for role_id in roles {
+                    for (role_id, valid_to) in roles {
                         user_roles.add_group_role(id, role_id);
+                        user_roles.set_group_role_valid_to(id, role_id, valid_to);
                     }
                     self.save_user_roles(&account_id, &user_roles);
-                    self.add_wallet_rewards(&account_id, rewards.clone(), current_timestamp);
+                    let member_rewards = Self::filter_tagged_rewards(
+                        &rewards,
+                        group
+                            .members
+                            .get(&account_id)
+                            .expect("fatal - member not found"),
+                    );
+                    self.add_wallet_rewards(&account_id, member_rewards, current_timestamp);
                 }
             }
-            self.groups.insert(&id, &group);
+            self.groups.insert(&id, &group.into());
             true
         } else {
             false
         }
     }
+    /// Return rewards not targeted by any tag or targeted by one of the `tags`.
+    fn filter_tagged_rewards(
+        rewards: &[(RewardId, Reward)],
+        tags: &[TagId],
+    ) -> Vec<(RewardId, Reward)> {
+        rewards
+            .iter()
+            .filter(|(_, reward)| reward.tag_id == 0 || tags.contains(&reward.tag_id))
+            .cloned()
+            .collect()
+    }
+    /// Add tags to the group member.
+    /// All tags must be defined in the group tag category.
+    /// Member gets assigned all existing group rewards targeted by the added tags and the member's roles.
+    pub fn group_add_member_tags(
+        &mut self,
+        id: GroupId,
+        account_id: &AccountId,
+        tags: Vec<TagId>,
+    ) -> Result<(), InternalDaoActionError> {
+        let mut group = self
+            .groups
+            .get(&id)
+            .map(Group::from)
+            .ok_or_else(|| InternalDaoActionError("group not found".into()))?;
+        if !group.is_member(account_id) {
+            return Err(InternalDaoActionError("account is not group member".into()));
+        }
+        if !self.group_tags_exist(&tags) {
+            return Err(InternalDaoActionError("tag not found".into()));
+        }
+        let added = group.add_member_tags(account_id, tags);
+        let user_roles = self
+            .user_roles
+            .get(account_id)
+            .map(UserRoles::from)
+            .unwrap_or_default();
+        let rewards: Vec<(RewardId, Reward)> = group
+            .group_reward_ids()
+            .into_iter()
+            .map(|(r, _)| (r, Reward::from(self.rewards.get(&r).unwrap())))
+            .filter(|(_, reward)| {
+                added.contains(&reward.tag_id)
+                    && (reward.role_id == 0 || user_roles.has_group_role(id, reward.role_id))
+            })
+            .collect();
+        self.add_wallet_rewards(account_id, rewards, current_timestamp_sec());
+        self.groups.insert(&id, &group.into());
+        Ok(())
+    }
+    /// Remove tags from the group member.
+    /// Include removal of rewards targeted by the removed tags.
+    pub fn group_remove_member_tags(
+        &mut self,
+        id: GroupId,
+        account_id: &AccountId,
+        tags: Vec<TagId>,
+    ) -> Result<(), InternalDaoActionError> {
+        let mut group = self
+            .groups
+            .get(&id)
+            .map(Group::from)
+            .ok_or_else(|| InternalDaoActionError("group not found".into()))?;
+        if !group.members.contains_key(account_id) {
+            return Err(InternalDaoActionError("account is not group member".into()));
+        }
+        let removed = group.remove_member_tags(account_id, tags);
+        let rewards: Vec<(RewardId, RoleId)> = group
+            .group_reward_ids()
+            .into_iter()
+            .filter(|(r, _)| {
+                let reward: Reward = self.rewards.get(r).unwrap().into();
+                removed.contains(&reward.tag_id)
+            })
+            .collect();
+        self.remove_wallet_reward(account_id, rewards.as_slice(), current_timestamp_sec());
+        self.groups.insert(&id, &group.into());
+        Ok(())
+    }
     pub fn group_remove_members(&mut self, id: GroupId, account_ids: Vec<AccountId>) -> bool {
-        if let Some(mut group) = self.groups.get(&id) {
+        if let Some(mut group) = self.groups.get(&id).map(Group::from) {
             let rewards: Vec<(u16, u16)> = group.group_reward_ids();
             let current_timestamp = current_timestamp_sec();
             for account_id in account_ids.into_iter() {
@@ -277,7 +655,7 @@ impl Contract {
                     self.remove_wallet_reward(&account_id, rewards.as_slice(), current_timestamp);
                 };
             }
-            self.groups.insert(&id, &group);
+            self.groups.insert(&id, &group.into());
             true
         } else {
             false
@@ -287,10 +665,11 @@ impl Contract {
     /// Include removal of rewards for the removed roles.
     /// Role id with value == 0 is ignored.
     pub fn group_remove_roles(&mut self, id: GroupId, roles: Vec<RoleId>) -> bool {
-        if let Some(mut group) = self.groups.get(&id) {
+        if let Some(mut group) = self.groups.get(&id).map(Group::from) {
             let mut group_roles = self
                 .group_roles
                 .get(&id)
+                .map(Roles::from)
                 .expect("fatal - group roles not found");
             let rewards: Vec<(u16, u16)> = group.group_reward_ids();
             let current_timestamp = current_timestamp_sec();
@@ -318,6 +697,7 @@ impl Contract {
                             let mut user_role = self
                                 .user_roles
                                 .get(&account_id)
+                                .map(UserRoles::from)
                                 .expect("fatal - user roles not found");
                             user_role.remove_group_role(id, role_id);
                             user_cache.insert(account_id, (user_role, rewards_to_remove.clone()));
@@ -333,8 +713,8 @@ impl Contract {
                     current_timestamp,
                 );
             }
-            self.groups.insert(&id, &group);
-            self.group_roles.insert(&id, &group_roles);
+            self.groups.insert(&id, &group.into());
+            self.group_roles.insert(&id, &group_roles.into());
             true
         } else {
             false
@@ -347,10 +727,11 @@ impl Contract {
         id: GroupId,
         member_roles: Vec<MemberRoles>,
     ) -> bool {
-        if let Some(group) = self.groups.get(&id) {
+        if let Some(group) = self.groups.get(&id).map(Group::from) {
             let group_roles = self
                 .group_roles
                 .get(&id)
+                .map(Roles::from)
                 .expect("fatal - group roles not found");
             let rewards: Vec<(u16, u16)> = group.group_reward_ids();
             let current_timestamp = current_timestamp_sec();
@@ -370,6 +751,7 @@ impl Contract {
                             let mut user_role = self
                                 .user_roles
                                 .get(&account_id)
+                                .map(UserRoles::from)
                                 .expect("fatal - user roles not found");
                             user_role.remove_group_role(id, role_id);
                             user_cache.insert(account_id, (user_role, rewards_to_remove.clone()));
@@ -390,8 +772,132 @@ impl Contract {
             false
         }
     }
-    pub fn group_remove(&mut self, id: GroupId) {
-        if let Some(group) = self.groups.get(&id) {
+    /// Add new role to the group.
+    /// Role name must be unique in the group. Empty metadata is not stored.
+    pub fn group_add_role(
+        &mut self,
+        id: GroupId,
+        name: String,
+        metadata: RoleMetadata,
+    ) -> Result<RoleId, InternalDaoActionError> {
+        let mut group_roles = self
+            .group_roles
+            .get(&id)
+            .map(Roles::from)
+            .ok_or_else(|| InternalDaoActionError("group not found".into()))?;
+        if group_roles.find_role_by_name(name.as_str()).is_some() {
+            return Err(InternalDaoActionError("role already exists".into()));
+        }
+        let role_id = group_roles
+            .insert(name)
+            .ok_or_else(|| InternalDaoActionError("empty role name".into()))?;
+        group_roles.set_metadata(role_id, metadata);
+        self.group_roles.insert(&id, &group_roles.into());
+        Ok(role_id)
+    }
+    /// Rename existing role in the group.
+    /// Role name must be unique in the group.
+    pub fn group_rename_role(
+        &mut self,
+        id: GroupId,
+        role_id: RoleId,
+        name: String,
+    ) -> Result<(), InternalDaoActionError> {
+        let mut group_roles = self
+            .group_roles
+            .get(&id)
+            .map(Roles::from)
+            .ok_or_else(|| InternalDaoActionError("group not found".into()))?;
+        if group_roles.get(role_id).is_none() {
+            return Err(InternalDaoActionError("role not found".into()));
+        }
+        if name.is_empty() {
+            return Err(InternalDaoActionError("empty role name".into()));
+        }
+        if group_roles
+            .find_role_by_name(name.as_str())
+            .map(|other_id| other_id != role_id)
+            .unwrap_or_default()
+        {
+            return Err(InternalDaoActionError("role already exists".into()));
+        }
+        group_roles.rename(role_id, name);
+        self.group_roles.insert(&id, &group_roles.into());
+        Ok(())
+    }
+    /// Replace metadata of existing role in the group.
+    /// Empty metadata removes the stored one.
+    pub fn group_describe_role(
+        &mut self,
+        id: GroupId,
+        role_id: RoleId,
+        metadata: RoleMetadata,
+    ) -> Result<(), InternalDaoActionError> {
+        let mut group_roles = self
+            .group_roles
+            .get(&id)
+            .map(Roles::from)
+            .ok_or_else(|| InternalDaoActionError("group not found".into()))?;
+        if !group_roles.set_metadata(role_id, metadata) {
+            return Err(InternalDaoActionError("role not found".into()));
+        }
+        self.group_roles.insert(&id, &group_roles.into());
+        Ok(())
+    }
+    /// Replace settings of the group.
+    /// New leader must be the group member and parent group must exist and must not create a cycle.
+    pub fn group_update(
+        &mut self,
+        id: GroupId,
+        settings: GroupSettings,
+    ) -> Result<(), InternalDaoActionError> {
+        let mut group = self
+            .groups
+            .get(&id)
+            .map(Group::from)
+            .ok_or_else(|| InternalDaoActionError("group not found".into()))?;
+        if let Some(ref leader) = settings.leader {
+            if !group.is_member(leader) {
+                return Err(InternalDaoActionError(
+                    "leader must be contained in group members".into(),
+                ));
+            }
+        }
+        self.assert_valid_parent_group(id, settings.parent_group)?;
+        group.settings = settings;
+        self.groups.insert(&id, &group.into());
+        Ok(())
+    }
+    /// Set leader of the group.
+    /// New leader must be the group member.
+    pub fn group_set_leader(
+        &mut self,
+        id: GroupId,
+        leader: Option<AccountId>,
+    ) -> Result<(), InternalDaoActionError> {
+        let mut group = self
+            .groups
+            .get(&id)
+            .map(Group::from)
+            .ok_or_else(|| InternalDaoActionError("group not found".into()))?;
+        if let Some(ref leader) = leader {
+            if !group.is_member(leader) {
+                return Err(InternalDaoActionError(
+                    "leader must be contained in group members".into(),
+                ));
+            }
+        }
+        group.settings.leader = leader;
+        self.groups.insert(&id, &group.into());
+        Ok(())
+    }
+    /// Remove the group with its roles.
+    /// Groups with child groups cannot be removed.
+    pub fn group_remove(&mut self, id: GroupId) -> Result<(), InternalDaoActionError> {
+        if !self.group_children(id).is_empty() {
+            return Err(InternalDaoActionError("group has child groups".into()));
+        }
+        if let Some(group) = self.groups.get(&id).map(Group::from) {
             let rewards: Vec<(u16, u16)> = group.group_reward_ids();
             let current_timestamp = current_timestamp_sec();
             for account_id in group.get_members_accounts_refs() {
@@ -401,6 +907,7 @@ impl Contract {
             self.group_roles.remove(&id);
             self.groups.remove(&id);
         }
+        Ok(())
     }
     /// Add group roles to the group and user roles to its members
     /// when group is created.
@@ -412,7 +919,11 @@ impl Contract {
     ) {
         let mut cache = HashMap::with_capacity(group_members.len());
         for member in group_members.iter() {
-            let user_roles = self.user_roles.get(&member.account_id).unwrap_or_default();
+            let user_roles = self
+                .user_roles
+                .get(&member.account_id)
+                .map(UserRoles::from)
+                .unwrap_or_default();
             cache.insert(&member.account_id, user_roles);
         }
         let mut group_roles = Roles::new();
@@ -421,13 +932,22 @@ impl Contract {
                 for member in member_role.members {
                     let user_roles = cache.get_mut(&member).expect("user roles do not match");
                     user_roles.add_group_role(self.group_last_id, role_id);
+                    user_roles.set_group_role_valid_to(
+                        self.group_last_id,
+                        role_id,
+                        member_role.valid_to,
+                    );
                 }
             }
         }
-        for (account, mut roles) in cache.into_iter() {
-            roles.add_group_role(group_id, 0);
-            self.save_user_roles(account, &roles);
+        for member in group_members.iter() {
+            if let Some(mut roles) = cache.remove(&member.account_id) {
+                roles.add_group_role(group_id, 0);
+                roles.set_group_role_valid_to(group_id, 0, member.valid_to);
+                self.save_user_roles(&member.account_id, &roles);
+            }
         }
-        self.group_roles.insert(&self.group_last_id, &group_roles);
+        self.group_roles
+            .insert(&self.group_last_id, &group_roles.into());
     }
 }
@@ -1,7 +1,10 @@
 use near_sdk::{env, require, AccountId, Balance};
 
 use crate::{
-    constants::{C_CURRENT_TIMESTAMP_SECS, C_DAO_ID, C_PREDECESSOR, LATEST_REWARD_ACTIVITY_ID},
+    constants::{
+        C_CURRENT_TIMESTAMP_SECS, C_DAO_ID, C_PREDECESSOR, LATEST_REWARD_ACTIVITY_ID,
+        VOTE_TOKEN_ASSET_ID,
+    },
     contract::{ActionLog, Contract},
     group::GroupInput,
     internal::utils::current_timestamp_sec,
@@ -50,7 +53,7 @@ impl Contract {
     #[inline]
     pub fn init_groups(&mut self, groups: Vec<GroupInput>) {
         for g in groups.into_iter() {
-            self.group_add(g);
+            self.group_add(g).expect("invalid group");
         }
     }
 
@@ -132,7 +135,7 @@ impl Contract {
     pub fn init_asset_cache(&mut self, vote_token: AccountId, decimals: u8) {
         self.cache_assets.insert(&0, &Asset::Near);
         self.cache_assets
-            .insert(&1, &Asset::new_ft(vote_token, decimals));
+            .insert(&VOTE_TOKEN_ASSET_ID, &Asset::new_ft(vote_token, decimals));
     }
 
     pub fn get_workflow_and_proposal(&self, proposal_id: u32) -> ProposalWf {
@@ -191,7 +194,7 @@ pub mod utils {
     use library::functions::utils::{
         into_storage_key_wrapper_str, into_storage_key_wrapper_u16, StorageKeyWrapper,
     };
-    use near_sdk::env;
+    use near_sdk::{env, require, Balance, Promise, StorageUsage};
 
     use crate::{
         constants::{GROUP_RELEASE_PREFIX, STORAGE_BUCKET_PREFIX},
@@ -209,6 +212,25 @@ pub mod utils {
     pub fn current_timestamp_sec() -> TimestampSec {
         env::block_timestamp() / 10u64.pow(9)
     }
+
+    /// Cost of storage used since `storage_before`.
+    pub fn storage_cost(storage_before: StorageUsage) -> Balance {
+        env::storage_usage().saturating_sub(storage_before) as Balance * env::storage_byte_cost()
+    }
+
+    /// Require attached deposit to cover storage used since `storage_before`.
+    /// Refund the rest of the deposit to the caller.
+    pub fn charge_storage_deposit(storage_before: StorageUsage) {
+        let storage_cost = storage_cost(storage_before);
+        let deposit = env::attached_deposit();
+        require!(
+            deposit >= storage_cost,
+            "Not enough deposit to cover storage."
+        );
+        if deposit > storage_cost {
+            Promise::new(env::predecessor_account_id()).transfer(deposit - storage_cost);
+        }
+    }
 }
 
 #[derive(Default)]
@@ -49,4 +49,7 @@ impl Contract {
             self.media.insert(&id, media);
         }
     }
+    pub fn media_remove(&mut self, id: u32) -> Option<Media> {
+        self.media.remove(&id)
+    }
 }
@@ -1,10 +1,10 @@
+use library::derive_into_versioned;
 use library::functions::utils::calculate_percent_u128;
 use library::workflow::action::InputSource;
 use library::workflow::instance::Instance;
 use library::workflow::settings::{ProposeSettings, TemplateSettings};
 use library::workflow::template::Template;
 use library::workflow::types::{ActivityRight, VoteScenario};
-use library::{derive_from_versioned, derive_into_versioned};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::env::panic_str;
 use near_sdk::json_types::U128;
@@ -12,11 +12,14 @@ use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env, log, near_bindgen, require, AccountId};
 use std::collections::HashMap;
 
-use crate::internal::utils::current_timestamp_sec;
+use crate::constants::MAX_PROPOSAL_MEDIA;
+use crate::group::Group;
+use crate::internal::utils::{charge_storage_deposit, current_timestamp_sec};
 use crate::media::Media;
 use crate::reward::RewardActivity;
+use crate::role::UserRoles;
 use crate::{contract::*, CalculatedVoteResults, VoteTotalPossible, Votes};
-use crate::{ResourceId, TimestampSec};
+use crate::{ProposalId, ResourceId, TimestampSec};
 
 pub const PROPOSAL_DESC_MAX_LENGTH: usize = 256;
 
@@ -25,11 +28,21 @@ pub const PROPOSAL_DESC_MAX_LENGTH: usize = 256;
 #[serde(crate = "near_sdk::serde")]
 #[serde(rename_all = "lowercase")]
 pub enum VersionedProposal {
-    V1(Proposal),
+    V1(ProposalV1),
+    V2(Proposal),
 }
 
-derive_into_versioned!(Proposal, VersionedProposal, V1);
-derive_from_versioned!(VersionedProposal, Proposal, V1);
+derive_into_versioned!(Proposal, VersionedProposal, V2);
+
+/// Proposals stored as `V1` are converted when read and saved as `V2` on next write.
+impl From<VersionedProposal> for Proposal {
+    fn from(input: VersionedProposal) -> Self {
+        match input {
+            VersionedProposal::V1(p) => p.into(),
+            VersionedProposal::V2(p) => p,
+        }
+    }
+}
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone, Copy)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
@@ -71,6 +84,45 @@ pub struct Proposal {
     pub workflow_id: u16,
     pub workflow_settings_id: u8,
     pub voting_results: Vec<U128>,
+    /// Id of the parent proposal in case this proposal was created by `SubWorkflow` action.
+    pub parent: Option<ProposalId>,
+    /// Ids of all media of the proposal in order they were added.
+    /// Contains all description versions and attachments.
+    pub media: Vec<ResourceId>,
+}
+
+/// Proposal layout before sub workflows and proposal media were introduced.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalV1 {
+    pub desc: ResourceId,
+    pub created: TimestampSec,
+    pub created_by: AccountId,
+    pub end: TimestampSec,
+    pub votes: HashMap<AccountId, u8>,
+    pub state: ProposalState,
+    pub workflow_id: u16,
+    pub workflow_settings_id: u8,
+    pub voting_results: Vec<U128>,
+}
+
+impl From<ProposalV1> for Proposal {
+    fn from(p: ProposalV1) -> Self {
+        Proposal {
+            desc: p.desc,
+            created: p.created,
+            created_by: p.created_by,
+            end: p.end,
+            votes: p.votes,
+            state: p.state,
+            workflow_id: p.workflow_id,
+            workflow_settings_id: p.workflow_settings_id,
+            voting_results: p.voting_results,
+            parent: None,
+            media: if p.desc > 0 { vec![p.desc] } else { vec![] },
+        }
+    }
 }
 
 impl Proposal {
@@ -93,8 +145,15 @@ impl Proposal {
             workflow_id,
             workflow_settings_id,
             voting_results: vec![],
+            parent: None,
+            media: vec![],
         }
     }
+    /// Return true if the proposal content can still be changed by its proposer.
+    /// Content is frozen once anyone votes.
+    pub fn is_amendable(&self) -> bool {
+        self.state == ProposalState::InProgress && self.votes.is_empty()
+    }
 }
 
 #[near_bindgen]
@@ -147,6 +206,10 @@ impl Contract {
                 "scenario: `TokenWeighted` + allowed_voters: `Members` is not supported yet.",
             );
         }
+        require!(
+            !settings.allowed_voters.is_composite() || settings.allowed_voters.is_enumerable(),
+            "allowed_voters: composite right must be resolvable to group members."
+        );
         self.proposal_last_id += 1;
         if is_wf_add_scenario(&wft, &propose_settings) {
             require!(
@@ -159,15 +222,25 @@ impl Contract {
             self.proposed_workflow_settings
                 .insert(&self.proposal_last_id, &template_settings.unwrap());
         }
+        let desc = description
+            .map(|mut media| {
+                media.proposal_id = Some(self.proposal_last_id);
+                media.version = "1".into();
+                self.media_add(&media)
+            })
+            .unwrap_or_default();
         let created = env::block_timestamp() / 10u64.pow(9) / 60 * 60 + 60;
-        let proposal = Proposal::new(
-            0,
+        let mut proposal = Proposal::new(
+            desc,
             created,
             caller,
             created + settings.duration as u64,
             template_id,
             template_settings_id,
         );
+        if desc > 0 {
+            proposal.media.push(desc);
+        }
         if wft.need_storage {
             if let Some(ref key) = propose_settings.storage_key {
                 require!(
@@ -182,13 +255,58 @@ impl Contract {
             .insert(&self.proposal_last_id, &proposal.into());
         self.workflow_propose_settings
             .insert(&self.proposal_last_id, &propose_settings);
-        if let Some(mut media) = description {
-            media.proposal_id = Some(self.proposal_last_id);
-            self.media_add(&media);
-        }
         self.proposal_last_id
     }
 
+    /// Replace description of the proposal with a new version.
+    /// Previous version is kept in the proposal media history and marked as not valid.
+    /// Version of the new description is the previous version incremented by one.
+    /// Panics if:
+    /// - caller is not the proposer
+    /// - proposal is not in progress or was already voted on
+    /// - proposal has max number of media
+    /// - attached deposit does not cover added storage
+    #[payable]
+    pub fn proposal_amend(&mut self, id: u32, description: Media) -> ResourceId {
+        let storage_before = env::storage_usage();
+        let mut proposal = self.get_amendable_proposal(id);
+        let mut version = 1;
+        if let Some(mut previous) = self.media.get(&proposal.desc) {
+            version = previous.version.parse::<u32>().unwrap_or(1) + 1;
+            previous.valid = false;
+            self.media_update(proposal.desc, &previous);
+        }
+        let mut media = description;
+        media.proposal_id = Some(id);
+        media.version = version.to_string();
+        media.valid = true;
+        let media_id = self.media_add(&media);
+        proposal.desc = media_id;
+        proposal.media.push(media_id);
+        self.proposals.insert(&id, &proposal.into());
+        charge_storage_deposit(storage_before);
+        media_id
+    }
+
+    /// Attach additional media to the proposal, eg. amendments, CIDs or links.
+    /// Panics if:
+    /// - caller is not the proposer
+    /// - proposal is not in progress or was already voted on
+    /// - proposal has max number of media
+    /// - attached deposit does not cover added storage
+    #[payable]
+    pub fn proposal_attach_media(&mut self, id: u32, media: Media) -> ResourceId {
+        let storage_before = env::storage_usage();
+        let mut proposal = self.get_amendable_proposal(id);
+        let mut media = media;
+        media.proposal_id = Some(id);
+        let media_id = self.media_add(&media);
+        proposal.media.push(media_id);
+        self.proposals.insert(&id, &proposal.into());
+        charge_storage_deposit(storage_before);
+        media_id
+    }
+
     #[payable]
     pub fn proposal_vote(&mut self, id: u32, vote: u8) -> VoteResult {
         if vote > 2 {
@@ -219,7 +337,7 @@ impl Contract {
         }
         self.register_executed_activity(&caller, RewardActivity::Vote.into());
         proposal.votes.insert(caller, vote);
-        self.proposals.insert(&id, &VersionedProposal::V1(proposal));
+        self.proposals.insert(&id, &proposal.into());
         VoteResult::Ok
     }
 
@@ -247,6 +365,12 @@ impl Contract {
                         &proposal.created_by,
                         RewardActivity::AcceptedProposal.into(),
                     );
+                    // Parent workflow cannot continue with the child which was not accepted.
+                    if !matches!(result_state, ProposalState::Accepted) {
+                        if let Some(parent_id) = proposal.parent.take() {
+                            self.sub_workflow_resolve(parent_id, id, false);
+                        }
+                    }
                     Some((result_state, vote_results))
                 }
             }
@@ -275,6 +399,23 @@ impl Contract {
 }
 
 impl Contract {
+    /// Return proposal which content can be changed by the caller.
+    fn get_amendable_proposal(&self, id: u32) -> Proposal {
+        let proposal = Proposal::from(self.proposals.get(&id).expect("Unknown proposal"));
+        require!(
+            proposal.created_by == env::predecessor_account_id(),
+            "Only proposer can change the proposal."
+        );
+        require!(
+            proposal.is_amendable(),
+            "Proposal cannot be changed after voting started."
+        );
+        require!(
+            proposal.media.len() < MAX_PROPOSAL_MEDIA,
+            "Proposal has max number of media."
+        );
+        proposal
+    }
     /// Evaluate vote results by scenario and type of voters.
     /// Return tuple CalculatedVoteResults.
     /// Scenario: TokenWeighted + Voters: Members is not implemented!
@@ -286,13 +427,29 @@ impl Contract {
     ) -> CalculatedVoteResults {
         let mut vote_result: Votes = [0_u128; 3];
         let mut max_possible_amount: VoteTotalPossible = 0;
+        if vote_target.is_composite() || matches!(vote_target, ActivityRight::ParentOf(_)) {
+            let voters = self
+                .right_accounts(vote_target)
+                .unwrap_or_else(|| panic_str("voters cannot be resolved"));
+            for voter in voters {
+                let weight = match scenario {
+                    VoteScenario::Democratic => 1,
+                    VoteScenario::TokenWeighted => self.delegations.get(&voter).unwrap_or(0),
+                };
+                max_possible_amount += weight;
+                if let Some(vote_value) = votes.get(&voter) {
+                    vote_result[*vote_value as usize] += weight;
+                }
+            }
+            return (max_possible_amount, vote_result);
+        }
         match scenario {
             VoteScenario::Democratic => {
                 match vote_target {
                     ActivityRight::Anyone => {
                         max_possible_amount = votes.len() as u128;
                     }
-                    ActivityRight::Group(g) => match self.groups.get(g) {
+                    ActivityRight::Group(g) => match self.groups.get(g).map(Group::from) {
                         Some(group) => {
                             max_possible_amount = group.members_count() as u128;
                         }
@@ -307,20 +464,31 @@ impl Contract {
                         max_possible_amount = self.total_delegators_count as u128;
                     }
                     ActivityRight::Member => {
-                        max_possible_amount = self.total_members_count as u128;
+                        max_possible_amount = self.active_members_count() as u128;
                     }
-                    ActivityRight::GroupRole(g, r) => match self.groups.get(g) {
+                    ActivityRight::GroupRole(g, r) => match self.groups.get(g).map(Group::from) {
                         Some(group) => {
                             max_possible_amount =
                                 self.get_group_members_with_role(*g, &group, *r).len() as u128;
                         }
                         None => panic_str("group not found"),
                     },
+                    ActivityRight::All(_)
+                    | ActivityRight::Not(_)
+                    | ActivityRight::MinStake(_)
+                    | ActivityRight::MinTagged(_, _)
+                    | ActivityRight::ParentOf(_) => unreachable!(),
                 }
 
                 if matches!(vote_target, ActivityRight::Member) {
                     for (voter, vote_value) in votes.iter() {
-                        if self.user_roles.get(voter).is_some() {
+                        if self
+                            .user_roles
+                            .get(voter)
+                            .map(UserRoles::from)
+                            .map(|roles| roles.is_active())
+                            .unwrap_or_default()
+                        {
                             vote_result[*vote_value as usize] += 1;
                         }
                     }
@@ -338,11 +506,16 @@ impl Contract {
                             self.delegations.get(voter).unwrap_or(0);
                     }
                 }
-                ActivityRight::Member => {
+                ActivityRight::Member
+                | ActivityRight::All(_)
+                | ActivityRight::Not(_)
+                | ActivityRight::MinStake(_)
+                | ActivityRight::MinTagged(_, _)
+                | ActivityRight::ParentOf(_) => {
                     unreachable!()
                 }
                 ActivityRight::Group(g) => {
-                    match self.groups.get(g) {
+                    match self.groups.get(g).map(Group::from) {
                         Some(group) => {
                             let members = group.get_members_accounts();
                             for member in members {
@@ -358,13 +531,14 @@ impl Contract {
                 }
                 // Expensive scenario.
                 ActivityRight::GroupRole(g, r) => {
-                    match self.groups.get(g) {
+                    match self.groups.get(g).map(Group::from) {
                         Some(group) => {
                             let members = group.get_members_accounts();
                             for member in members {
                                 let member_vote_weight = self.delegations.get(&member).unwrap_or(0);
                                 // Group member always has role record, therefore unwraping is ok.
-                                let member_roles = self.user_roles.get(&member).unwrap();
+                                let member_roles =
+                                    self.user_roles.get(&member).map(UserRoles::from).unwrap();
                                 if member_roles.has_group_role(*g, *r) {
                                     max_possible_amount += member_vote_weight;
                                     if let Some(vote_value) = votes.get(&member) {
@@ -377,7 +551,7 @@ impl Contract {
                     };
                 }
                 ActivityRight::GroupMember(g, account_id) => {
-                    match self.groups.get(g) {
+                    match self.groups.get(g).map(Group::from) {
                         Some(group) => {
                             if group.is_member(account_id) {
                                 let member_vote_weight =
@@ -399,7 +573,7 @@ impl Contract {
                     }
                 }
                 ActivityRight::GroupLeader(g) => {
-                    match self.groups.get(g) {
+                    match self.groups.get(g).map(Group::from) {
                         Some(group) => {
                             if let Some(leader) = group.group_leader() {
                                 let member_vote_weight = self.delegations.get(leader).unwrap_or(0);
@@ -417,6 +591,77 @@ impl Contract {
         (max_possible_amount, vote_result)
     }
 
+    /// Returns accounts which have the right.
+    /// Returns `None` if the accounts cannot be resolved from groups.
+    pub fn right_accounts(&self, right: &ActivityRight) -> Option<Vec<AccountId>> {
+        let accounts = match right {
+            ActivityRight::Group(g) => self
+                .groups
+                .get(g)
+                .map(Group::from)
+                .map(|group| group.get_members_accounts())
+                .unwrap_or_default(),
+            ActivityRight::GroupMember(_, account_id) | ActivityRight::Account(account_id) => {
+                if self.check_right(right, account_id) {
+                    vec![account_id.to_owned()]
+                } else {
+                    vec![]
+                }
+            }
+            ActivityRight::GroupRole(g, r) => self
+                .groups
+                .get(g)
+                .map(Group::from)
+                .map(|group| self.get_group_members_with_role(*g, &group, *r))
+                .unwrap_or_default(),
+            ActivityRight::GroupLeader(g) => self
+                .groups
+                .get(g)
+                .map(Group::from)
+                .and_then(|group| group.group_leader().cloned())
+                .into_iter()
+                .collect(),
+            ActivityRight::MinTagged(g, t) => self
+                .groups
+                .get(g)
+                .map(Group::from)
+                .map(|group| group.get_members_with_tag(*t))
+                .unwrap_or_default(),
+            ActivityRight::ParentOf(g) => {
+                let mut accounts: Vec<AccountId> = self
+                    .group_ancestors(*g)
+                    .into_iter()
+                    .filter_map(|id| self.groups.get(&id).map(Group::from))
+                    .flat_map(|group| group.get_members_accounts())
+                    .collect();
+                accounts.sort();
+                accounts.dedup();
+                accounts
+            }
+            ActivityRight::All(rights) => {
+                let (idx, accounts) = rights
+                    .iter()
+                    .enumerate()
+                    .find_map(|(idx, right)| self.right_accounts(right).map(|a| (idx, a)))?;
+                accounts
+                    .into_iter()
+                    .filter(|account_id| {
+                        rights
+                            .iter()
+                            .enumerate()
+                            .all(|(i, right)| i == idx || self.check_right(right, account_id))
+                    })
+                    .collect()
+            }
+            ActivityRight::Anyone
+            | ActivityRight::TokenHolder
+            | ActivityRight::Member
+            | ActivityRight::Not(_)
+            | ActivityRight::MinStake(_) => return None,
+        };
+        Some(accounts)
+    }
+
     /// Evaluates proposal voting according to vote settings.
     pub fn eval_votes(
         &self,
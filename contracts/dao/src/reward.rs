@@ -1,21 +1,61 @@
-use library::{derive_from_versioned, derive_into_versioned};
+use library::derive_into_versioned;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::env::panic_str;
 use near_sdk::serde::Serialize;
 use near_sdk::AccountId;
 
+use crate::group::Group;
 use crate::internal::utils::current_timestamp_sec;
 use crate::wallet::Wallet;
 use crate::workflow::InternalDaoActionError;
 use crate::{contract::*, AssetId, RewardId, RoleId};
 use crate::{treasury::TreasuryPartition, TimestampSec};
 
-derive_into_versioned!(Reward, VersionedReward, V1);
-derive_from_versioned!(VersionedReward, Reward, V1);
+derive_into_versioned!(Reward, VersionedReward, V2);
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub enum VersionedReward {
-    V1(Reward),
+    V1(RewardV1),
+    V2(Reward),
+}
+
+/// Rewards stored as `V1` are converted when read and saved as `V2` on next write.
+impl From<VersionedReward> for Reward {
+    fn from(input: VersionedReward) -> Self {
+        match input {
+            VersionedReward::V1(r) => r.into(),
+            VersionedReward::V2(r) => r,
+        }
+    }
+}
+
+/// Reward without member tag filter.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct RewardV1 {
+    pub name: String,
+    pub group_id: u16,
+    pub role_id: u16,
+    pub partition_id: u16,
+    pub r#type: RewardType,
+    pub reward_amounts: Vec<(AssetId, u128)>,
+    pub time_valid_from: u64,
+    pub time_valid_to: u64,
+}
+
+impl From<RewardV1> for Reward {
+    fn from(input: RewardV1) -> Self {
+        Self {
+            name: input.name,
+            group_id: input.group_id,
+            role_id: input.role_id,
+            tag_id: 0,
+            partition_id: input.partition_id,
+            r#type: input.r#type,
+            reward_amounts: input.reward_amounts,
+            time_valid_from: input.time_valid_from,
+            time_valid_to: input.time_valid_to,
+        }
+    }
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
@@ -26,6 +66,9 @@ pub struct Reward {
     pub group_id: u16,
     /// Role id in the group.
     pub role_id: u16,
+    /// Tag id of the group members from the group tag category.
+    /// Zero means members are not filtered by tag.
+    pub tag_id: u16,
     /// Partition from which the assets are taken.
     /// Partition must have all defined assets.
     pub partition_id: u16,
@@ -47,6 +90,7 @@ impl Reward {
         name: String,
         group_id: u16,
         role_id: u16,
+        tag_id: u16,
         partition_id: u16,
         r#type: RewardType,
         reward_amounts: Vec<(AssetId, u128)>,
@@ -57,6 +101,7 @@ impl Reward {
             name,
             group_id,
             role_id,
+            tag_id,
             partition_id,
             r#type,
             reward_amounts,
@@ -249,12 +294,19 @@ impl Contract {
             let mut group = self
                 .groups
                 .get(&reward.group_id)
+                .map(Group::from)
                 .ok_or_else(|| InternalDaoActionError("group not found".into()))?;
-            let rewarded_users = if reward.role_id == 0 {
+            if reward.tag_id > 0 && !self.group_tags_exist(&[reward.tag_id]) {
+                return Err(InternalDaoActionError("tag not found".into()));
+            }
+            let mut rewarded_users = if reward.role_id == 0 {
                 group.get_members_accounts()
             } else {
                 self.get_group_members_with_role(reward.group_id, &group, reward.role_id)
             };
+            if reward.tag_id > 0 {
+                rewarded_users.retain(|user| group.is_member_tagged(user, reward.tag_id));
+            }
             let mut reward_assets: Vec<AssetId> = reward
                 .reward_amounts()
                 .iter()
@@ -277,7 +329,7 @@ impl Contract {
                 );
             }
             group.add_new_reward(self.reward_last_id, reward.role_id);
-            self.groups.insert(&reward.group_id, &group);
+            self.groups.insert(&reward.group_id, &group.into());
         } else if reward.get_reward_type() != RewardTypeIdent::UserActivity {
             return Err(InternalDaoActionError(
                 "only activity rewards can be defined for anyone".into(),
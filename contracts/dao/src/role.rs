@@ -1,12 +1,50 @@
-use std::collections::{hash_map::Iter, HashMap};
+use std::collections::{hash_map::Iter, HashMap, HashSet};
 
+use library::derive_into_versioned;
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
     serde::{Deserialize, Serialize},
     AccountId,
 };
 
-use crate::{contract::Contract, GroupId, RoleId};
+use crate::{
+    contract::Contract, group::Group, internal::utils::current_timestamp_sec, GroupId, RoleId,
+    TimestampSec,
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum VersionedRoles {
+    V1(RolesV1),
+    V2(Roles),
+}
+
+derive_into_versioned!(Roles, VersionedRoles, V2);
+
+impl From<VersionedRoles> for Roles {
+    fn from(input: VersionedRoles) -> Self {
+        match input {
+            VersionedRoles::V1(roles) => roles.into(),
+            VersionedRoles::V2(roles) => roles,
+        }
+    }
+}
+
+/// Group roles without role metadata.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct RolesV1 {
+    last_id: RoleId,
+    map: HashMap<RoleId, String>,
+}
+
+impl From<RolesV1> for Roles {
+    fn from(input: RolesV1) -> Self {
+        Self {
+            last_id: input.last_id,
+            map: input.map,
+            metadata: HashMap::new(),
+        }
+    }
+}
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq, Clone))]
@@ -14,6 +52,25 @@ use crate::{contract::Contract, GroupId, RoleId};
 pub struct Roles {
     last_id: RoleId,
     map: HashMap<RoleId, String>,
+    /// Optional metadata of the roles.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    metadata: HashMap<RoleId, RoleMetadata>,
+}
+
+/// Describes purpose of the role.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoleMetadata {
+    pub description: String,
+    /// Hints about permissions the role is meant to have. Not enforced by the DAO.
+    pub permissions: Vec<String>,
+}
+
+impl RoleMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.description.is_empty() && self.permissions.is_empty()
+    }
 }
 
 impl Roles {
@@ -21,6 +78,7 @@ impl Roles {
         Roles {
             last_id: 0,
             map: HashMap::new(),
+            metadata: HashMap::new(),
         }
     }
     /// Insert new role and returns assigned `RoleId`
@@ -38,6 +96,7 @@ impl Roles {
     /// Remove role with `id`.
     /// Return true if actually removed.
     pub fn remove(&mut self, id: RoleId) -> bool {
+        self.metadata.remove(&id);
         self.map.remove(&id).is_some()
     }
     pub fn rename(&mut self, id: RoleId, name: String) {
@@ -48,6 +107,22 @@ impl Roles {
     pub fn get(&self, id: RoleId) -> Option<&String> {
         self.map.get(&id)
     }
+    /// Set metadata of the existing role, empty metadata removes the stored one.
+    /// Return false if role does not exist.
+    pub fn set_metadata(&mut self, id: RoleId, metadata: RoleMetadata) -> bool {
+        if !self.map.contains_key(&id) {
+            return false;
+        }
+        if metadata.is_empty() {
+            self.metadata.remove(&id);
+        } else {
+            self.metadata.insert(id, metadata);
+        }
+        true
+    }
+    pub fn get_metadata(&self, id: RoleId) -> Option<&RoleMetadata> {
+        self.metadata.get(&id)
+    }
     pub fn iter(&self) -> Iter<RoleId, String> {
         self.map.iter()
     }
@@ -62,75 +137,226 @@ impl Roles {
     }
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Default)]
-#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq, Clone))]
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum VersionedUserRoles {
+    V1(UserRolesV1),
+    V2(UserRoles),
+}
+
+derive_into_versioned!(UserRoles, VersionedUserRoles, V2);
+
+impl From<VersionedUserRoles> for UserRoles {
+    fn from(input: VersionedUserRoles) -> Self {
+        match input {
+            VersionedUserRoles::V1(roles) => roles.into(),
+            VersionedUserRoles::V2(roles) => roles,
+        }
+    }
+}
+
+/// User roles without role expiration.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct UserRolesV1(HashMap<GroupId, Vec<RoleId>>);
+
+impl From<UserRolesV1> for UserRoles {
+    fn from(input: UserRolesV1) -> Self {
+        Self {
+            roles: input.0,
+            valid_to: HashMap::new(),
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Default, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 #[serde(crate = "near_sdk::serde")]
-pub struct UserRoles(HashMap<GroupId, Vec<RoleId>>);
+pub struct UserRoles {
+    #[serde(flatten)]
+    roles: HashMap<GroupId, Vec<RoleId>>,
+    /// Timestamps until which are the group roles valid.
+    /// Roles without record are valid forever.
+    /// Provided by `user_roles_valid_to` view.
+    #[serde(skip)]
+    valid_to: HashMap<GroupId, HashMap<RoleId, TimestampSec>>,
+}
 impl UserRoles {
     pub fn add_group_role(&mut self, group_id: GroupId, role_id: RoleId) {
-        if let Some(roles) = self.0.get_mut(&group_id) {
+        if let Some(roles) = self.roles.get_mut(&group_id) {
             if !roles.contains(&role_id) {
                 roles.push(role_id);
             }
         } else {
             let roles = vec![role_id];
-            self.0.insert(group_id, roles);
+            self.roles.insert(group_id, roles);
+        }
+    }
+    /// Set timestamp until which is the group role valid.
+    /// `None` makes the role valid forever.
+    pub fn set_group_role_valid_to(
+        &mut self,
+        group_id: GroupId,
+        role_id: RoleId,
+        valid_to: Option<TimestampSec>,
+    ) {
+        match valid_to {
+            Some(valid_to) => {
+                self.valid_to
+                    .entry(group_id)
+                    .or_default()
+                    .insert(role_id, valid_to);
+            }
+            None => self.remove_group_role_valid_to(group_id, role_id),
+        }
+    }
+    fn remove_group_role_valid_to(&mut self, group_id: GroupId, role_id: RoleId) {
+        if let Some(terms) = self.valid_to.get_mut(&group_id) {
+            terms.remove(&role_id);
+            if terms.is_empty() {
+                self.valid_to.remove(&group_id);
+            }
         }
     }
     pub fn remove_group_role(&mut self, group_id: GroupId, role_id: RoleId) {
-        if let Some(roles) = self.0.get_mut(&group_id) {
+        if let Some(roles) = self.roles.get_mut(&group_id) {
             if let Some(pos) = roles.iter().position(|el| *el == role_id) {
                 roles.swap_remove(pos);
             }
         }
+        self.remove_group_role_valid_to(group_id, role_id);
     }
     pub fn remove_all_group_roles(&mut self, group_id: GroupId) {
-        self.0.remove(&group_id);
+        self.roles.remove(&group_id);
+        self.valid_to.remove(&group_id);
     }
+    /// Return true if the group role is assigned and not expired.
     pub fn has_group_role(&self, group_id: GroupId, role_id: RoleId) -> bool {
-        if let Some(roles) = self.0.get(&group_id) {
+        if let Some(roles) = self.roles.get(&group_id) {
             roles.contains(&role_id)
+                && !self.is_group_role_expired(group_id, role_id, current_timestamp_sec())
         } else {
             false
         }
     }
+    pub fn is_group_role_expired(
+        &self,
+        group_id: GroupId,
+        role_id: RoleId,
+        current_timestamp: TimestampSec,
+    ) -> bool {
+        self.valid_to
+            .get(&group_id)
+            .and_then(|terms| terms.get(&role_id))
+            .map(|valid_to| *valid_to <= current_timestamp)
+            .unwrap_or_default()
+    }
+    /// Return sorted ids of groups with any role assigned.
+    pub fn group_ids(&self) -> Vec<GroupId> {
+        let mut group_ids: Vec<GroupId> = self.roles.keys().copied().collect();
+        group_ids.sort_unstable();
+        group_ids
+    }
+    /// Return sorted not expired roles in the group.
+    pub fn group_roles(&self, group_id: GroupId) -> Vec<RoleId> {
+        let current_timestamp = current_timestamp_sec();
+        let mut roles: Vec<RoleId> = self
+            .roles
+            .get(&group_id)
+            .map(|roles| {
+                roles
+                    .iter()
+                    .filter(|role_id| {
+                        !self.is_group_role_expired(group_id, **role_id, current_timestamp)
+                    })
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default();
+        roles.sort_unstable();
+        roles
+    }
+    pub fn valid_to(&self) -> &HashMap<GroupId, HashMap<RoleId, TimestampSec>> {
+        &self.valid_to
+    }
+    /// Return true if user has at least one not expired role.
+    pub fn is_active(&self) -> bool {
+        let current_timestamp = current_timestamp_sec();
+        self.roles.iter().any(|(group_id, roles)| {
+            roles
+                .iter()
+                .any(|role_id| !self.is_group_role_expired(*group_id, *role_id, current_timestamp))
+        })
+    }
+    /// Remove expired roles in the group.
+    /// Return removed role ids.
+    pub fn remove_expired_group_roles(
+        &mut self,
+        group_id: GroupId,
+        current_timestamp: TimestampSec,
+    ) -> Vec<RoleId> {
+        let expired: Vec<RoleId> = self
+            .valid_to
+            .get(&group_id)
+            .map(|terms| {
+                terms
+                    .iter()
+                    .filter(|(_, valid_to)| **valid_to <= current_timestamp)
+                    .map(|(role_id, _)| *role_id)
+                    .collect()
+            })
+            .unwrap_or_default();
+        for role_id in expired.iter() {
+            self.remove_group_role(group_id, *role_id);
+        }
+        if self
+            .roles
+            .get(&group_id)
+            .map(|roles| roles.is_empty())
+            .unwrap_or_default()
+        {
+            self.roles.remove(&group_id);
+        }
+        expired
+    }
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.roles.is_empty()
     }
 }
 
 #[cfg(test)]
 impl UserRoles {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self::default()
     }
     pub fn sort(self) -> Self {
         let roles = self
-            .0
+            .roles
             .into_iter()
             .map(|(k, mut v)| {
                 v.sort();
                 (k, v)
             })
             .collect();
-        Self(roles)
+        Self {
+            roles,
+            valid_to: self.valid_to,
+        }
     }
     pub fn add_group_roles(mut self, group_id: u16, roles: Vec<u16>) -> Self {
-        self.0.insert(group_id, roles);
+        self.roles.insert(group_id, roles);
         self
     }
     pub fn add_role(mut self, group_id: u16, role_id: u16) -> Self {
-        if let Some(roles) = self.0.get_mut(&group_id) {
+        if let Some(roles) = self.roles.get_mut(&group_id) {
             if !roles.contains(&role_id) {
                 roles.push(role_id)
             }
         } else {
-            self.0.insert(group_id, vec![role_id]);
+            self.roles.insert(group_id, vec![role_id]);
         }
         self
     }
     pub fn remove_role(mut self, group_id: u16, role_id: u16) -> Self {
-        if let Some(roles) = self.0.get_mut(&group_id) {
+        if let Some(roles) = self.roles.get_mut(&group_id) {
             if let Some(pos) = roles.iter().position(|el| *el == role_id) {
                 roles.swap_remove(pos);
             }
@@ -145,6 +371,9 @@ impl UserRoles {
 pub struct MemberRoles {
     pub name: String,
     pub members: Vec<AccountId>,
+    /// Timestamp until which is the role valid for the members.
+    #[serde(default)]
+    pub valid_to: Option<TimestampSec>,
 }
 
 impl Contract {
@@ -155,7 +384,11 @@ impl Contract {
         if roles.is_empty() {
             self.user_roles.remove(account_id);
             self.total_members_count -= 1;
-        } else if self.user_roles.insert(account_id, roles).is_none() {
+        } else if self
+            .user_roles
+            .insert(account_id, &roles.clone().into())
+            .is_none()
+        {
             self.total_members_count += 1;
         }
     }
@@ -163,9 +396,26 @@ impl Contract {
     /// Save if not empty.
     /// Remove it from the contract othewise.
     pub fn remove_user_role_group(&mut self, account_id: &AccountId, group_id: u16) {
-        if let Some(mut roles) = self.user_roles.get(account_id) {
+        if let Some(roles) = self.user_roles.get(account_id) {
+            let mut roles = UserRoles::from(roles);
             roles.remove_all_group_roles(group_id);
             self.save_user_roles(account_id, &roles);
         }
     }
+    /// Return count of members with at least one not expired role.
+    pub fn active_members_count(&self) -> u32 {
+        let mut accounts = HashSet::new();
+        for group in self.groups.values() {
+            accounts.extend(Group::from(group).members.into_keys());
+        }
+        accounts
+            .iter()
+            .filter(|account_id| {
+                self.user_roles
+                    .get(account_id)
+                    .map(|roles| UserRoles::from(roles).is_active())
+                    .unwrap_or_default()
+            })
+            .count() as u32
+    }
 }
@@ -10,7 +10,7 @@ use near_sdk::{
     serde::{Deserialize, Serialize},
 };
 
-use crate::{contract::Contract, TagId};
+use crate::{constants::GROUP_TAG_CATEGORY, contract::Contract, TagId};
 
 #[derive(Deserialize, Serialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
@@ -157,6 +157,14 @@ impl Contract {
         }
     }
 
+    /// Return true if all `tags` are defined in the group tag category.
+    pub fn group_tags_exist(&self, tags: &[TagId]) -> bool {
+        self.tags
+            .get(&GROUP_TAG_CATEGORY.to_string())
+            .map(|t| tags.iter().all(|id| t.get(*id).is_some()))
+            .unwrap_or_default()
+    }
+
     pub fn tag_remove(&mut self, category: String, id: u16) -> bool {
         match self.tags.get(&category) {
             Some(mut t) => {
@@ -10,7 +10,12 @@ use near_sdk::{
 };
 
 use crate::{
-    contract::*, internal::utils::current_timestamp_sec, ApprovalId, AssetId, TimestampSec, TokenId,
+    constants::{MAX_FT_DISTRIBUTE_MEMBERS, VOTE_TOKEN_ASSET_ID},
+    contract::*,
+    group::Group,
+    internal::utils::current_timestamp_sec,
+    workflow::InternalDaoActionError,
+    ApprovalId, AssetId, GroupId, RoleId, TimestampSec, TokenId,
 };
 
 derive_into_versioned!(TreasuryPartition, VersionedTreasuryPartition, V1);
@@ -353,4 +358,61 @@ impl Contract {
             false
         }
     }
+    /// Distribute `amount` of vote tokens from the partition to each member of the group with `role_id`.
+    /// Role id 0 means all group members.
+    /// Tokens are sent to the members immediately. Failed transfers are kept in member's wallet.
+    /// Fails if there are more than `MAX_FT_DISTRIBUTE_MEMBERS` members.
+    /// Return count of members tokens were sent to.
+    pub fn ft_distribute(
+        &mut self,
+        partition_id: u16,
+        group_id: GroupId,
+        role_id: RoleId,
+        amount: u128,
+    ) -> Result<u32, InternalDaoActionError> {
+        let group = self
+            .groups
+            .get(&group_id)
+            .map(Group::from)
+            .ok_or_else(|| InternalDaoActionError("group not found".into()))?;
+        let members = self.get_group_members_with_role(group_id, &group, role_id);
+        if members.is_empty() || amount == 0 {
+            return Ok(0);
+        }
+        if members.len() > MAX_FT_DISTRIBUTE_MEMBERS {
+            return Err(InternalDaoActionError(
+                "too many members to distribute to".into(),
+            ));
+        }
+        let total_amount = amount
+            .checked_mul(members.len() as u128)
+            .ok_or_else(|| InternalDaoActionError("amount overflow".into()))?;
+        let ft_total_distributed = u32::try_from(total_amount / 10u128.pow(self.decimals as u32))
+            .ok()
+            .and_then(|distributed| self.ft_total_distributed.checked_add(distributed))
+            .ok_or_else(|| InternalDaoActionError("total distributed overflow".into()))?;
+        let mut partition: TreasuryPartition = self
+            .treasury_partition
+            .get(&partition_id)
+            .ok_or_else(|| InternalDaoActionError("partition not found".into()))?
+            .into();
+        let available_amount = partition
+            .asset(VOTE_TOKEN_ASSET_ID)
+            .map(|a| a.available_amount())
+            .unwrap_or_default();
+        if available_amount < total_amount {
+            return Err(InternalDaoActionError(
+                "not enough vote tokens in the partition".into(),
+            ));
+        }
+        partition.remove_amount(VOTE_TOKEN_ASSET_ID, 0, total_amount);
+        self.treasury_partition
+            .insert(&partition_id, &partition.into());
+        self.ft_total_distributed = ft_total_distributed;
+        let count = members.len() as u32;
+        for account_id in members {
+            self.send_reward(account_id, VOTE_TOKEN_ASSET_ID, amount);
+        }
+        Ok(count)
+    }
 }
@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+
+use library::{types::Value, workflow::types::DaoActionIdent};
+use near_sdk::{testing_env, AccountId};
+
+use crate::{
+    constants::{GROUP_TAG_CATEGORY, MAX_FT_DISTRIBUTE_MEMBERS, VOTE_TOKEN_ASSET_ID},
+    group::GroupMember,
+    media::{Media, ResourceType},
+    role::RoleMetadata,
+    settings::Settings,
+    treasury::TreasuryPartition,
+    unit_tests::{
+        as_account_id, decimal_const, get_context_builder, get_default_contract,
+        get_default_dao_config, ACC_1, FOUNDER_1, FOUNDER_2, FOUNDER_3, GROUP_1_NAME,
+        GROUP_1_ROLE_1, GROUP_2_NAME,
+    },
+    view::RoleView,
+};
+
+fn inputs(values: Vec<(&str, Value)>) -> HashMap<String, Value> {
+    values
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect()
+}
+
+#[test]
+fn dao_action_settings_update() {
+    let ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let mut input = inputs(vec![
+        ("name", Value::String("new dao name".into())),
+        ("tags", Value::VecU64(vec![1])),
+        ("scheduler", Value::Null),
+        ("token_id", Value::String("other.token.testnet".into())),
+    ]);
+    contract
+        .execute_dao_action(DaoActionIdent::SettingsUpdate, &mut input)
+        .unwrap();
+    let expected_settings = Settings {
+        name: "new dao name".into(),
+        tags: vec![1],
+        scheduler: None,
+        ..get_default_dao_config()
+    };
+    let settings: Settings = contract.settings.get().unwrap().into();
+    assert_eq!(settings, expected_settings);
+}
+
+#[test]
+fn dao_action_group_update() {
+    let ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let mut input = inputs(vec![
+        ("id", Value::U64(2)),
+        ("settings.name", Value::String(GROUP_2_NAME.into())),
+        ("settings.leader", Value::String(FOUNDER_2.into())),
+    ]);
+    contract
+        .execute_dao_action(DaoActionIdent::GroupUpdate, &mut input)
+        .unwrap();
+    let group = contract.group(2).unwrap();
+    assert_eq!(group.group_leader(), Some(&as_account_id(FOUNDER_2)));
+    assert_eq!(group.settings.parent_group, 0);
+
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("settings.name", Value::String(GROUP_1_NAME.into())),
+        (
+            "settings.leader",
+            Value::String("not_member.testnet".into()),
+        ),
+    ]);
+    assert!(contract
+        .execute_dao_action(DaoActionIdent::GroupUpdate, &mut input)
+        .is_err());
+}
+
+#[test]
+fn dao_action_tags() {
+    let ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let mut input = inputs(vec![
+        ("category", Value::String("media".into())),
+        (
+            "values",
+            Value::VecString(vec!["tag1".into(), "tag2".into()]),
+        ),
+    ]);
+    contract
+        .execute_dao_action(DaoActionIdent::TagAdd, &mut input)
+        .unwrap();
+    let mut input = inputs(vec![
+        ("category", Value::String("media".into())),
+        ("id", Value::U64(2)),
+        ("value", Value::String("renamed".into())),
+    ]);
+    contract
+        .execute_dao_action(DaoActionIdent::TagUpdate, &mut input)
+        .unwrap();
+    let mut input = inputs(vec![
+        ("category", Value::String("media".into())),
+        ("id", Value::U64(1)),
+    ]);
+    contract
+        .execute_dao_action(DaoActionIdent::TagRemove, &mut input)
+        .unwrap();
+    let tags = contract.tags.get(&"media".to_string()).unwrap();
+    assert!(tags.get(1).is_none());
+    assert_eq!(tags.get(2), Some(&"renamed".to_string()));
+
+    let mut input = inputs(vec![
+        ("category", Value::String("unknown".into())),
+        ("id", Value::U64(1)),
+    ]);
+    assert!(contract
+        .execute_dao_action(DaoActionIdent::TagRemove, &mut input)
+        .is_err());
+}
+
+#[test]
+fn dao_action_media_remove() {
+    let ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let id = contract.media_add(&Media {
+        proposal_id: None,
+        name: "media".into(),
+        category: "test".into(),
+        r#type: ResourceType::Text("text".into()),
+        tags: vec![],
+        version: "1".into(),
+        valid: true,
+    });
+    let mut input = inputs(vec![("id", Value::U64(id as u64))]);
+    contract
+        .execute_dao_action(DaoActionIdent::MediaRemove, &mut input)
+        .unwrap();
+    assert!(contract.media.get(&id).is_none());
+    let mut input = inputs(vec![("id", Value::U64(id as u64))]);
+    assert!(contract
+        .execute_dao_action(DaoActionIdent::MediaRemove, &mut input)
+        .is_err());
+}
+
+#[test]
+fn dao_action_ft_distribute() {
+    let ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let amount = 1_000 * decimal_const();
+    contract.partition_add_asset_amount(2, VOTE_TOKEN_ASSET_ID, 5 * amount);
+    let mut input = inputs(vec![
+        ("partition_id", Value::U64(2)),
+        ("group_id", Value::U64(1)),
+        ("amount", Value::U128(amount.into())),
+    ]);
+    contract
+        .execute_dao_action(DaoActionIdent::FtDistribute, &mut input)
+        .unwrap();
+    let partition: TreasuryPartition = contract.treasury_partition.get(&2).unwrap().into();
+    assert_eq!(
+        partition
+            .asset(VOTE_TOKEN_ASSET_ID)
+            .unwrap()
+            .available_amount(),
+        2 * amount
+    );
+    assert_eq!(contract.ft_total_distributed, 3_000);
+
+    // Group 2 has 5 members.
+    let mut input = inputs(vec![
+        ("partition_id", Value::U64(2)),
+        ("group_id", Value::U64(2)),
+        ("amount", Value::U128(amount.into())),
+    ]);
+    assert!(contract
+        .execute_dao_action(DaoActionIdent::FtDistribute, &mut input)
+        .is_err());
+}
+
+#[test]
+fn dao_action_ft_distribute_total_overflow() {
+    let ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let amount = 1_000 * decimal_const();
+    contract.partition_add_asset_amount(2, VOTE_TOKEN_ASSET_ID, 5 * amount);
+    contract.ft_total_distributed = u32::MAX - 2_000;
+    let mut input = inputs(vec![
+        ("partition_id", Value::U64(2)),
+        ("group_id", Value::U64(1)),
+        ("amount", Value::U128(amount.into())),
+    ]);
+    assert!(contract
+        .execute_dao_action(DaoActionIdent::FtDistribute, &mut input)
+        .is_err());
+    let partition: TreasuryPartition = contract.treasury_partition.get(&2).unwrap().into();
+    assert_eq!(
+        partition
+            .asset(VOTE_TOKEN_ASSET_ID)
+            .unwrap()
+            .available_amount(),
+        5 * amount
+    );
+    assert_eq!(contract.ft_total_distributed, u32::MAX - 2_000);
+}
+
+#[test]
+fn dao_action_ft_distribute_too_many_members() {
+    let ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let members = (0..MAX_FT_DISTRIBUTE_MEMBERS)
+        .map(|i| GroupMember::new(as_account_id(&format!("member{}.testnet", i))))
+        .collect();
+    assert!(contract.group_add_members(1, members, vec![]));
+    contract.partition_add_asset_amount(2, VOTE_TOKEN_ASSET_ID, 1_000 * decimal_const());
+    let mut input = inputs(vec![
+        ("partition_id", Value::U64(2)),
+        ("group_id", Value::U64(1)),
+        ("amount", Value::U128(1.into())),
+    ]);
+    assert!(contract
+        .execute_dao_action(DaoActionIdent::FtDistribute, &mut input)
+        .is_err());
+}
+
+#[test]
+fn dao_action_group_set_leader() {
+    let ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("leader", Value::String(FOUNDER_2.into())),
+    ]);
+    contract
+        .execute_dao_action(DaoActionIdent::GroupSetLeader, &mut input)
+        .unwrap();
+    assert_eq!(
+        contract.group(1).unwrap().group_leader(),
+        Some(&as_account_id(FOUNDER_2))
+    );
+
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("leader", Value::String(ACC_1.into())),
+    ]);
+    assert!(contract
+        .execute_dao_action(DaoActionIdent::GroupSetLeader, &mut input)
+        .is_err());
+
+    let mut input = inputs(vec![("id", Value::U64(1)), ("leader", Value::Null)]);
+    contract
+        .execute_dao_action(DaoActionIdent::GroupSetLeader, &mut input)
+        .unwrap();
+    assert!(contract.group(1).unwrap().group_leader().is_none());
+}
+
+#[test]
+fn dao_action_group_member_tags() {
+    let ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    contract.tag_add(
+        GROUP_TAG_CATEGORY.into(),
+        vec!["junior".into(), "senior".into()],
+    );
+    assert_eq!(
+        contract.group_members_with_tag(1, 2),
+        vec![as_account_id(FOUNDER_3)]
+    );
+
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("account_id", Value::String(FOUNDER_1.into())),
+        ("tags", Value::VecU64(vec![1, 2])),
+    ]);
+    contract
+        .execute_dao_action(DaoActionIdent::GroupAddMemberTags, &mut input)
+        .unwrap();
+    let mut members = contract.group_members_with_tag(1, 2);
+    members.sort();
+    assert_eq!(
+        members,
+        vec![as_account_id(FOUNDER_1), as_account_id(FOUNDER_3)]
+    );
+
+    // Tag 3 is not defined in the group tag category.
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("account_id", Value::String(FOUNDER_1.into())),
+        ("tags", Value::VecU64(vec![3])),
+    ]);
+    assert!(contract
+        .execute_dao_action(DaoActionIdent::GroupAddMemberTags, &mut input)
+        .is_err());
+
+    // ACC_1 is not member of the group 1.
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("account_id", Value::String(ACC_1.into())),
+        ("tags", Value::VecU64(vec![1])),
+    ]);
+    assert!(contract
+        .execute_dao_action(DaoActionIdent::GroupAddMemberTags, &mut input)
+        .is_err());
+
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("account_id", Value::String(FOUNDER_1.into())),
+        ("tags", Value::VecU64(vec![2])),
+    ]);
+    contract
+        .execute_dao_action(DaoActionIdent::GroupRemoveMemberTags, &mut input)
+        .unwrap();
+    assert_eq!(
+        contract.group_members_with_tag(1, 2),
+        vec![as_account_id(FOUNDER_3)]
+    );
+    assert!(contract
+        .group(1)
+        .unwrap()
+        .is_member_tagged(&as_account_id(FOUNDER_1), 1));
+}
+
+#[test]
+fn dao_action_group_roles() {
+    let ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("name", Value::String("treasurer".into())),
+        (
+            "metadata.description",
+            Value::String("Manages treasury".into()),
+        ),
+        (
+            "metadata.permissions",
+            Value::VecString(vec!["treasury_send_near".into()]),
+        ),
+    ]);
+    contract
+        .execute_dao_action(DaoActionIdent::GroupAddRole, &mut input)
+        .unwrap();
+
+    // Role names are unique in the group.
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("name", Value::String(GROUP_1_ROLE_1.into())),
+    ]);
+    assert!(contract
+        .execute_dao_action(DaoActionIdent::GroupAddRole, &mut input)
+        .is_err());
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("role_id", Value::U64(2)),
+        ("name", Value::String(GROUP_1_ROLE_1.into())),
+    ]);
+    assert!(contract
+        .execute_dao_action(DaoActionIdent::GroupRenameRole, &mut input)
+        .is_err());
+
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("role_id", Value::U64(2)),
+        ("name", Value::String("cashier".into())),
+    ]);
+    contract
+        .execute_dao_action(DaoActionIdent::GroupRenameRole, &mut input)
+        .unwrap();
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("role_id", Value::U64(1)),
+        ("metadata.description", Value::String("Founders".into())),
+    ]);
+    contract
+        .execute_dao_action(DaoActionIdent::GroupDescribeRole, &mut input)
+        .unwrap();
+    let mut input = inputs(vec![
+        ("id", Value::U64(1)),
+        ("role_id", Value::U64(3)),
+        ("metadata.description", Value::String("Unknown".into())),
+    ]);
+    assert!(contract
+        .execute_dao_action(DaoActionIdent::GroupDescribeRole, &mut input)
+        .is_err());
+
+    assert_eq!(
+        contract.group_role_list(1),
+        vec![
+            RoleView {
+                id: 1,
+                name: GROUP_1_ROLE_1.into(),
+                metadata: Some(RoleMetadata {
+                    description: "Founders".into(),
+                    permissions: vec![],
+                }),
+                members: vec![as_account_id(FOUNDER_1)],
+            },
+            RoleView {
+                id: 2,
+                name: "cashier".into(),
+                metadata: Some(RoleMetadata {
+                    description: "Manages treasury".into(),
+                    permissions: vec!["treasury_send_near".into()],
+                }),
+                members: vec![],
+            },
+        ]
+    );
+
+    // Metadata of removed role is removed as well.
+    assert!(contract.group_remove_roles(1, vec![2]));
+    let roles = contract.group_roles(1).unwrap();
+    assert!(roles.get_metadata(2).is_none());
+    assert!(roles.get_metadata(1).is_some());
+}
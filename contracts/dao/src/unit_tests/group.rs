@@ -1,11 +1,14 @@
 use std::collections::HashMap;
 
 use library::workflow::types::{ActivityRight, VoteScenario};
-use near_sdk::{test_utils::accounts, testing_env, AccountId, MockedBlockchain};
+use near_sdk::{
+    borsh::BorshSerialize, env, test_utils::accounts, testing_env, AccountId, IntoStorageKey,
+    MockedBlockchain,
+};
 
 use crate::{
-    contract::Contract,
-    group::{Group, GroupInput, GroupMember, GroupSettings},
+    contract::{Contract, StorageKeys},
+    group::{Group, GroupInput, GroupMember, GroupSettings, GroupTreeNode, GroupV1},
     reward::{Reward, RewardType},
     role::{MemberRoles, Roles, UserRoles},
     treasury::Asset,
@@ -13,8 +16,8 @@ use crate::{
         as_account_id, assert_group_members, assert_group_rewards, assert_group_role_members,
         assert_no_wallet_reward, assert_user_roles, assert_wallet, claimable_rewards_sum,
         decimal_const, default_group_1_roles, default_group_2_roles, founder_1_roles,
-        founder_2_roles, founder_3_roles, get_default_contract, tm, FOUNDER_1, FOUNDER_2,
-        FOUNDER_3, FOUNDER_4, FOUNDER_5, GROUP_1_ROLE_1,
+        founder_2_roles, founder_3_roles, get_default_contract, tm, ACC_1, FOUNDER_1, FOUNDER_2,
+        FOUNDER_3, FOUNDER_4, FOUNDER_5, GROUP_1_NAME, GROUP_1_ROLE_1, GROUP_2_NAME,
     },
     wallet::Wallet,
     AssetId,
@@ -34,22 +37,26 @@ fn group_add() {
         GroupMember {
             account_id: as_account_id(FOUNDER_4),
             tags: vec![0],
+            valid_to: None,
         },
         GroupMember {
             account_id: as_account_id(FOUNDER_5),
             tags: vec![1],
+            valid_to: None,
         },
     ];
 
-    contract.group_add(GroupInput {
-        settings: GroupSettings {
-            name: "council_rest".into(),
-            leader: Some(as_account_id(FOUNDER_4)),
-            parent_group: 0,
-        },
-        members: new_group_members.clone(),
-        member_roles: vec![],
-    });
+    contract
+        .group_add(GroupInput {
+            settings: GroupSettings {
+                name: "council_rest".into(),
+                leader: Some(as_account_id(FOUNDER_4)),
+                parent_group: 0,
+            },
+            members: new_group_members.clone(),
+            member_roles: vec![],
+        })
+        .unwrap();
 
     assert_eq!(contract.total_members_count, 8);
 }
@@ -81,14 +88,17 @@ fn group_add_members_only_members() {
         GroupMember {
             account_id: accounts(0),
             tags: vec![],
+            valid_to: None,
         },
         GroupMember {
             account_id: accounts(1),
             tags: vec![],
+            valid_to: None,
         },
         GroupMember {
             account_id: accounts(2),
             tags: vec![],
+            valid_to: None,
         },
     ];
     let new_member_roles = vec![];
@@ -145,14 +155,17 @@ fn group_add_members_only_roles() {
         MemberRoles {
             name: "bilboswaggings".into(),
             members: vec![],
+            valid_to: None,
         },
         MemberRoles {
             name: "rustyboi".into(),
             members: vec![],
+            valid_to: None,
         },
         MemberRoles {
             name: "hamstalover".into(),
             members: vec![],
+            valid_to: None,
         },
     ];
     let members_count = new_group_members.len();
@@ -207,28 +220,34 @@ fn group_add_members_members_and_new_roles() {
         GroupMember {
             account_id: accounts(0),
             tags: vec![],
+            valid_to: None,
         },
         GroupMember {
             account_id: accounts(1),
             tags: vec![],
+            valid_to: None,
         },
         GroupMember {
             account_id: accounts(2),
             tags: vec![],
+            valid_to: None,
         },
     ];
     let new_member_roles = vec![
         MemberRoles {
             name: "bilboswaggings".into(),
             members: vec![accounts(0)],
+            valid_to: None,
         },
         MemberRoles {
             name: "rustyboi".into(),
             members: vec![accounts(1)],
+            valid_to: None,
         },
         MemberRoles {
             name: "hamstalover".into(),
             members: vec![],
+            valid_to: None,
         },
     ];
     let members_count = new_group_members.len();
@@ -296,18 +315,22 @@ fn group_add_members_roles_for_existing_members() {
         MemberRoles {
             name: GROUP_1_ROLE_1.into(),
             members: vec![as_account_id(FOUNDER_2), as_account_id(FOUNDER_3)],
+            valid_to: None,
         },
         MemberRoles {
             name: "bilboswaggings".into(),
             members: vec![as_account_id(FOUNDER_1)],
+            valid_to: None,
         },
         MemberRoles {
             name: "rustyboi".into(),
             members: vec![as_account_id(FOUNDER_2)],
+            valid_to: None,
         },
         MemberRoles {
             name: "hamstalover".into(),
             members: vec![],
+            valid_to: None,
         },
     ];
     let members_count = new_group_members.len();
@@ -384,14 +407,17 @@ fn group_add_roles_with_unknown_members() {
         MemberRoles {
             name: "bilboswaggings".into(),
             members: vec![accounts(0)],
+            valid_to: None,
         },
         MemberRoles {
             name: "rustyboi".into(),
             members: vec![accounts(1), accounts(2)],
+            valid_to: None,
         },
         MemberRoles {
             name: "hamstalover".into(),
             members: vec![],
+            valid_to: None,
         },
     ];
     let members_count = new_group_members.len();
@@ -449,6 +475,265 @@ fn group_remove_members() {
     assert_eq!(contract.total_members_count, 5);
 }
 
+#[test]
+fn group_members_and_roles_expire() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    assert_eq!(contract.total_members_count, 6);
+    contract.group_add_members(
+        1,
+        vec![
+            GroupMember {
+                account_id: as_account_id(FOUNDER_4),
+                tags: vec![],
+                valid_to: Some(100),
+            },
+            GroupMember {
+                account_id: as_account_id(FOUNDER_5),
+                tags: vec![],
+                valid_to: None,
+            },
+        ],
+        vec![MemberRoles {
+            name: "council".into(),
+            members: vec![as_account_id(FOUNDER_5)],
+            valid_to: Some(50),
+        }],
+    );
+    assert_eq!(contract.total_members_count, 8);
+    let role_id = contract
+        .group_roles(1)
+        .unwrap()
+        .find_role_by_name("council")
+        .unwrap();
+    assert_group_role_members(&contract, 1, role_id, vec![as_account_id(FOUNDER_5)]);
+    assert!(contract.check_rights(&[ActivityRight::Group(1)], &as_account_id(FOUNDER_4)));
+
+    testing_env!(ctx.block_timestamp(tm(50)).build());
+    assert_group_role_members(&contract, 1, role_id, vec![]);
+    assert!(!contract.check_rights(
+        &[ActivityRight::GroupRole(1, role_id)],
+        &as_account_id(FOUNDER_5)
+    ));
+    assert!(contract.check_rights(&[ActivityRight::Group(1)], &as_account_id(FOUNDER_4)));
+
+    testing_env!(ctx.block_timestamp(tm(100)).build());
+    assert_group_members(
+        &contract,
+        1,
+        vec![
+            as_account_id(FOUNDER_1),
+            as_account_id(FOUNDER_2),
+            as_account_id(FOUNDER_3),
+            as_account_id(FOUNDER_5),
+        ],
+    );
+    assert!(!contract.check_rights(&[ActivityRight::Group(1)], &as_account_id(FOUNDER_4)));
+    assert!(!contract.check_rights(&[ActivityRight::Member], &as_account_id(FOUNDER_4)));
+    assert_eq!(
+        contract.calculate_votes(
+            &HashMap::new(),
+            &VoteScenario::Democratic,
+            &ActivityRight::Group(1)
+        ),
+        (4, [0, 0, 0])
+    );
+    assert_eq!(
+        contract.calculate_votes(
+            &HashMap::new(),
+            &VoteScenario::Democratic,
+            &ActivityRight::Member
+        ),
+        (7, [0, 0, 0])
+    );
+
+    assert_eq!(contract.group_purge_expired(1), 1);
+    assert_eq!(contract.total_members_count, 7);
+    assert_user_roles(&contract, as_account_id(FOUNDER_4), None);
+    assert_user_roles(
+        &contract,
+        as_account_id(FOUNDER_5),
+        Some(UserRoles::new().add_role(1, 0)),
+    );
+    assert!(!contract
+        .group(1)
+        .unwrap()
+        .members
+        .contains_key(&as_account_id(FOUNDER_4)));
+}
+
+#[test]
+fn group_expired_leader() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    contract.group_add_members(
+        1,
+        vec![GroupMember {
+            account_id: as_account_id(FOUNDER_4),
+            tags: vec![],
+            valid_to: Some(100),
+        }],
+        vec![],
+    );
+    contract
+        .group_set_leader(1, Some(as_account_id(FOUNDER_4)))
+        .unwrap();
+    assert!(contract.check_rights(&[ActivityRight::GroupLeader(1)], &as_account_id(FOUNDER_4)));
+
+    testing_env!(ctx.block_timestamp(tm(100)).build());
+    assert!(!contract.check_rights(&[ActivityRight::GroupLeader(1)], &as_account_id(FOUNDER_4)));
+    let mut votes = HashMap::new();
+    votes.insert(as_account_id(FOUNDER_4), 1);
+    assert_eq!(
+        contract.calculate_votes(
+            &votes,
+            &VoteScenario::TokenWeighted,
+            &ActivityRight::GroupLeader(1)
+        ),
+        (0, [0, 0, 0])
+    );
+}
+
+#[test]
+fn group_migrate_unversioned() {
+    let ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let group = contract.group(1).unwrap();
+    let old_group = GroupV1 {
+        settings: group.settings,
+        members: group.members,
+        rewards: group.rewards,
+    };
+    // Overwrite stored values with the layouts of the previous version.
+    let index = contract
+        .groups
+        .keys_as_vector()
+        .iter()
+        .position(|id| id == 1)
+        .unwrap() as u64;
+    let key_raw = contract.groups.keys_as_vector().get_raw(index).unwrap();
+    contract
+        .groups
+        .insert_raw(&key_raw, &old_group.try_to_vec().unwrap());
+    let founder_1_key = [
+        StorageKeys::UserRoles.into_storage_key(),
+        as_account_id(FOUNDER_1).try_to_vec().unwrap(),
+    ]
+    .concat();
+    let mut old_roles = HashMap::new();
+    old_roles.insert(1u16, vec![0u16, 1]);
+    env::storage_write(&founder_1_key, &old_roles.try_to_vec().unwrap());
+    let group_roles = contract.group_roles(1).unwrap();
+    let role_names: HashMap<u16, String> = group_roles
+        .iter()
+        .map(|(id, name)| (*id, name.to_owned()))
+        .collect();
+    let last_role_id = *role_names.keys().max().unwrap();
+    env::storage_write(
+        &[StorageKeys::GroupRoles.into_storage_key(), key_raw].concat(),
+        &(last_role_id, role_names).try_to_vec().unwrap(),
+    );
+
+    contract.migrate_unversioned_groups();
+    let group = contract.group(1).unwrap();
+    assert!(group.members_valid_to.is_empty());
+    assert!(group.is_member(&as_account_id(FOUNDER_1)));
+    assert_eq!(contract.group_roles(1), Some(group_roles));
+    assert_user_roles(
+        &contract,
+        as_account_id(FOUNDER_1),
+        Some(UserRoles::new().add_group_roles(1, vec![0, 1])),
+    );
+    assert_eq!(
+        near_sdk::serde_json::to_value(contract.user_roles(as_account_id(FOUNDER_1)).unwrap())
+            .unwrap(),
+        near_sdk::serde_json::json!({ "1": [0, 1] })
+    );
+    // Already versioned values are kept.
+    contract.migrate_unversioned_groups();
+    assert!(contract
+        .group(1)
+        .unwrap()
+        .is_member(&as_account_id(FOUNDER_1)));
+    assert_eq!(
+        contract.user_roles(as_account_id(FOUNDER_2)),
+        Some(founder_2_roles())
+    );
+}
+
+#[test]
+fn group_hierarchy() {
+    let ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    contract
+        .group_add(GroupInput {
+            settings: GroupSettings {
+                name: "sub".into(),
+                leader: None,
+                parent_group: 2,
+            },
+            members: vec![],
+            member_roles: vec![],
+        })
+        .unwrap();
+    assert_eq!(contract.group_ancestors(3), vec![2, 1]);
+    assert!(contract.check_rights(&[ActivityRight::ParentOf(3)], &as_account_id(FOUNDER_1)));
+    assert!(contract.check_rights(&[ActivityRight::ParentOf(3)], &as_account_id(ACC_1)));
+    assert!(!contract.check_rights(&[ActivityRight::ParentOf(2)], &as_account_id(ACC_1)));
+    assert_eq!(
+        contract.calculate_votes(
+            &HashMap::new(),
+            &VoteScenario::Democratic,
+            &ActivityRight::ParentOf(3)
+        ),
+        (6, [0, 0, 0])
+    );
+
+    // Cycle 1 -> 3 -> 2 -> 1.
+    assert!(contract
+        .group_update(
+            1,
+            GroupSettings {
+                name: GROUP_1_NAME.into(),
+                leader: None,
+                parent_group: 3,
+            },
+        )
+        .is_err());
+    assert!(contract
+        .group_add(GroupInput {
+            settings: GroupSettings {
+                name: "orphan".into(),
+                leader: None,
+                parent_group: 10,
+            },
+            members: vec![],
+            member_roles: vec![],
+        })
+        .is_err());
+    assert!(contract.group_remove(2).is_err());
+    assert_eq!(
+        contract.group_tree(),
+        vec![GroupTreeNode {
+            id: 1,
+            name: GROUP_1_NAME.into(),
+            children: vec![GroupTreeNode {
+                id: 2,
+                name: GROUP_2_NAME.into(),
+                children: vec![GroupTreeNode {
+                    id: 3,
+                    name: "sub".into(),
+                    children: vec![],
+                }],
+            }],
+        }]
+    );
+}
+
 #[test]
 fn group_remove_member_role_only_role() {
     let mut ctx = get_context_builder();
@@ -527,6 +812,7 @@ fn group_remove_member_role_with_members() {
         vec![MemberRoles {
             name: GROUP_1_ROLE_1.into(),
             members: vec![as_account_id(FOUNDER_2)],
+            valid_to: None,
         }],
     );
     assert_group_members(
@@ -559,6 +845,7 @@ fn group_remove_member_role_with_members() {
         vec![MemberRoles {
             name: GROUP_1_ROLE_1.into(),
             members: vec![as_account_id(FOUNDER_1), as_account_id(FOUNDER_3)],
+            valid_to: None,
         }],
     );
     assert_group_members(
@@ -641,30 +928,36 @@ fn group_scenario() {
         GroupMember {
             account_id: accounts(0),
             tags: vec![],
+            valid_to: None,
         },
         GroupMember {
             account_id: accounts(1),
             tags: vec![],
+            valid_to: None,
         },
         GroupMember {
             account_id: accounts(2),
             tags: vec![],
+            valid_to: None,
         },
         GroupMember {
             account_id: founder_account.clone(),
             tags: vec![],
+            valid_to: None,
         },
     ];
     let members_count = new_group_members.len();
-    contract.group_add(GroupInput {
-        settings: GroupSettings {
-            name: "artists".into(),
-            leader: Some(accounts(0)),
-            parent_group: 0,
-        },
-        members: new_group_members.clone(),
-        member_roles: vec![],
-    });
+    contract
+        .group_add(GroupInput {
+            settings: GroupSettings {
+                name: "artists".into(),
+                leader: Some(accounts(0)),
+                parent_group: 0,
+            },
+            members: new_group_members.clone(),
+            member_roles: vec![],
+        })
+        .unwrap();
     assert_eq!(contract.total_members_count as usize, 6 + members_count - 1); // Note: FOUNDER_1 is already in the council group.
     assert_eq!(contract.group_last_id, 3);
     let group = contract.group(3).unwrap();
@@ -705,6 +998,7 @@ fn group_scenario() {
         "test".into(),
         3,
         0,
+        0,
         1,
         RewardType::new_wage(1),
         vec![(reward_asset_id, 1)],
@@ -768,10 +1062,12 @@ fn group_scenario() {
         vec![GroupMember {
             account_id: accounts(3),
             tags: vec![],
+            valid_to: None,
         }],
         vec![MemberRoles {
             name: "eksmen".into(),
             members: vec![accounts(3)],
+            valid_to: None,
         }],
     );
     assert_eq!(
@@ -886,14 +1182,17 @@ fn group_scenario() {
             MemberRoles {
                 name: "alpha".into(),
                 members: vec![],
+                valid_to: None,
             },
             MemberRoles {
                 name: "beta".into(),
                 members: vec![],
+                valid_to: None,
             },
             MemberRoles {
                 name: "gamma".into(),
                 members: vec![],
+                valid_to: None,
             },
         ],
     );
@@ -932,24 +1231,29 @@ fn group_scenario() {
             GroupMember {
                 account_id: accounts(4),
                 tags: vec![],
+                valid_to: None,
             },
             GroupMember {
                 account_id: accounts(5),
                 tags: vec![],
+                valid_to: None,
             },
         ],
         vec![
             MemberRoles {
                 name: "alpha".into(),
                 members: vec![accounts(4)],
+                valid_to: None,
             },
             MemberRoles {
                 name: "beta".into(),
                 members: vec![accounts(5)],
+                valid_to: None,
             },
             MemberRoles {
                 name: "gamma".into(),
                 members: vec![],
+                valid_to: None,
             },
         ],
     );
@@ -1003,6 +1307,7 @@ fn group_scenario() {
         "test".into(),
         3,
         2,
+        0,
         1,
         RewardType::new_wage(1),
         vec![(reward_asset_id, 1)],
@@ -1092,7 +1397,7 @@ fn group_scenario() {
         6 + members_count - 1 + 1 - 1 + 2
     );
     assert!(contract.group_roles.get(&3).is_some());
-    contract.group_remove(3);
+    contract.group_remove(3).unwrap();
     assert!(contract.group_roles.get(&3).is_none());
     assert_eq!(contract.total_members_count as usize, 6);
     assert!(contract.group(3).is_none());
@@ -1228,7 +1533,7 @@ fn group_add_remove_add_role_with_reward() {
     let mut expected_founder_roles = founder_1_roles();
     let expected_group_1_roles = default_group_1_roles();
     assert_eq!(
-        contract.group_roles.get(&1).unwrap(),
+        contract.group_roles(1).unwrap(),
         expected_group_1_roles.clone()
     );
     assert_user_roles(
@@ -1245,6 +1550,7 @@ fn group_add_remove_add_role_with_reward() {
         "test".into(),
         1,
         1,
+        0,
         1,
         RewardType::new_wage(1),
         vec![(reward_asset_id, 1)],
@@ -1271,7 +1577,7 @@ fn group_add_remove_add_role_with_reward() {
         10
     );
     assert_eq!(
-        contract.group_roles.get(&1).unwrap(),
+        contract.group_roles(1).unwrap(),
         expected_group_1_roles.clone()
     );
     // Remove founder_1 from founder role.
@@ -1280,6 +1586,7 @@ fn group_add_remove_add_role_with_reward() {
         vec![MemberRoles {
             name: GROUP_1_ROLE_1.into(),
             members: vec![founder_account.clone()],
+            valid_to: None,
         }],
     );
     expected_founder_roles = expected_founder_roles.remove_role(1, 1);
@@ -1310,6 +1617,7 @@ fn group_add_remove_add_role_with_reward() {
     let member_roles = vec![MemberRoles {
         name: GROUP_1_ROLE_1.into(),
         members: vec![founder_account.clone()],
+        valid_to: None,
     }];
     contract.group_add_members(1, vec![], member_roles);
     expected_founder_roles.add_group_role(1, 1);
@@ -1344,6 +1652,7 @@ fn group_add_remove_add_role_with_reward() {
         vec![MemberRoles {
             name: GROUP_1_ROLE_1.into(),
             members: vec![founder_account.clone()],
+            valid_to: None,
         }],
     );
     expected_founder_roles = expected_founder_roles.remove_role(1, 1);
@@ -1377,6 +1686,7 @@ fn group_add_remove_add_role_with_reward() {
     let member_roles = vec![MemberRoles {
         name: GROUP_1_ROLE_1.into(),
         members: vec![founder_account.clone()],
+        valid_to: None,
     }];
     contract.group_add_members(1, vec![], member_roles);
     expected_founder_roles.add_group_role(1, 1);
@@ -28,9 +28,12 @@ use crate::{
     AssetId, RewardId, RoleId,
 };
 
+mod dao_action;
 mod group;
+mod proposal;
 mod reward;
 pub mod treasury;
+mod view;
 mod voting;
 pub mod workflow;
 
@@ -193,19 +196,23 @@ pub(crate) fn get_default_groups() -> Vec<GroupInput> {
         GroupMember {
             account_id: as_account_id(FOUNDER_1),
             tags: vec![0],
+            valid_to: None,
         },
         GroupMember {
             account_id: as_account_id(FOUNDER_2),
             tags: vec![1],
+            valid_to: None,
         },
         GroupMember {
             account_id: as_account_id(FOUNDER_3),
             tags: vec![2],
+            valid_to: None,
         },
     ];
     let group_1_roles = vec![MemberRoles {
         name: GROUP_1_ROLE_1.into(),
         members: vec![as_account_id(FOUNDER_1)],
+        valid_to: None,
     }];
     groups.push(GroupInput {
         settings: GroupSettings {
@@ -221,22 +228,27 @@ pub(crate) fn get_default_groups() -> Vec<GroupInput> {
         GroupMember {
             account_id: as_account_id(ACC_1),
             tags: vec![],
+            valid_to: None,
         },
         GroupMember {
             account_id: as_account_id(ACC_2),
             tags: vec![],
+            valid_to: None,
         },
         GroupMember {
             account_id: as_account_id(ACC_3),
             tags: vec![],
+            valid_to: None,
         },
         GroupMember {
             account_id: as_account_id(FOUNDER_2),
             tags: vec![],
+            valid_to: None,
         },
         GroupMember {
             account_id: as_account_id(FOUNDER_3),
             tags: vec![],
+            valid_to: None,
         },
     ];
     let group_2_roles: Vec<MemberRoles> = vec![];
@@ -348,6 +360,7 @@ pub(crate) fn get_role_id(contract: &Contract, group_id: u16, role_name: &str) -
     let group_roles = contract
         .group_roles
         .get(&group_id)
+        .map(Roles::from)
         .expect("group not found");
     let role_id = group_roles
         .iter()
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use near_sdk::{testing_env, ONE_NEAR};
+
+use crate::{
+    constants::MAX_PROPOSAL_MEDIA,
+    contract::Contract,
+    media::{Media, ResourceType},
+    proposal::{Proposal, ProposalState, ProposalV1, VersionedProposal},
+    unit_tests::{
+        as_account_id, dummy_propose_settings, dummy_template_settings, get_context_builder,
+        get_default_contract, FOUNDER_1, FOUNDER_2,
+    },
+};
+
+fn media(name: &str, r#type: ResourceType) -> Media {
+    Media {
+        proposal_id: None,
+        name: name.into(),
+        category: "proposal".into(),
+        r#type,
+        tags: vec![],
+        version: "".into(),
+        valid: true,
+    }
+}
+
+fn create_proposal(contract: &mut Contract, description: Option<Media>) -> u32 {
+    contract.proposal_create(
+        description,
+        1,
+        0,
+        dummy_propose_settings(),
+        Some(vec![dummy_template_settings()]),
+        None,
+    )
+}
+
+#[test]
+fn proposal_media_history() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx
+        .predecessor_account_id(as_account_id(FOUNDER_1))
+        .attached_deposit(ONE_NEAR)
+        .build());
+    let mut contract = get_default_contract();
+    let id = create_proposal(
+        &mut contract,
+        Some(media("desc", ResourceType::Text("first".into()))),
+    );
+    let proposal = Proposal::from(contract.proposals.get(&id).unwrap());
+    assert_eq!(proposal.media, vec![proposal.desc]);
+    let first_desc = proposal.desc;
+    testing_env!(ctx.attached_deposit(ONE_NEAR / 100).build());
+
+    let amended_desc =
+        contract.proposal_amend(id, media("desc", ResourceType::Text("second".into())));
+    let attachment = contract.proposal_attach_media(
+        id,
+        media("spec", ResourceType::Link("https://example.com".into())),
+    );
+    let proposal = Proposal::from(contract.proposals.get(&id).unwrap());
+    assert_eq!(proposal.desc, amended_desc);
+
+    let history = contract.proposal_media(id);
+    assert_eq!(
+        history.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![first_desc, amended_desc, attachment]
+    );
+    let (_, first) = &history[0];
+    assert_eq!(first.version, "1");
+    assert!(!first.valid);
+    let (_, second) = &history[1];
+    assert_eq!(second.version, "2");
+    assert!(second.valid);
+    assert_eq!(second.r#type, ResourceType::Text("second".into()));
+    assert!(history.iter().all(|(_, m)| m.proposal_id == Some(id)));
+
+    // Proposal without description.
+    testing_env!(ctx.attached_deposit(ONE_NEAR).build());
+    let id = create_proposal(&mut contract, None);
+    testing_env!(ctx.attached_deposit(ONE_NEAR / 100).build());
+    assert!(contract.proposal_media(id).is_empty());
+    contract.proposal_amend(id, media("desc", ResourceType::Text("late".into())));
+    let history = contract.proposal_media(id);
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].1.version, "1");
+}
+
+#[test]
+#[should_panic]
+fn proposal_amend_by_other_account() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx
+        .predecessor_account_id(as_account_id(FOUNDER_1))
+        .attached_deposit(ONE_NEAR)
+        .build());
+    let mut contract = get_default_contract();
+    let id = create_proposal(&mut contract, None);
+    testing_env!(ctx.predecessor_account_id(as_account_id(FOUNDER_2)).build());
+    contract.proposal_amend(id, media("desc", ResourceType::Text("hijack".into())));
+}
+
+#[test]
+#[should_panic]
+fn proposal_amend_after_vote() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx
+        .predecessor_account_id(as_account_id(FOUNDER_1))
+        .attached_deposit(ONE_NEAR)
+        .build());
+    let mut contract = get_default_contract();
+    let id = create_proposal(&mut contract, None);
+    contract.proposal_vote(id, 1);
+    contract.proposal_amend(id, media("desc", ResourceType::Text("too late".into())));
+}
+
+#[test]
+#[should_panic(expected = "Not enough deposit to cover storage.")]
+fn proposal_attach_media_without_deposit() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx
+        .predecessor_account_id(as_account_id(FOUNDER_1))
+        .attached_deposit(ONE_NEAR)
+        .build());
+    let mut contract = get_default_contract();
+    let id = create_proposal(&mut contract, None);
+    testing_env!(ctx.attached_deposit(0).build());
+    contract.proposal_attach_media(
+        id,
+        media("spec", ResourceType::Link("https://example.com".into())),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Proposal has max number of media.")]
+fn proposal_attach_media_max_count() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx
+        .predecessor_account_id(as_account_id(FOUNDER_1))
+        .attached_deposit(ONE_NEAR)
+        .build());
+    let mut contract = get_default_contract();
+    let id = create_proposal(&mut contract, None);
+    testing_env!(ctx.attached_deposit(ONE_NEAR / 100).build());
+    for _ in 0..MAX_PROPOSAL_MEDIA {
+        contract.proposal_attach_media(
+            id,
+            media("spec", ResourceType::Link("https://example.com".into())),
+        );
+    }
+    assert_eq!(contract.proposal_media(id).len(), MAX_PROPOSAL_MEDIA);
+    contract.proposal_amend(id, media("desc", ResourceType::Text("one more".into())));
+}
+
+#[test]
+fn proposal_v1_converted_on_read() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let proposal = ProposalV1 {
+        desc: 5,
+        created: 60,
+        created_by: as_account_id(FOUNDER_1),
+        end: 120,
+        votes: HashMap::new(),
+        state: ProposalState::InProgress,
+        workflow_id: 1,
+        workflow_settings_id: 0,
+        voting_results: vec![],
+    };
+    contract
+        .proposals
+        .insert(&1, &VersionedProposal::V1(proposal));
+    let proposal = Proposal::from(contract.proposals.get(&1).unwrap());
+    assert_eq!(proposal.parent, None);
+    assert_eq!(proposal.media, vec![5]);
+
+    // Saved as the latest version on next write.
+    contract.proposals.insert(&1, &proposal.into());
+    assert!(matches!(
+        contract.proposals.get(&1).unwrap(),
+        VersionedProposal::V2(_)
+    ));
+}
@@ -6,10 +6,10 @@ use library::{
 use near_sdk::{test_utils::accounts, testing_env, AccountId, ONE_NEAR};
 
 use crate::{
-    constants::LATEST_REWARD_ACTIVITY_ID,
+    constants::{GROUP_TAG_CATEGORY, LATEST_REWARD_ACTIVITY_ID},
     contract::Contract,
     proposal::{Proposal, ProposalState},
-    reward::{Reward, RewardType, RewardTypeIdent, RewardWage},
+    reward::{Reward, RewardType, RewardTypeIdent, RewardV1, RewardWage, VersionedReward},
     treasury::{Asset, AssetRegistrar, PartitionAsset, PartitionAssetInput, TreasuryPartition},
     unit_tests::{
         as_account_id, assert_cache_reward_activity, claimable_rewards_sum, dummy_propose_settings,
@@ -61,6 +61,7 @@ fn reward_wage_one_asset() {
         "test".into(),
         1,
         role_id,
+        0,
         partition_id,
         RewardType::new_wage(2),
         vec![(reward_asset_id, 1)],
@@ -206,6 +207,7 @@ fn reward_activity_one_asset() {
         "test".into(),
         1,
         role_id,
+        0,
         partition_id,
         RewardType::new_user_activity(vec![0, 1]),
         vec![(reward_asset_id, 100)],
@@ -423,6 +425,7 @@ fn reward_activity_one_asset_for_anyone() {
         "test".into(),
         0,
         0,
+        0,
         1,
         RewardType::new_user_activity(vec![0, 1]),
         vec![(reward_asset_id, 100)],
@@ -570,6 +573,7 @@ fn reward_wage_one_asset_reward_updated() {
         "test".into(),
         1,
         role_id,
+        0,
         partition_id,
         RewardType::new_wage(1),
         vec![(reward_asset_id, 1)],
@@ -712,6 +716,7 @@ fn reward_wage_one_asset_reward_updated_2() {
         "test".into(),
         1,
         role_id,
+        0,
         partition_id,
         RewardType::new_wage(1),
         vec![(reward_asset_id, 1)],
@@ -902,6 +907,7 @@ fn reward_multiple_wage_rewards() {
         "test".into(),
         1,
         role_id,
+        0,
         partition_id,
         RewardType::new_wage(10),
         vec![(reward_asset_1_id, 100)],
@@ -912,6 +918,7 @@ fn reward_multiple_wage_rewards() {
         "test".into(),
         1,
         role_id,
+        0,
         partition_id,
         RewardType::new_wage(10),
         vec![(reward_asset_2_id, 100), (reward_asset_3_id, 100)],
@@ -922,6 +929,7 @@ fn reward_multiple_wage_rewards() {
         "test".into(),
         1,
         role_id,
+        0,
         partition_id,
         RewardType::new_wage(10),
         vec![
@@ -1229,6 +1237,7 @@ fn reward_one_asset_scenario() {
         "test".into(),
         1,
         role_id,
+        0,
         partition_id,
         RewardType::new_wage(1),
         vec![(reward_asset_1_id, 1 * ONE_NEAR)],
@@ -1428,6 +1437,7 @@ fn cache_reward_activity_management() {
         "test".into(),
         1,
         0,
+        0,
         1,
         RewardType::new_user_activity(vec![0, 1]),
         vec![(0, 100)],
@@ -1458,6 +1468,7 @@ fn cache_reward_activity_management() {
         "test".into(),
         1,
         0,
+        0,
         1,
         RewardType::new_user_activity(vec![0, 2]),
         vec![(0, 100)],
@@ -1570,3 +1581,119 @@ fn cache_reward_activity_management() {
         ],
     );
 }
+
+#[test]
+fn reward_wage_tagged_members() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let reward_asset_id = 0;
+    let (founder_1, founder_2, founder_3) = (
+        as_account_id(FOUNDER_1),
+        as_account_id(FOUNDER_2),
+        as_account_id(FOUNDER_3),
+    );
+    contract.tag_add(
+        GROUP_TAG_CATEGORY.into(),
+        vec!["junior".into(), "senior".into()],
+    );
+    let partition = TreasuryPartition {
+        name: "test".into(),
+        assets: vec![PartitionAsset::try_from(
+            PartitionAssetInput {
+                asset_id: Asset::Near,
+                unlocking: UnlockingInput {
+                    amount_init_unlock: 1000,
+                    lock: None,
+                },
+            },
+            &mut contract as &mut dyn AssetRegistrar,
+        )
+        .unwrap()],
+    };
+    let partition_id = contract.partition_add(partition);
+
+    // Tag 3 is not defined in the group tag category.
+    let reward = Reward::new(
+        "test".into(),
+        1,
+        0,
+        3,
+        partition_id,
+        RewardType::new_wage(1),
+        vec![(reward_asset_id, 1)],
+        0,
+        1000,
+    );
+    assert!(contract.reward_add(reward).is_err());
+
+    // Only founder_3 is tagged with tag 2.
+    let reward = Reward::new(
+        "test".into(),
+        1,
+        0,
+        2,
+        partition_id,
+        RewardType::new_wage(1),
+        vec![(reward_asset_id, 1)],
+        0,
+        1000,
+    );
+    let reward_id = contract.reward_add(reward).unwrap();
+    assert!(contract.wallet(founder_1.clone()).is_none());
+    assert!(contract.wallet(founder_2.clone()).is_none());
+    assert!(get_wallet(&contract, &founder_3)
+        .wallet_reward(reward_id)
+        .is_some());
+
+    testing_env!(ctx.block_timestamp(tm(10)).build());
+    contract
+        .group_add_member_tags(1, &founder_1, vec![2])
+        .unwrap();
+    let wallet = get_wallet(&contract, &founder_1);
+    let wallet_reward = wallet.wallet_reward(reward_id).unwrap();
+    assert_eq!(wallet_reward.time_added(), 10);
+    assert_eq!(wallet_reward.time_removed(), None);
+
+    testing_env!(ctx.block_timestamp(tm(20)).build());
+    contract
+        .group_remove_member_tags(1, &founder_1, vec![2])
+        .unwrap();
+    let wallet = get_wallet(&contract, &founder_1);
+    let wallet_reward = wallet.wallet_reward(reward_id).unwrap();
+    assert_eq!(wallet_reward.time_removed(), Some(20));
+    assert!(get_wallet(&contract, &founder_3)
+        .wallet_reward(reward_id)
+        .unwrap()
+        .time_removed()
+        .is_none());
+}
+
+#[test]
+fn reward_v1_is_not_filtered_by_tag() {
+    let reward_v1 = RewardV1 {
+        name: "test".into(),
+        group_id: 1,
+        role_id: 2,
+        partition_id: 3,
+        r#type: RewardType::new_wage(2),
+        reward_amounts: vec![(0, 1)],
+        time_valid_from: 0,
+        time_valid_to: 1000,
+    };
+    let reward: Reward = VersionedReward::V1(reward_v1).into();
+    assert_eq!(
+        reward,
+        Reward::new(
+            "test".into(),
+            1,
+            2,
+            0,
+            3,
+            RewardType::new_wage(2),
+            vec![(0, 1)],
+            0,
+            1000,
+        )
+    );
+}
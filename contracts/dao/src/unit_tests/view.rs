@@ -0,0 +1,187 @@
+use library::workflow::instance::{Instance, InstanceState};
+use near_sdk::{testing_env, ONE_NEAR};
+
+use crate::{
+    media::{Media, ResourceType},
+    proposal::{Proposal, ProposalState},
+    unit_tests::{
+        as_account_id, dummy_propose_settings, dummy_template_settings, get_context_builder,
+        get_default_contract, tm, ACC_1, FOUNDER_1, FOUNDER_2, FOUNDER_3, GROUP_1_NAME,
+        GROUP_2_NAME,
+    },
+    view::{AccountGroupView, GroupMemberView, ProposalView},
+};
+
+#[test]
+fn view_paged_lists() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    for proposer in [FOUNDER_1, FOUNDER_2, FOUNDER_1] {
+        testing_env!(ctx
+            .predecessor_account_id(as_account_id(proposer))
+            .attached_deposit(ONE_NEAR)
+            .build());
+        contract.proposal_create(
+            None,
+            1,
+            0,
+            dummy_propose_settings(),
+            Some(vec![dummy_template_settings()]),
+            None,
+        );
+    }
+
+    // Proposals.
+    let ids = |views: Vec<ProposalView>| -> Vec<u32> { views.into_iter().map(|v| v.id).collect() };
+    assert_eq!(
+        ids(contract.proposal_list(0, 10, None, None, None)),
+        vec![1, 2, 3]
+    );
+    assert_eq!(ids(contract.proposal_list(2, 1, None, None, None)), vec![2]);
+    assert_eq!(
+        ids(contract.proposal_list(1, 10, None, Some(as_account_id(FOUNDER_1)), None)),
+        vec![1, 3]
+    );
+    assert_eq!(
+        ids(contract.proposal_list(1, 10, Some(ProposalState::InProgress), None, Some(1))),
+        vec![1, 2, 3]
+    );
+    assert!(contract
+        .proposal_list(1, 10, Some(ProposalState::Accepted), None, None)
+        .is_empty());
+    assert!(contract
+        .proposal_list(1, 10, None, None, Some(2))
+        .is_empty());
+
+    // Workflow instances are created when proposal is finished.
+    assert!(contract.wf_instance_list(1, 10, None).is_empty());
+    let instance = Instance::new(1, 1, vec![1]);
+    contract.workflow_instance.insert(&2, &instance);
+    let instances = contract.wf_instance_list(1, 10, Some(InstanceState::Waiting));
+    assert_eq!(instances.len(), 1);
+    assert_eq!(instances[0].proposal_id, 2);
+    assert!(contract
+        .wf_instance_list(1, 10, Some(InstanceState::Running))
+        .is_empty());
+    assert_eq!(contract.wf_template_list(1, 10).len(), 1);
+
+    // Groups.
+    let groups = contract.group_list(2, 10);
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].id, 2);
+    assert_eq!(groups[0].group.settings.name, GROUP_2_NAME);
+
+    // Group members.
+    assert_eq!(
+        contract.group_member_list(1, 0, 10),
+        vec![
+            GroupMemberView {
+                account_id: as_account_id(FOUNDER_1),
+                tags: vec![0],
+                roles: vec![0, 1],
+                valid_to: None,
+            },
+            GroupMemberView {
+                account_id: as_account_id(FOUNDER_2),
+                tags: vec![1],
+                roles: vec![0],
+                valid_to: None,
+            },
+            GroupMemberView {
+                account_id: as_account_id(FOUNDER_3),
+                tags: vec![2],
+                roles: vec![0],
+                valid_to: None,
+            },
+        ]
+    );
+    let members = contract.group_member_list(1, 1, 1);
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].account_id, as_account_id(FOUNDER_2));
+    assert!(contract.group_member_list(3, 0, 10).is_empty());
+
+    // Media, limit is number of items.
+    for name in ["media_1", "media_2", "media_3"] {
+        contract.media_add(&Media {
+            proposal_id: None,
+            name: name.into(),
+            category: "test".into(),
+            r#type: ResourceType::Text("text".into()),
+            tags: vec![],
+            version: "1".into(),
+            valid: true,
+        });
+    }
+    let media_ids =
+        |list: Vec<(u32, Media)>| -> Vec<u32> { list.into_iter().map(|(id, _)| id).collect() };
+    assert_eq!(media_ids(contract.media_list(2, 1)), vec![2]);
+    assert_eq!(media_ids(contract.media_list(2, 10)), vec![2, 3]);
+    assert!(contract.media_list(4, 10).is_empty());
+}
+
+#[test]
+fn view_account_overview() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    testing_env!(ctx
+        .predecessor_account_id(as_account_id(FOUNDER_1))
+        .attached_deposit(ONE_NEAR)
+        .build());
+    let proposal_id = contract.proposal_create(
+        None,
+        1,
+        0,
+        dummy_propose_settings(),
+        Some(vec![dummy_template_settings()]),
+        None,
+    );
+    testing_env!(ctx.predecessor_account_id(as_account_id(FOUNDER_2)).build());
+    contract.proposal_vote(proposal_id, 1);
+
+    let overview = contract.account_overview(as_account_id(FOUNDER_1), 1, 100);
+    assert_eq!(
+        overview.groups,
+        vec![AccountGroupView {
+            id: 1,
+            name: GROUP_1_NAME.into(),
+            roles: vec![0, 1],
+            tags: vec![0],
+            valid_to: None,
+            leader: true,
+        }]
+    );
+    assert_eq!(overview.open_proposals, vec![proposal_id]);
+    assert!(overview.votes.is_empty());
+    assert!(overview.claimable_rewards.is_empty());
+
+    // Already voted and vote only once is required.
+    let overview = contract.account_overview(as_account_id(FOUNDER_2), 1, 100);
+    assert_eq!(
+        overview
+            .groups
+            .iter()
+            .map(|g| (g.id, g.roles.clone()))
+            .collect::<Vec<_>>(),
+        vec![(1, vec![0]), (2, vec![0])]
+    );
+    assert!(overview.open_proposals.is_empty());
+    assert_eq!(overview.votes, vec![(proposal_id, 1)]);
+
+    // Not a member of the voting group.
+    let overview = contract.account_overview(as_account_id(ACC_1), 1, 100);
+    assert!(!overview.groups.is_empty());
+    assert!(overview.open_proposals.is_empty());
+    assert!(overview.votes.is_empty());
+
+    // Voting ended.
+    let end = Proposal::from(contract.proposals.get(&proposal_id).unwrap()).end;
+    testing_env!(ctx.block_timestamp(tm(end + 1)).build());
+    let overview = contract.account_overview(as_account_id(FOUNDER_1), 1, 100);
+    assert!(overview.open_proposals.is_empty());
+
+    // Proposal outside of the scanned range.
+    let overview = contract.account_overview(as_account_id(FOUNDER_2), proposal_id + 1, 100);
+    assert!(overview.votes.is_empty());
+}
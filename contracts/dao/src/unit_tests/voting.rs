@@ -254,3 +254,76 @@ test_voting!(
     YES: 0,
     NO: 0
 );
+
+// ----- COMPOSITE RIGHTS -----
+test_voting!(
+    voting_democratic_group_1_min_stake;
+    ActivityRight::All(vec![ActivityRight::Group(1), ActivityRight::MinStake(200.into())]), Democratic;
+    FOUNDER_1 => 1,111 FOUNDER_2 => 1,222 FOUNDER_3 => 2,1  "guest_1.testnet" => 1,1 "guest_2.testnet" => 2,2 "guest_3.testnet" => 2,0;
+    TOTAL: 1,
+    SPAM: 0,
+    YES: 1,
+    NO: 0
+);
+test_voting!(
+    voting_democratic_group_1_except_leader;
+    ActivityRight::All(vec![ActivityRight::Group(1), ActivityRight::Not(Box::new(ActivityRight::GroupLeader(1)))]), Democratic;
+    FOUNDER_1 => 1,111 FOUNDER_2 => 1,222 FOUNDER_3 => 2,1  "guest_1.testnet" => 1,1 "guest_2.testnet" => 2,2 "guest_3.testnet" => 2,0;
+    TOTAL: 2,
+    SPAM: 0,
+    YES: 1,
+    NO: 1
+);
+test_voting!(
+    voting_democratic_group_1_tagged;
+    ActivityRight::MinTagged(1, 1), Democratic;
+    FOUNDER_1 => 1,111 FOUNDER_2 => 1,222 FOUNDER_3 => 2,1  "guest_1.testnet" => 1,1 "guest_2.testnet" => 2,2 "guest_3.testnet" => 2,0;
+    TOTAL: 1,
+    SPAM: 0,
+    YES: 1,
+    NO: 0
+);
+test_voting!(
+    voting_tokenweighted_group_1_tagged;
+    ActivityRight::MinTagged(1, 2), TokenWeighted;
+    FOUNDER_1 => 1,111 FOUNDER_2 => 1,222 FOUNDER_3 => 2,1  "guest_1.testnet" => 1,1 "guest_2.testnet" => 2,2 "guest_3.testnet" => 2,0;
+    TOTAL: 1,
+    SPAM: 0,
+    YES: 0,
+    NO: 1
+);
+test_voting!(
+    voting_tokenweighted_group_1_except_leader;
+    ActivityRight::All(vec![ActivityRight::Group(1), ActivityRight::Not(Box::new(ActivityRight::GroupLeader(1)))]), TokenWeighted;
+    FOUNDER_1 => 1,111 FOUNDER_2 => 1,222 FOUNDER_3 => 2,1  "guest_1.testnet" => 1,1 "guest_2.testnet" => 2,2 "guest_3.testnet" => 2,0;
+    TOTAL: 223,
+    SPAM: 0,
+    YES: 222,
+    NO: 1
+);
+
+#[test]
+#[should_panic]
+fn voting_composite_not_enumerable() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    update_template_settings_vote_rights(
+        &mut contract,
+        1,
+        0,
+        ActivityRight::Not(Box::new(ActivityRight::Group(1))),
+    );
+    testing_env!(ctx
+        .predecessor_account_id(as_account_id(FOUNDER_1))
+        .attached_deposit(ONE_NEAR)
+        .build());
+    contract.proposal_create(
+        None,
+        1,
+        0,
+        dummy_propose_settings(),
+        Some(vec![dummy_template_settings()]),
+        None,
+    );
+}
@@ -2,16 +2,24 @@ use std::collections::HashMap;
 
 use library::workflow::{
     action::{
-        ActionData, ActionInput, ActionInputType, DaoActionData, InputSource::User, TemplateAction,
+        ActionData, ActionInput, ActionInputType, DaoActionData, InputSource::User,
+        SubWorkflowData, TemplateAction,
     },
+    instance::{Instance, InstanceState},
     postprocessing::Postprocessing,
     runtime::activity_input::UserInput,
     types::DaoActionIdent,
 };
 
-use near_sdk::testing_env;
+use near_sdk::{testing_env, ONE_NEAR};
 
-use crate::unit_tests::{get_context_builder, get_default_contract};
+use crate::{
+    proposal::{Proposal, ProposalState},
+    unit_tests::{
+        as_account_id, dummy_propose_settings, dummy_template_settings, get_context_builder,
+        get_default_contract, FOUNDER_1,
+    },
+};
 
 fn test_event_action(optional: bool) -> TemplateAction {
     TemplateAction {
@@ -160,3 +168,126 @@ fn activity_check_activity_input_invalid() {
     let inputs = vec![action_input(), None, None, None];
     assert!(!contract.check_activity_input(&actions, &inputs, 0));
 }
+
+#[test]
+fn sub_workflow_create_and_resolve() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    testing_env!(ctx
+        .predecessor_account_id(as_account_id(FOUNDER_1))
+        .attached_deposit(ONE_NEAR)
+        .build());
+    let parent_id = contract.proposal_create(
+        None,
+        1,
+        0,
+        dummy_propose_settings(),
+        Some(vec![dummy_template_settings()]),
+        None,
+    );
+
+    // Parent instance awaits the sub workflow action.
+    let (template, settings) = contract.workflow_template.get(&1).unwrap();
+    let mut wfi = Instance::new(1, template.activities.len(), template.end.clone());
+    wfi.init_running(
+        template.transitions.as_slice(),
+        settings[0].transition_limits.as_slice(),
+    );
+    wfi.register_new_activity(1, 2, false);
+    wfi.await_promises(0, 1);
+    contract.workflow_instance.insert(&parent_id, &wfi);
+
+    let data = SubWorkflowData {
+        template_id: 1,
+        settings_id: 0,
+        propose_settings: dummy_propose_settings(),
+        binds: vec![],
+        skip_vote: true,
+    };
+    let mut deposit = ONE_NEAR;
+    let child_id = contract
+        .sub_workflow_create(
+            parent_id,
+            &as_account_id(FOUNDER_1),
+            data,
+            &HashMap::new(),
+            &mut deposit,
+        )
+        .unwrap();
+    // Storage of the child proposal is paid from the deposit.
+    assert!(deposit < ONE_NEAR);
+    let child: Proposal = contract.proposals.get(&child_id).unwrap().into();
+    assert_eq!(child.parent, Some(parent_id));
+    assert_eq!(child.state, ProposalState::Accepted);
+    assert_eq!(
+        contract
+            .workflow_instance
+            .get(&child_id)
+            .unwrap()
+            .get_state(),
+        InstanceState::Running
+    );
+
+    contract.sub_workflow_finished(child_id, true);
+    let child: Proposal = contract.proposals.get(&child_id).unwrap().into();
+    assert!(child.parent.is_none());
+    let wfi = contract.workflow_instance.get(&parent_id).unwrap();
+    assert_eq!(wfi.get_state(), InstanceState::Running);
+    assert_eq!(wfi.actions_done_count(), 1);
+}
+
+#[test]
+fn sub_workflow_storage_key_per_parent() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let mut propose_settings = dummy_propose_settings();
+    propose_settings.storage_key = Some("child".into());
+    for parent_id in [1, 2] {
+        let data = SubWorkflowData {
+            template_id: 1,
+            settings_id: 0,
+            propose_settings: propose_settings.clone(),
+            binds: vec![],
+            skip_vote: true,
+        };
+        let child_id = contract
+            .sub_workflow_create(
+                parent_id,
+                &as_account_id(FOUNDER_1),
+                data,
+                &HashMap::new(),
+                &mut ONE_NEAR.clone(),
+            )
+            .unwrap();
+        let key = format!("child-{}", parent_id);
+        assert_eq!(
+            contract
+                .workflow_propose_settings
+                .get(&child_id)
+                .unwrap()
+                .storage_key,
+            Some(key.clone())
+        );
+        assert!(contract.storage.get(&key).is_some());
+    }
+}
+
+#[test]
+#[should_panic(expected = "Not enough deposit to cover storage.")]
+fn sub_workflow_create_without_deposit() {
+    let mut ctx = get_context_builder();
+    testing_env!(ctx.build());
+    let mut contract = get_default_contract();
+    let data = SubWorkflowData {
+        template_id: 1,
+        settings_id: 0,
+        propose_settings: dummy_propose_settings(),
+        binds: vec![],
+        skip_vote: true,
+    };
+    contract
+        .sub_workflow_create(1, &as_account_id(FOUNDER_1), data, &HashMap::new(), &mut 0)
+        .unwrap();
+}
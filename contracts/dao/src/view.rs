@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use library::types::Value;
-use library::workflow::instance::Instance;
+use library::workflow::instance::{Instance, InstanceState};
 use library::workflow::settings::{ProposeSettings, TemplateSettings};
 use library::workflow::template::Template;
 use library::MethodName;
@@ -10,18 +10,18 @@ use near_sdk::serde::Serialize;
 use near_sdk::{env, near_bindgen, AccountId};
 
 use crate::constants::VERSION;
-use crate::group::Group;
+use crate::group::{Group, GroupTreeNode};
 use crate::internal::utils::current_timestamp_sec;
 use crate::media::Media;
-use crate::proposal::VersionedProposal;
+use crate::proposal::{Proposal, ProposalState, VersionedProposal};
 use crate::reward::Reward;
-use crate::role::{Roles, UserRoles};
+use crate::role::{RoleMetadata, Roles, UserRoles};
 use crate::settings::Settings;
 use crate::tags::Tags;
 use crate::treasury::{Asset, TreasuryPartition};
-use crate::wallet::{ClaimableReward, ClaimableRewards, Wallet};
+use crate::wallet::{ClaimableRewards, Wallet};
 use crate::{contract::*, StorageKey};
-use crate::{AssetId, TagCategory};
+use crate::{GroupId, ProposalId, RoleId, TagCategory, TagId, TimestampSec};
 
 #[near_bindgen]
 impl Contract {
@@ -35,7 +35,7 @@ impl Contract {
     }
 
     /// Return general statitstics about DAO.
-    pub fn statistics(self) -> Statistics {
+    pub fn statistics(&self) -> Statistics {
         Statistics {
             version: VERSION,
             total_delegation_amount: self.total_delegation_amount.into(),
@@ -64,72 +64,136 @@ impl Contract {
             .collect()
     }
 
-    pub fn settings(self) -> Settings {
+    /// Returns proposals with id in range `from_id..from_id + limit`
+    /// which match all provided filters.
+    pub fn proposal_list(
+        &self,
+        from_id: ProposalId,
+        limit: u32,
+        state: Option<ProposalState>,
+        created_by: Option<AccountId>,
+        workflow_id: Option<u16>,
+    ) -> Vec<ProposalView> {
+        id_range(from_id, limit, self.proposal_last_id)
+            .filter_map(|id| self.proposals.get(&id).map(|p| (id, Proposal::from(p))))
+            .filter(|(_, p)| state.map(|s| p.state == s).unwrap_or(true))
+            .filter(|(_, p)| {
+                created_by
+                    .as_ref()
+                    .map(|a| p.created_by == *a)
+                    .unwrap_or(true)
+            })
+            .filter(|(_, p)| workflow_id.map(|w| p.workflow_id == w).unwrap_or(true))
+            .map(|(id, proposal)| ProposalView { id, proposal })
+            .collect()
+    }
+
+    pub fn settings(&self) -> Settings {
         self.settings.get().unwrap().into()
     }
 
-    pub fn standard_fncall_names(self) -> Vec<MethodName> {
+    pub fn standard_fncall_names(&self) -> Vec<MethodName> {
         self.standard_function_call_metadata.keys().collect()
     }
 
-    pub fn wf_template(self, id: u16) -> Option<(Template, Vec<TemplateSettings>)> {
+    pub fn wf_template(&self, id: u16) -> Option<(Template, Vec<TemplateSettings>)> {
         self.workflow_template.get(&id)
     }
 
-    pub fn wf_templates(self) -> Vec<(u16, (Template, Vec<TemplateSettings>))> {
+    /// Deprecated: reads all templates at once, use `wf_template_list` instead.
+    pub fn wf_templates(&self) -> Vec<(u16, (Template, Vec<TemplateSettings>))> {
         self.workflow_template.to_vec()
     }
 
-    pub fn wf_instance(self, proposal_id: u32) -> Option<Instance> {
+    /// Returns templates with id in range `from_id..from_id + limit`.
+    pub fn wf_template_list(&self, from_id: u16, limit: u16) -> Vec<TemplateView> {
+        id_range(from_id, limit, self.workflow_last_id)
+            .filter_map(|id| {
+                self.workflow_template
+                    .get(&id)
+                    .map(|(template, settings)| TemplateView {
+                        id,
+                        template,
+                        settings,
+                    })
+            })
+            .collect()
+    }
+
+    pub fn wf_instance(&self, proposal_id: u32) -> Option<Instance> {
         self.workflow_instance.get(&proposal_id)
     }
 
-    pub fn wf_propose_settings(self, proposal_id: u32) -> Option<ProposeSettings> {
+    pub fn wf_propose_settings(&self, proposal_id: u32) -> Option<ProposeSettings> {
         self.workflow_propose_settings.get(&proposal_id)
     }
     pub fn wf_add_proposed_template_settings(
-        self,
+        &self,
         proposal_id: u32,
     ) -> Option<Vec<TemplateSettings>> {
         self.proposed_workflow_settings.get(&proposal_id)
     }
 
-    pub fn wf_instances(self) -> Vec<Option<Instance>> {
+    /// Deprecated: reads instances of all proposals at once, use `wf_instance_list` instead.
+    pub fn wf_instances(&self) -> Vec<Option<Instance>> {
         (1..=self.proposal_last_id)
             .into_iter()
             .map(|i| self.workflow_instance.get(&i))
             .collect()
     }
 
+    /// Returns workflow instances of proposals with id in range `from_id..from_id + limit`
+    /// optionally filtered by instance state.
+    pub fn wf_instance_list(
+        &self,
+        from_id: ProposalId,
+        limit: u32,
+        state: Option<InstanceState>,
+    ) -> Vec<InstanceView> {
+        id_range(from_id, limit, self.proposal_last_id)
+            .filter_map(|proposal_id| {
+                self.workflow_instance
+                    .get(&proposal_id)
+                    .map(|instance| InstanceView {
+                        proposal_id,
+                        instance,
+                    })
+            })
+            .filter(|view| {
+                state
+                    .map(|s| view.instance.get_state() == s)
+                    .unwrap_or(true)
+            })
+            .collect()
+    }
+
     // For debugging purposes only.
     pub fn debug_log(self) -> Vec<String> {
         self.debug_log
     }
     // For integration tests.
-    pub fn current_timestamp(self) -> u64 {
+    pub fn current_timestamp(&self) -> u64 {
         current_timestamp_sec()
     }
 
     pub fn reward(&self, id: u16) -> Option<Reward> {
         self.rewards.get(&id).map(|r| r.into())
     }
-    pub fn reward_list(self, from_id: u16, limit: u16) -> Vec<(u16, Reward)> {
-        let mut rewards = Vec::with_capacity(self.reward_last_id as usize);
-        for i in from_id..std::cmp::min(self.reward_last_id + 1, limit) {
-            if let Some(reward) = self.rewards.get(&i) {
-                rewards.push((i, reward.into()));
-            }
-        }
-        rewards
+    /// Returns rewards with id in range `from_id..from_id + limit`.
+    pub fn reward_list(&self, from_id: u16, limit: u16) -> Vec<(u16, Reward)> {
+        id_range(from_id, limit, self.reward_last_id)
+            .filter_map(|id| self.rewards.get(&id).map(|reward| (id, reward.into())))
+            .collect()
     }
-    pub fn partition_list(self, from_id: u16, limit: u16) -> Vec<(u16, TreasuryPartition)> {
-        let mut partitions = Vec::with_capacity(self.reward_last_id as usize);
-        for i in from_id..std::cmp::min(self.partition_last_id + 1, limit) {
-            if let Some(partition) = self.treasury_partition.get(&i) {
-                partitions.push((i, partition.into()));
-            }
-        }
-        partitions
+    /// Returns partitions with id in range `from_id..from_id + limit`.
+    pub fn partition_list(&self, from_id: u16, limit: u16) -> Vec<(u16, TreasuryPartition)> {
+        id_range(from_id, limit, self.partition_last_id)
+            .filter_map(|id| {
+                self.treasury_partition
+                    .get(&id)
+                    .map(|partition| (id, partition.into()))
+            })
+            .collect()
     }
     pub fn partition(&self, id: u16) -> Option<TreasuryPartition> {
         self.treasury_partition.get(&id).map(|p| p.into())
@@ -138,10 +202,47 @@ impl Contract {
         self.wallets.get(&account_id).map(|w| w.into())
     }
     pub fn user_roles(&self, account_id: AccountId) -> Option<UserRoles> {
-        self.user_roles.get(&account_id)
+        self.user_roles.get(&account_id).map(UserRoles::from)
+    }
+    /// Returns timestamps until which are the user roles valid.
+    /// Roles without record are valid forever.
+    pub fn user_roles_valid_to(
+        &self,
+        account_id: AccountId,
+    ) -> HashMap<GroupId, HashMap<RoleId, TimestampSec>> {
+        self.user_roles
+            .get(&account_id)
+            .map(|roles| UserRoles::from(roles).valid_to().to_owned())
+            .unwrap_or_default()
     }
     pub fn group_roles(&self, id: u16) -> Option<Roles> {
-        self.group_roles.get(&id)
+        self.group_roles.get(&id).map(Roles::from)
+    }
+    /// Returns roles of the group ordered by id with their metadata and not expired members.
+    /// Default group role with id 0 is not included.
+    pub fn group_role_list(&self, id: GroupId) -> Vec<RoleView> {
+        let (group, roles) = match (
+            self.groups.get(&id).map(Group::from),
+            self.group_roles.get(&id).map(Roles::from),
+        ) {
+            (Some(group), Some(roles)) => (group, roles),
+            _ => return vec![],
+        };
+        let mut role_list: Vec<RoleView> = roles
+            .iter()
+            .map(|(role_id, name)| {
+                let mut members = self.get_group_members_with_role(id, &group, *role_id);
+                members.sort();
+                RoleView {
+                    id: *role_id,
+                    name: name.to_owned(),
+                    metadata: roles.get_metadata(*role_id).cloned(),
+                    members,
+                }
+            })
+            .collect();
+        role_list.sort_by_key(|role| role.id);
+        role_list
     }
 
     #[allow(unused_variables)]
@@ -155,35 +256,124 @@ impl Contract {
         unimplemented!()
     }
 
-    pub fn groups(self) -> Vec<(u16, Group)> {
-        self.groups.to_vec()
+    /// Deprecated: reads all groups at once, use `group_list` instead.
+    pub fn groups(&self) -> Vec<(u16, Group)> {
+        self.groups
+            .iter()
+            .map(|(id, group)| (id, group.into()))
+            .collect()
     }
 
     pub fn group(&self, id: u16) -> Option<Group> {
-        self.groups.get(&id)
+        self.groups.get(&id).map(Group::from)
+    }
+
+    /// Returns groups with id in range `from_id..from_id + limit`.
+    pub fn group_list(&self, from_id: GroupId, limit: u16) -> Vec<GroupView> {
+        id_range(from_id, limit, self.group_last_id)
+            .filter_map(|id| {
+                self.groups
+                    .get(&id)
+                    .map(Group::from)
+                    .map(|group| GroupView { id, group })
+            })
+            .collect()
     }
 
-    pub fn tags(self, category: TagCategory) -> Option<Tags> {
+    /// Returns not expired group members ordered by account id with their not expired roles.
+    pub fn group_member_list(
+        &self,
+        id: GroupId,
+        from_index: u32,
+        limit: u32,
+    ) -> Vec<GroupMemberView> {
+        let group = match self.groups.get(&id).map(Group::from) {
+            Some(group) => group,
+            None => return vec![],
+        };
+        let mut accounts = group.get_members_accounts();
+        accounts.sort();
+        accounts
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|account_id| GroupMemberView {
+                tags: group.members.get(&account_id).cloned().unwrap_or_default(),
+                roles: self
+                    .user_roles
+                    .get(&account_id)
+                    .map(UserRoles::from)
+                    .map(|roles| roles.group_roles(id))
+                    .unwrap_or_default(),
+                valid_to: group.members_valid_to.get(&account_id).copied(),
+                account_id,
+            })
+            .collect()
+    }
+
+    /// Returns not expired members of the group tagged with the tag id.
+    pub fn group_members_with_tag(&self, id: GroupId, tag: TagId) -> Vec<AccountId> {
+        self.groups
+            .get(&id)
+            .map(Group::from)
+            .map(|group| group.get_members_with_tag(tag))
+            .unwrap_or_default()
+    }
+
+    /// Returns hierarchy of groups starting with groups without parent.
+    pub fn group_tree(&self) -> Vec<GroupTreeNode> {
+        let mut children: HashMap<GroupId, Vec<(GroupId, String)>> = HashMap::new();
+        for (id, group) in self.groups.iter() {
+            let group = Group::from(group);
+            children
+                .entry(group.settings.parent_group)
+                .or_default()
+                .push((id, group.settings.name));
+        }
+        GroupTreeNode::build(0, &mut children)
+    }
+
+    pub fn tags(&self, category: TagCategory) -> Option<Tags> {
         self.tags.get(&category)
     }
 
-    pub fn storage_bucket_data_all(self, bucket_id: StorageKey) -> Option<Vec<(String, Value)>> {
+    /// Deprecated: reads the whole bucket at once, use `storage_bucket_data_list` instead.
+    pub fn storage_bucket_data_all(&self, bucket_id: StorageKey) -> Option<Vec<(String, Value)>> {
         self.storage
             .get(&bucket_id)
             .map(|bucket| bucket.get_all_data())
     }
 
-    pub fn storage_buckets(self) -> Vec<StorageKey> {
+    /// Returns at most `limit` bucket entries starting at index `from_index`.
+    pub fn storage_bucket_data_list(
+        &self,
+        bucket_id: StorageKey,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<StorageDataView> {
+        self.storage
+            .get(&bucket_id)
+            .map(|bucket| {
+                bucket
+                    .get_data_list(from_index, limit)
+                    .into_iter()
+                    .map(|(key, value)| StorageDataView { key, value })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn storage_buckets(&self) -> Vec<StorageKey> {
         self.storage.keys_as_vector().to_vec()
     }
 
-    pub fn storage_bucket_data(self, bucket_id: StorageKey, data_id: String) -> Option<Value> {
+    pub fn storage_bucket_data(&self, bucket_id: StorageKey, data_id: String) -> Option<Value> {
         self.storage
             .get(&bucket_id)
             .and_then(|bucket| bucket.get_data(&data_id))
     }
 
-    pub fn wf_log(self, proposal_id: u32) -> Option<Vec<ActionLog>> {
+    pub fn wf_log(&self, proposal_id: u32) -> Option<Vec<ActionLog>> {
         self.workflow_activity_log.get(&proposal_id)
     }
     /// Calculate claimable rewards for `account_id`.
@@ -193,63 +383,115 @@ impl Contract {
             .get(&account_id)
             .expect("Wallet not found.")
             .into();
-        let mut claimable_rewards = Vec::with_capacity(4);
+        self.internal_claimable_rewards(&wallet)
+    }
+
+    /// Returns overview of the `account_id` in the DAO:
+    /// group memberships, vote weight, proposals to vote on, cast votes and rewards.
+    /// Only proposals with id in range `from_id..from_id + limit` are scanned for open proposals and votes.
+    pub fn account_overview(
+        &self,
+        account_id: AccountId,
+        from_id: ProposalId,
+        limit: u32,
+    ) -> AccountOverview {
+        let groups = self
+            .user_roles
+            .get(&account_id)
+            .map(UserRoles::from)
+            .map(|user_roles| {
+                user_roles
+                    .group_ids()
+                    .into_iter()
+                    .filter_map(|id| {
+                        self.groups
+                            .get(&id)
+                            .map(Group::from)
+                            .map(|group| (id, group))
+                    })
+                    .filter(|(_, group)| group.is_member(&account_id))
+                    .map(|(id, group)| AccountGroupView {
+                        id,
+                        roles: user_roles.group_roles(id),
+                        tags: group.members.get(&account_id).cloned().unwrap_or_default(),
+                        valid_to: group.members_valid_to.get(&account_id).copied(),
+                        leader: group.is_account_id_leader(&account_id),
+                        name: group.settings.name,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
         let current_timestamp = current_timestamp_sec();
-        let mut asset_cache: HashMap<AssetId, Asset> = HashMap::new();
-        for wallet_reward in wallet.rewards() {
-            if let Some(versioned_reward) = self.rewards.get(&wallet_reward.reward_id()) {
-                let reward: Reward = versioned_reward.into();
-                for (asset_id, _) in reward.reward_amounts().iter() {
-                    let (amount, _) = Contract::internal_claimable_reward_asset(
-                        &wallet,
-                        wallet_reward.reward_id(),
-                        &reward,
-                        *asset_id,
-                        current_timestamp,
-                    );
-                    let asset = if let Some(asset) = asset_cache.get(asset_id) {
-                        asset.clone()
+        let mut open_proposals = vec![];
+        let mut votes = vec![];
+        for id in id_range(from_id, limit, self.proposal_last_id) {
+            let proposal = match self.proposals.get(&id) {
+                Some(proposal) => Proposal::from(proposal),
+                None => continue,
+            };
+            let vote = proposal.votes.get(&account_id).copied();
+            if let Some(vote) = vote {
+                votes.push((id, vote));
+            }
+            if proposal.state != ProposalState::InProgress {
+                continue;
+            }
+            let (_, _, wfs) = self.get_workflow_and_proposal(id);
+            if proposal.end >= current_timestamp
+                && !(wfs.vote_only_once && vote.is_some())
+                && self.check_rights(&[wfs.allowed_voters], &account_id)
+            {
+                open_proposals.push(id);
+            }
+        }
+        let (claimable_rewards, failed_withdraws) = match self.wallets.get(&account_id) {
+            Some(wallet) => {
+                let rewards = self.internal_claimable_rewards(&wallet.into());
+                let mut amounts: Vec<(Asset, U128)> = vec![];
+                for reward in rewards.claimable_rewards {
+                    if let Some((_, amount)) = amounts.iter_mut().find(|(a, _)| *a == reward.asset)
+                    {
+                        amount.0 += reward.amount.0;
                     } else {
-                        let asset = self.cache_assets.get(asset_id).unwrap();
-                        asset_cache.insert(*asset_id, asset.clone());
-                        asset
-                    };
-                    claimable_rewards.push(ClaimableReward {
-                        asset,
-                        reward_id: wallet_reward.reward_id(),
-                        amount: amount.into(),
-                        partition_id: reward.partition_id,
-                    });
+                        amounts.push((reward.asset, reward.amount));
+                    }
                 }
+                (amounts, rewards.failed_withdraws)
             }
-        }
-        ClaimableRewards {
+            None => (vec![], vec![]),
+        };
+        AccountOverview {
+            groups,
+            vote_weight: self.delegations.get(&account_id).unwrap_or_default().into(),
+            open_proposals,
+            votes,
             claimable_rewards,
-            failed_withdraws: wallet
-                .failed_withdraws()
-                .iter()
-                .copied()
-                .map(|(id, v)| {
-                    let asset = asset_cache.remove(&id).unwrap_or_else(|| {
-                        self.cache_assets.get(&id).expect("fatal - asset not found")
-                    });
-                    (asset, v.into())
-                })
-                .collect(),
+            failed_withdraws,
         }
     }
 
-    pub fn media(self, id: u32) -> Option<Media> {
+    pub fn media(&self, id: u32) -> Option<Media> {
         self.media.get(&id)
     }
-    pub fn media_list(self, from_id: u32, limit: u32) -> Vec<(u32, Media)> {
-        let mut media_list = Vec::with_capacity(self.media_last_id as usize);
-        for i in from_id..std::cmp::min(self.media_last_id + 1, limit) {
-            if let Some(media) = self.media.get(&i) {
-                media_list.push((i, media));
-            }
-        }
-        media_list
+    /// Returns all media of the proposal in order they were added
+    /// including all description versions.
+    pub fn proposal_media(&self, id: u32) -> Vec<(u32, Media)> {
+        self.proposals
+            .get(&id)
+            .map(|p| {
+                Proposal::from(p)
+                    .media
+                    .into_iter()
+                    .filter_map(|media_id| self.media.get(&media_id).map(|m| (media_id, m)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    /// Returns media with id in range `from_id..from_id + limit`.
+    pub fn media_list(&self, from_id: u32, limit: u32) -> Vec<(u32, Media)> {
+        id_range(from_id, limit, self.media_last_id)
+            .filter_map(|id| self.media.get(&id).map(|media| (id, media)))
+            .collect()
     }
     pub fn registered_assets(&self) -> Vec<(u8, Asset)> {
         let mut assets = vec![];
@@ -275,3 +517,99 @@ pub struct Statistics {
     pub total_account_balance: U128,
     pub free_account_balance: U128,
 }
+
+/// Iterator over ids in range `from_id..from_id + limit` capped by `last_id`.
+fn id_range<T>(from_id: T, limit: T, last_id: T) -> impl Iterator<Item = T>
+where
+    T: Copy + Into<u64> + TryFrom<u64>,
+{
+    let from_id: u64 = from_id.into();
+    let to_id = std::cmp::min(from_id.saturating_add(limit.into()), last_id.into() + 1);
+    (from_id..to_id).filter_map(|id| T::try_from(id).ok())
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalView {
+    pub id: ProposalId,
+    pub proposal: Proposal,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GroupView {
+    pub id: GroupId,
+    pub group: Group,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct GroupMemberView {
+    pub account_id: AccountId,
+    pub tags: Vec<TagId>,
+    pub roles: Vec<RoleId>,
+    pub valid_to: Option<TimestampSec>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct InstanceView {
+    pub proposal_id: ProposalId,
+    pub instance: Instance,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TemplateView {
+    pub id: u16,
+    pub template: Template,
+    pub settings: Vec<TemplateSettings>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageDataView {
+    pub key: String,
+    pub value: Value,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountOverview {
+    /// Groups where the account is a member.
+    pub groups: Vec<AccountGroupView>,
+    pub vote_weight: U128,
+    /// Proposals in progress the account can vote on from the scanned range.
+    pub open_proposals: Vec<ProposalId>,
+    /// Votes cast by the account from the scanned range.
+    pub votes: Vec<(ProposalId, u8)>,
+    /// Sum of claimable amounts per asset.
+    pub claimable_rewards: Vec<(Asset, U128)>,
+    pub failed_withdraws: Vec<(Asset, U128)>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountGroupView {
+    pub id: GroupId,
+    pub name: String,
+    pub roles: Vec<RoleId>,
+    pub tags: Vec<TagId>,
+    pub valid_to: Option<TimestampSec>,
+    pub leader: bool,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoleView {
+    pub id: RoleId,
+    pub name: String,
+    pub metadata: Option<RoleMetadata>,
+    pub members: Vec<AccountId>,
+}
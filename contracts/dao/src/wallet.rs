@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use library::{derive_from_versioned, derive_into_versioned};
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
@@ -19,7 +21,7 @@ use crate::{
 
 #[ext_contract(ext_self)]
 trait CbWallet {
-    fn withdraw_check(account_id: AccountId, asset: Asset, amount: u128);
+    fn withdraw_check(account_id: AccountId, asset_id: u8, amount: u128);
 }
 
 derive_into_versioned!(Wallet, VersionedWallet, V1);
@@ -442,6 +444,53 @@ impl Contract {
             .map(|w| w.into())
             .unwrap_or_else(Wallet::new)
     }
+    /// Calculate claimable rewards and failed withdraws stored in the `wallet`.
+    pub fn internal_claimable_rewards(&self, wallet: &Wallet) -> ClaimableRewards {
+        let mut claimable_rewards = Vec::with_capacity(4);
+        let current_timestamp = current_timestamp_sec();
+        let mut asset_cache: HashMap<AssetId, Asset> = HashMap::new();
+        for wallet_reward in wallet.rewards() {
+            if let Some(versioned_reward) = self.rewards.get(&wallet_reward.reward_id()) {
+                let reward: Reward = versioned_reward.into();
+                for (asset_id, _) in reward.reward_amounts().iter() {
+                    let (amount, _) = Contract::internal_claimable_reward_asset(
+                        wallet,
+                        wallet_reward.reward_id(),
+                        &reward,
+                        *asset_id,
+                        current_timestamp,
+                    );
+                    let asset = if let Some(asset) = asset_cache.get(asset_id) {
+                        asset.clone()
+                    } else {
+                        let asset = self.cache_assets.get(asset_id).unwrap();
+                        asset_cache.insert(*asset_id, asset.clone());
+                        asset
+                    };
+                    claimable_rewards.push(ClaimableReward {
+                        asset,
+                        reward_id: wallet_reward.reward_id(),
+                        amount: amount.into(),
+                        partition_id: reward.partition_id,
+                    });
+                }
+            }
+        }
+        ClaimableRewards {
+            claimable_rewards,
+            failed_withdraws: wallet
+                .failed_withdraws()
+                .iter()
+                .copied()
+                .map(|(id, v)| {
+                    let asset = asset_cache.remove(&id).unwrap_or_else(|| {
+                        self.cache_assets.get(&id).expect("fatal - asset not found")
+                    });
+                    (asset, v.into())
+                })
+                .collect(),
+        }
+    }
     /// Return (claimable amount, amount per activity)
     /// that is possible to be withdrawn from `reward_id` at the moment.
     pub fn internal_claimable_reward_asset(
@@ -552,11 +601,7 @@ impl Contract {
                     .then(
                         ext_self::ext(env::current_account_id())
                             .with_static_gas(Gas(10 * 10u64.pow(12)))
-                            .withdraw_check(
-                                account_id,
-                                Asset::new_ft(ft.account_id, ft.decimals),
-                                amount,
-                            ),
+                            .withdraw_check(account_id, asset_id, amount),
                     );
             }
             Asset::Nft(nft) => {
@@ -574,11 +619,7 @@ impl Contract {
                     .then(
                         ext_self::ext(env::current_account_id())
                             .with_static_gas(Gas(10 * 10u64.pow(12)))
-                            .withdraw_check(
-                                account_id,
-                                Asset::new_nft(nft.account_id, nft.token_id, nft.approval_id),
-                                amount,
-                            ),
+                            .withdraw_check(account_id, asset_id, amount),
                     );
             }
         };
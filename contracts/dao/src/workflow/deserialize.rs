@@ -12,11 +12,12 @@ use crate::{
     group::{GroupInput, GroupMember, GroupSettings},
     media::{CIDInfo, Media, ResourceType},
     reward::{Reward, RewardType, RewardUserActivity, RewardWage},
-    role::MemberRoles,
+    role::{MemberRoles, RoleMetadata},
+    settings::{AdminRight, Settings},
     treasury::{
         Asset, AssetRegistrar, PartitionAssetInput, TreasuryPartition, TreasuryPartitionInput,
     },
-    AssetId, RoleId,
+    AssetId, RoleId, TagId,
 };
 
 use super::error::DeserializeError;
@@ -113,6 +114,7 @@ pub fn deser_reward(action_input: &mut dyn ActivityInput) -> Result<Reward, Dese
         .take("role_id")
         .ok_or_else(|| DeserializeError::MissingInputKey("role_id".into()))?
         .try_into_u64()? as u16;
+    let tag_id = deser_optional_u64("tag_id", action_input)?.unwrap_or_default() as u16;
     let partition_id = action_input
         .take("partition_id")
         .ok_or_else(|| DeserializeError::MissingInputKey("partition_id".into()))?
@@ -139,6 +141,7 @@ pub fn deser_reward(action_input: &mut dyn ActivityInput) -> Result<Reward, Dese
         name,
         group_id,
         role_id,
+        tag_id,
         partition_id,
         reward_object,
         reward_amounts,
@@ -246,7 +249,7 @@ pub fn deser_group_input(
     })
 }
 
-fn deser_group_settins(
+pub fn deser_group_settins(
     prefix: &str,
     action_input: &mut dyn ActivityInput,
 ) -> Result<GroupSettings, DeserializeError> {
@@ -295,8 +298,14 @@ pub fn deser_group_members(
             } else {
                 vec![]
             };
+            let key_valid_to = format!("{}.{}.valid_to", prefix, i);
+            let valid_to = deser_optional_u64(&key_valid_to, action_input)?;
 
-            members.push(GroupMember { account_id, tags });
+            members.push(GroupMember {
+                account_id,
+                tags,
+                valid_to,
+            });
             i += 1;
         } else {
             break;
@@ -304,6 +313,16 @@ pub fn deser_group_members(
     }
     Ok(members)
 }
+/// Missing key and null value are both deserialized as `None`.
+fn deser_optional_u64(
+    key: &str,
+    action_input: &mut dyn ActivityInput,
+) -> Result<Option<u64>, DeserializeError> {
+    match action_input.take(key) {
+        Some(v) if !v.is_null() => Ok(Some(v.try_into_u64()?)),
+        _ => Ok(None),
+    }
+}
 pub fn deser_member_roles(
     prefix: &str,
     action_input: &mut dyn ActivityInput,
@@ -328,7 +347,13 @@ pub fn deser_member_roles(
                     break;
                 }
             }
-            member_roles.push(MemberRoles { name, members });
+            let key_valid_to = format!("{}.{}.valid_to", prefix, i);
+            let valid_to = deser_optional_u64(&key_valid_to, action_input)?;
+            member_roles.push(MemberRoles {
+                name,
+                members,
+                valid_to,
+            });
             i += 1;
         } else {
             break;
@@ -337,6 +362,27 @@ pub fn deser_member_roles(
     Ok(member_roles)
 }
 
+/// Missing keys are deserialized as empty values.
+pub fn deser_role_metadata(
+    prefix: &str,
+    action_input: &mut dyn ActivityInput,
+) -> Result<RoleMetadata, DeserializeError> {
+    let key_description = format!("{}.description", prefix);
+    let description = match action_input.take(&key_description) {
+        Some(v) if !v.is_null() => v.try_into_string()?,
+        _ => String::new(),
+    };
+    let key_permissions = format!("{}.permissions", prefix);
+    let permissions = match action_input.take(&key_permissions) {
+        Some(v) if !v.is_null() => v.try_into_vec_string()?,
+        _ => vec![],
+    };
+    Ok(RoleMetadata {
+        description,
+        permissions,
+    })
+}
+
 pub fn deser_account_ids(
     prefix: &str,
     action_input: &mut dyn ActivityInput,
@@ -373,6 +419,20 @@ pub fn deser_roles_ids(
     }
 }
 
+pub fn deser_tag_ids(
+    prefix: &str,
+    action_input: &mut dyn ActivityInput,
+) -> Result<Vec<TagId>, DeserializeError> {
+    let tags = action_input
+        .take(prefix)
+        .ok_or_else(|| DeserializeError::MissingInputKey(prefix.into()))?
+        .try_into_vec_u64()?
+        .into_iter()
+        .map(|t| t as u16)
+        .collect();
+    Ok(tags)
+}
+
 pub fn deser_u64(
     prefix: &str,
     action_input: &mut dyn ActivityInput,
@@ -384,6 +444,36 @@ pub fn deser_u64(
     Ok(id)
 }
 
+pub fn deser_string(
+    prefix: &str,
+    action_input: &mut dyn ActivityInput,
+) -> Result<String, DeserializeError> {
+    let string = action_input
+        .take(prefix)
+        .ok_or_else(|| DeserializeError::MissingInputKey(prefix.into()))?
+        .try_into_string()?;
+    Ok(string)
+}
+
+pub fn deser_account_id(
+    prefix: &str,
+    action_input: &mut dyn ActivityInput,
+) -> Result<AccountId, DeserializeError> {
+    let account_id_string = deser_string(prefix, action_input)?;
+    Ok(AccountId::try_from(account_id_string)?)
+}
+
+/// Missing key and null value are both deserialized as `None`.
+pub fn deser_optional_account_id(
+    prefix: &str,
+    action_input: &mut dyn ActivityInput,
+) -> Result<Option<AccountId>, DeserializeError> {
+    match action_input.take(prefix) {
+        Some(v) if !v.is_null() => Ok(Some(AccountId::try_from(v.try_into_string()?)?)),
+        _ => Ok(None),
+    }
+}
+
 pub fn deser_u128(
     prefix: &str,
     action_input: &mut dyn ActivityInput,
@@ -518,3 +608,56 @@ pub fn deser_media_type(
     }
     Err(DeserializeError::MissingInputKey("media type".into()))
 }
+
+/// Deserialize DAO settings update.
+/// Missing keys keep values from `settings`.
+/// Vote token and staking cannot be changed.
+pub fn deser_dao_settings(
+    action_input: &mut dyn ActivityInput,
+    mut settings: Settings,
+) -> Result<Settings, DeserializeError> {
+    if let Some(v) = action_input.take("name") {
+        settings.name = v.try_into_string()?;
+    }
+    if let Some(v) = action_input.take("purpose") {
+        settings.purpose = v.try_into_string()?;
+    }
+    if let Some(v) = action_input.take("tags") {
+        settings.tags = v
+            .try_into_vec_u64()?
+            .into_iter()
+            .map(|t| t as u16)
+            .collect();
+    }
+    if let Some(v) = action_input.take("dao_admin_account_id") {
+        settings.dao_admin_account_id = AccountId::try_from(v.try_into_string()?)?;
+    }
+    if let Some(v) = action_input.take("dao_admin_rights") {
+        let mut rights = vec![];
+        for right in v.try_into_vec_string()? {
+            match right.as_str() {
+                "upgrade" => rights.push(AdminRight::Upgrade),
+                _ => return Err(DeserializeError::Conversion("dao admin right".into())),
+            }
+        }
+        settings.dao_admin_rights = rights;
+    }
+    if let Some(v) = action_input.take("workflow_provider") {
+        settings.workflow_provider = AccountId::try_from(v.try_into_string()?)?;
+    }
+    if let Some(v) = action_input.take("resource_provider") {
+        settings.resource_provider = if v.is_null() {
+            None
+        } else {
+            Some(AccountId::try_from(v.try_into_string()?)?)
+        };
+    }
+    if let Some(v) = action_input.take("scheduler") {
+        settings.scheduler = if v.is_null() {
+            None
+        } else {
+            Some(AccountId::try_from(v.try_into_string()?)?)
+        };
+    }
+    Ok(settings)
+}
@@ -27,16 +27,20 @@ use near_sdk::{
 };
 
 use super::deserialize::{
-    deser_account_ids, deser_group_input, deser_group_members, deser_media, deser_member_roles,
-    deser_partition, deser_reward, deser_roles_ids, deser_u128, deser_u64,
+    deser_account_id, deser_account_ids, deser_dao_settings, deser_group_input,
+    deser_group_members, deser_group_settins, deser_media, deser_member_roles,
+    deser_optional_account_id, deser_partition, deser_reward, deser_role_metadata, deser_roles_ids,
+    deser_string, deser_tag_ids, deser_u128, deser_u64,
 };
-use super::error::{ActionError, ActivityError};
+use super::error::{ActionError, ActivityError, DeserializeError, InternalDaoActionError};
 use crate::constants::GLOBAL_BUCKET_IDENT;
 use crate::contract::*;
+use crate::group::Group;
 use crate::internal::utils::current_timestamp_sec;
 use crate::internal::ActivityContext;
 use crate::proposal::ProposalState;
 use crate::reward::RewardActivity;
+use crate::role::UserRoles;
 use crate::treasury::AssetRegistrar;
 
 #[ext_contract(ext_self)]
@@ -220,7 +224,9 @@ impl Contract {
                     .take_global_storage()
                     .expect("Missing global storage"),
             );
-            wfi.new_actions_done(ctx.actions_done(), current_timestamp_sec());
+            if wfi.new_actions_done(ctx.actions_done(), current_timestamp_sec()) {
+                self.sub_workflow_finished(proposal_id, true);
+            }
             None
         } else {
             // Async cannot panic immediately.
@@ -315,6 +321,9 @@ impl Contract {
             ) && wfi.try_to_finish()
         {
             self.workflow_instance.insert(&proposal_id, &wfi);
+            if proposal.parent.is_some() {
+                self.sub_workflow_finished(proposal_id, wfi.get_state() == InstanceState::Finished);
+            }
             true
         } else {
             false
@@ -475,6 +484,45 @@ impl Contract {
                 }
             };
             let user_inputs = action_input.to_vec();
+            // Child workflow is awaited same way as dispatched promise.
+            if tpl_action.action_data.is_sub_workflow() {
+                let action_data = std::mem::replace(&mut tpl_action.action_data, ActionData::None)
+                    .try_into_sub_workflow_data()
+                    .ok_or_else(|| {
+                        ActionError::InvalidWfStructure("missing sub workflow data".into())
+                    })?;
+                if !validate(
+                    sources,
+                    tpl_action.validators.as_slice(),
+                    expressions,
+                    action_input.as_ref(),
+                )? {
+                    return Err(ActionError::Validation);
+                }
+                bind_input(
+                    sources,
+                    action_data.binds.as_slice(),
+                    expressions,
+                    action_input.as_mut(),
+                )?;
+                let caller = ctx.caller.clone();
+                self.sub_workflow_create(
+                    ctx.proposal_id,
+                    &caller,
+                    action_data,
+                    action_input.as_ref(),
+                    &mut ctx.attached_deposit,
+                )?;
+                self.log_action(
+                    ctx.proposal_id,
+                    env::predecessor_account_id(),
+                    ctx.activity_id as u8,
+                    idx as u8,
+                    user_inputs,
+                );
+                ctx.set_next_action_done();
+                continue;
+            }
             let new_promise = if tpl_action.action_data.is_fncall() {
                 let action_data = std::mem::replace(&mut tpl_action.action_data, ActionData::None)
                     .try_into_fncall_data()
@@ -611,7 +659,9 @@ impl Contract {
         } else {
             let timestamp = current_timestamp_sec();
             wfi.promise_success();
-            wfi.new_actions_done(1, timestamp);
+            if wfi.new_actions_done(1, timestamp) {
+                self.sub_workflow_finished(proposal_id, true);
+            }
         }
         self.workflow_instance.insert(&proposal_id, &wfi);
     }
@@ -675,7 +725,9 @@ impl Contract {
             }
         };
         wfi.promise_success();
-        wfi.new_actions_done(1, current_timestamp_sec());
+        if wfi.new_actions_done(1, current_timestamp_sec()) {
+            self.sub_workflow_finished(proposal_id, true);
+        }
         self.workflow_instance.insert(&proposal_id, &wfi);
     }
 
@@ -683,74 +735,72 @@ impl Contract {
         if rights.is_empty() {
             return true;
         }
-        for right in rights.iter() {
-            match right {
-                ActivityRight::Anyone => {
-                    return true;
-                }
-                ActivityRight::Group(g) => match self.groups.get(g) {
-                    Some(group) => {
-                        if group.is_member(account_id) {
-                            return true;
-                        } else {
-                            continue;
-                        }
-                    }
-                    _ => continue,
-                },
-                ActivityRight::GroupMember(g, name) => {
-                    if *name != *account_id {
-                        continue;
-                    }
-                    match self.groups.get(g) {
-                        Some(group) => match group.is_member(account_id) {
-                            true => return true,
-                            false => continue,
-                        },
-                        _ => continue,
-                    }
-                }
-                ActivityRight::TokenHolder => {
-                    if self.delegations.get(account_id).unwrap_or(0) > 0 {
-                        return true;
-                    } else {
-                        continue;
-                    }
-                }
-                ActivityRight::GroupRole(g, r) => match self.user_roles.get(account_id) {
-                    Some(roles) => {
-                        if roles.has_group_role(*g, *r) {
-                            return true;
-                        } else {
-                            continue;
-                        }
-                    }
-                    _ => continue,
-                },
-                ActivityRight::GroupLeader(g) => match self.groups.get(g) {
-                    Some(group) => {
-                        if group.is_account_id_leader(account_id) {
-                            return true;
-                        } else {
-                            continue;
-                        }
-                    }
-                    _ => continue,
-                },
-                ActivityRight::Member => {
-                    if self.user_roles.get(account_id).is_some() {
-                        return true;
-                    } else {
-                        continue;
-                    }
-                }
-                ActivityRight::Account(a) => match *a == *account_id {
-                    true => return true,
-                    false => continue,
-                },
+        rights
+            .iter()
+            .any(|right| self.check_right(right, account_id))
+    }
+
+    /// Checks if account has the right.
+    /// Composite rights are evaluated recursively.
+    pub fn check_right(&self, right: &ActivityRight, account_id: &AccountId) -> bool {
+        match right {
+            ActivityRight::Anyone => true,
+            ActivityRight::Group(g) => self
+                .groups
+                .get(g)
+                .map(Group::from)
+                .map(|group| group.is_member(account_id))
+                .unwrap_or_default(),
+            ActivityRight::GroupMember(g, name) => {
+                *name == *account_id
+                    && self
+                        .groups
+                        .get(g)
+                        .map(Group::from)
+                        .map(|group| group.is_member(account_id))
+                        .unwrap_or_default()
             }
+            ActivityRight::TokenHolder => self.delegations.get(account_id).unwrap_or(0) > 0,
+            ActivityRight::GroupRole(g, r) => self
+                .user_roles
+                .get(account_id)
+                .map(UserRoles::from)
+                .map(|roles| roles.has_group_role(*g, *r))
+                .unwrap_or_default(),
+            ActivityRight::GroupLeader(g) => self
+                .groups
+                .get(g)
+                .map(Group::from)
+                .map(|group| group.is_account_id_leader(account_id))
+                .unwrap_or_default(),
+            ActivityRight::Member => self
+                .user_roles
+                .get(account_id)
+                .map(UserRoles::from)
+                .map(|roles| roles.is_active())
+                .unwrap_or_default(),
+            ActivityRight::Account(a) => *a == *account_id,
+            ActivityRight::All(rights) => rights
+                .iter()
+                .all(|right| self.check_right(right, account_id)),
+            ActivityRight::Not(right) => !self.check_right(right, account_id),
+            ActivityRight::MinStake(amount) => {
+                self.delegations.get(account_id).unwrap_or(0) >= amount.0
+            }
+            ActivityRight::MinTagged(g, t) => self
+                .groups
+                .get(g)
+                .map(Group::from)
+                .map(|group| group.is_member_tagged(account_id, *t))
+                .unwrap_or_default(),
+            ActivityRight::ParentOf(g) => self.group_ancestors(*g).into_iter().any(|id| {
+                self.groups
+                    .get(&id)
+                    .map(Group::from)
+                    .map(|group| group.is_member(account_id))
+                    .unwrap_or_default()
+            }),
         }
-        false
     }
 
     /// Checks if inputs structure is same as activity definition.
@@ -809,11 +859,11 @@ impl Contract {
             }
             DaoActionIdent::GroupAdd => {
                 let group = deser_group_input(inputs)?;
-                self.group_add(group);
+                self.group_add(group)?;
             }
             DaoActionIdent::GroupRemove => {
                 let id = deser_u64("id", inputs)? as u16;
-                self.group_remove(id);
+                self.group_remove(id)?;
             }
             DaoActionIdent::GroupAddMembers => {
                 let id = deser_u64("id", inputs)? as u16;
@@ -845,10 +895,86 @@ impl Contract {
                 let media = deser_media("media", inputs)?;
                 self.media_update(id, &media);
             }
+            DaoActionIdent::MediaRemove => {
+                let id = deser_u64("id", inputs)? as u32;
+                self.media_remove(id)
+                    .ok_or_else(|| InternalDaoActionError("media not found".into()))?;
+            }
+            DaoActionIdent::GroupSetLeader => {
+                let id = deser_u64("id", inputs)? as u16;
+                let leader = deser_optional_account_id("leader", inputs)?;
+                self.group_set_leader(id, leader)?;
+            }
+            DaoActionIdent::GroupAddMemberTags => {
+                let id = deser_u64("id", inputs)? as u16;
+                let account_id = deser_account_id("account_id", inputs)?;
+                let tags = deser_tag_ids("tags", inputs)?;
+                self.group_add_member_tags(id, &account_id, tags)?;
+            }
+            DaoActionIdent::GroupRemoveMemberTags => {
+                let id = deser_u64("id", inputs)? as u16;
+                let account_id = deser_account_id("account_id", inputs)?;
+                let tags = deser_tag_ids("tags", inputs)?;
+                self.group_remove_member_tags(id, &account_id, tags)?;
+            }
+            DaoActionIdent::GroupAddRole => {
+                let id = deser_u64("id", inputs)? as u16;
+                let name = deser_string("name", inputs)?;
+                let metadata = deser_role_metadata("metadata", inputs)?;
+                self.group_add_role(id, name, metadata)?;
+            }
+            DaoActionIdent::GroupRenameRole => {
+                let id = deser_u64("id", inputs)? as u16;
+                let role_id = deser_u64("role_id", inputs)? as u16;
+                let name = deser_string("name", inputs)?;
+                self.group_rename_role(id, role_id, name)?;
+            }
+            DaoActionIdent::GroupDescribeRole => {
+                let id = deser_u64("id", inputs)? as u16;
+                let role_id = deser_u64("role_id", inputs)? as u16;
+                let metadata = deser_role_metadata("metadata", inputs)?;
+                self.group_describe_role(id, role_id, metadata)?;
+            }
+            DaoActionIdent::GroupUpdate => {
+                let id = deser_u64("id", inputs)? as u16;
+                let settings = deser_group_settins("settings", inputs)?;
+                self.group_update(id, settings)?;
+            }
+            DaoActionIdent::SettingsUpdate => {
+                let settings = deser_dao_settings(inputs, self.settings.get().unwrap().into())?;
+                self.settings_update(settings);
+            }
             DaoActionIdent::TagAdd => {
-                todo!();
+                let category = deser_string("category", inputs)?;
+                let values = inputs
+                    .take("values")
+                    .ok_or_else(|| DeserializeError::MissingInputKey("values".into()))?
+                    .try_into_vec_string()?;
+                self.tag_add(category, values);
+            }
+            DaoActionIdent::TagUpdate => {
+                let category = deser_string("category", inputs)?;
+                let id = deser_u64("id", inputs)? as u16;
+                let value = deser_string("value", inputs)?;
+                if !self.tag_edit(category, id, value) {
+                    return Err(InternalDaoActionError("tag category not found".into()).into());
+                }
+            }
+            DaoActionIdent::TagRemove => {
+                let category = deser_string("category", inputs)?;
+                let id = deser_u64("id", inputs)? as u16;
+                if !self.tag_remove(category, id) {
+                    return Err(InternalDaoActionError("tag category not found".into()).into());
+                }
+            }
+            DaoActionIdent::FtDistribute => {
+                let partition_id = deser_u64("partition_id", inputs)? as u16;
+                let group_id = deser_u64("group_id", inputs)? as u16;
+                let role_id = deser_u64("role_id", inputs).unwrap_or(0) as u16;
+                let amount = deser_u128("amount", inputs)?;
+                self.ft_distribute(partition_id, group_id, role_id, amount)?;
             }
-            _ => unreachable!(),
+            DaoActionIdent::WorkflowAdd | DaoActionIdent::Event => unreachable!(),
         }
         Ok(())
     }
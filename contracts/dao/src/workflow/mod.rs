@@ -1,5 +1,6 @@
 mod deserialize;
 mod error;
 pub mod execution;
+pub mod sub_workflow;
 
 pub use error::InternalDaoActionError;
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use library::workflow::{
+    action::SubWorkflowData,
+    instance::{Instance, InstanceState},
+    runtime::activity_input::ActivityInput,
+    template::SourceDataVariant,
+};
+use near_sdk::{env, AccountId, Balance};
+
+use super::error::ActionError;
+use crate::contract::Contract;
+use crate::internal::utils::{current_timestamp_sec, storage_cost};
+use crate::proposal::{Proposal, ProposalState};
+use crate::ProposalId;
+
+impl Contract {
+    /// Creates child proposal of the `parent_id` proposal.
+    /// Values in `input` are merged into child's propose settings constants.
+    /// If `skip_vote` is set, child proposal is accepted and its instance is running immediately.
+    /// Otherwise child proposal must be voted as any other proposal.
+    /// Storage of the child proposal is paid from the remaining `deposit` of the activity.
+    /// Returns id of the child proposal.
+    pub fn sub_workflow_create(
+        &mut self,
+        parent_id: ProposalId,
+        caller: &AccountId,
+        data: SubWorkflowData,
+        input: &dyn ActivityInput,
+        deposit: &mut Balance,
+    ) -> Result<ProposalId, ActionError> {
+        let storage_before = env::storage_usage();
+        let SubWorkflowData {
+            template_id,
+            settings_id,
+            mut propose_settings,
+            skip_vote,
+            ..
+        } = data;
+        let (wft, mut wfs) = self.workflow_template.get(&template_id).ok_or_else(|| {
+            ActionError::InvalidWfStructure("sub workflow template not found".into())
+        })?;
+        if settings_id as usize >= wfs.len() {
+            return Err(ActionError::InvalidWfStructure(
+                "sub workflow template settings not found".into(),
+            ));
+        }
+        let settings = wfs.swap_remove(settings_id as usize);
+        if propose_settings.activity_constants.len() != wft.activities.len() {
+            return Err(ActionError::InvalidWfStructure(
+                "sub workflow activity constants do not match template activities".into(),
+            ));
+        }
+        // Child storage key is derived from the parent so one template can be spawned by several parents.
+        if let Some(key) = propose_settings.storage_key.take() {
+            propose_settings.storage_key = Some(format!("{}-{}", key, parent_id));
+        }
+        let storage_key_valid = match propose_settings.storage_key {
+            Some(ref key) => self.storage.get(key).is_none(),
+            None => false,
+        };
+        if wft.need_storage && !storage_key_valid {
+            return Err(ActionError::InvalidWfStructure(
+                "sub workflow storage key is missing or already exists".into(),
+            ));
+        }
+
+        // Merge binded values into child's constants.
+        let mut constants = match propose_settings.constants.take() {
+            Some(SourceDataVariant::Map(map)) => map,
+            None => HashMap::new(),
+        };
+        for (key, value) in input.to_vec() {
+            constants.insert(key, value);
+        }
+        propose_settings.constants = Some(SourceDataVariant::Map(constants));
+
+        self.proposal_last_id += 1;
+        let created = current_timestamp_sec();
+        let mut proposal = Proposal::new(
+            0,
+            created,
+            caller.to_owned(),
+            created + settings.duration as u64,
+            template_id,
+            settings_id,
+        );
+        proposal.parent = Some(parent_id);
+        if skip_vote {
+            proposal.state = ProposalState::Accepted;
+            let mut instance = Instance::new(template_id, wft.activities.len(), wft.end.clone());
+            instance.init_running(
+                wft.transitions.as_slice(),
+                settings.transition_limits.as_slice(),
+            );
+            self.workflow_instance
+                .insert(&self.proposal_last_id, &instance);
+            if let Some(ref storage_key) = propose_settings.storage_key {
+                self.storage_bucket_add(storage_key);
+            }
+        }
+        self.proposals
+            .insert(&self.proposal_last_id, &proposal.into());
+        self.workflow_propose_settings
+            .insert(&self.proposal_last_id, &propose_settings);
+        *deposit = deposit
+            .checked_sub(storage_cost(storage_before))
+            .expect("Not enough deposit to cover storage.");
+        Ok(self.proposal_last_id)
+    }
+
+    /// Unlinks child proposal from its parent and resolves awaiting parent instance.
+    /// Does nothing if the proposal has no parent.
+    pub fn sub_workflow_finished(&mut self, child_id: ProposalId, success: bool) {
+        let mut proposal = match self.proposals.get(&child_id) {
+            Some(p) => Proposal::from(p),
+            None => return,
+        };
+        if let Some(parent_id) = proposal.parent.take() {
+            self.proposals.insert(&child_id, &proposal.into());
+            self.sub_workflow_resolve(parent_id, child_id, success);
+        }
+    }
+
+    /// Resolves parent instance awaiting the child workflow.
+    /// On success all values from child's storage are returned to the parent's storage
+    /// and the parent's action is marked as done.
+    /// Otherwise parent's activity is handled same as failed promise.
+    pub fn sub_workflow_resolve(
+        &mut self,
+        parent_id: ProposalId,
+        child_id: ProposalId,
+        success: bool,
+    ) {
+        let mut wfi = match self.workflow_instance.get(&parent_id) {
+            Some(wfi) => wfi,
+            None => return,
+        };
+        if wfi.get_state() != InstanceState::Awaiting {
+            return;
+        }
+        if success {
+            let child_key = self
+                .workflow_propose_settings
+                .get(&child_id)
+                .and_then(|s| s.storage_key);
+            let parent_key = self
+                .workflow_propose_settings
+                .get(&parent_id)
+                .and_then(|s| s.storage_key);
+            if let (Some(child_key), Some(parent_key)) = (child_key, parent_key) {
+                if let (Some(child_storage), Some(mut parent_storage)) =
+                    (self.storage.get(&child_key), self.storage.get(&parent_key))
+                {
+                    for (key, value) in child_storage.get_all_data() {
+                        parent_storage.add_data(&key, &value);
+                    }
+                    self.storage.insert(&parent_key, &parent_storage);
+                }
+            }
+            wfi.promise_success();
+            if wfi.new_actions_done(1, current_timestamp_sec()) {
+                self.sub_workflow_finished(parent_id, true);
+            }
+        } else {
+            wfi.promise_failed();
+        }
+        self.workflow_instance.insert(&parent_id, &wfi);
+    }
+}
@@ -1,5 +1,5 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::UnorderedMap;
+use near_sdk::collections::{LookupMap, UnorderedMap};
 use near_sdk::json_types::{Base64VecU8, U128};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
@@ -18,7 +18,9 @@ const CREATE_CALL_GAS: Gas = Gas(150_000_000_000_000);
 const ON_CREATE_CALL_GAS: Gas = Gas(30_000_000_000_000);
 
 const DEPOSIT_CREATE: u128 = 10_000_000_000_000_000_000_000_000;
-const MAX_DAO_VERSIONS: u8 = 5;
+
+/// Migration and upgrade binary of one dao version.
+pub type VersionBlobs = (Vec<u8>, Vec<u8>);
 
 #[derive(Serialize, Deserialize, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
@@ -44,6 +46,18 @@ pub trait ExtSelf {
 #[derive(BorshStorageKey, BorshSerialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 pub enum StorageKeys {
+    Daos,
+    Versions,
+    VersionMetadata,
+    PendingMigration,
+}
+
+/// Storage keys of the contract state before versions were stored in `versions`.
+/// Version blobs were kept in 5 rotating slots, the first version in `CurrentVersion`.
+#[derive(BorshStorageKey, BorshSerialize, Clone, Copy)]
+enum OldStorageKeys {
+    /// Keeps serialized index of the following keys.
+    #[allow(dead_code)]
     Daos,
     CurrentVersion,
     LatestVersion,
@@ -58,49 +72,94 @@ pub enum StorageKeys {
     V5Migration,
     V5Upgrade,
 }
+
+impl OldStorageKeys {
+    /// Migration and upgrade slot of `version`.
+    fn version_slots(version: u8) -> (Self, Self) {
+        match version % 5 {
+            1 => (Self::V1Migration, Self::V1Upgrade),
+            2 => (Self::V2Migration, Self::V2Upgrade),
+            3 => (Self::V3Migration, Self::V3Upgrade),
+            4 => (Self::V4Migration, Self::V4Upgrade),
+            0 => (Self::V5Migration, Self::V5Upgrade),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Contract state before versions were stored in `versions`.
+#[derive(BorshDeserialize)]
+#[cfg_attr(test, derive(BorshSerialize))]
+pub struct OldContract {
+    pub daos: UnorderedMap<AccountId, DaoInfo>,
+    pub tags: Vec<String>,
+    pub latest_migration_version_idx: u8,
+    pub latest_upgrade_version_idx: u8,
+    pub version_count: u8,
+}
+
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
     pub daos: UnorderedMap<AccountId, DaoInfo>,
     pub tags: Vec<String>,
-    pub latest_migration_version_idx: u8,
-    pub latest_upgrade_version_idx: u8,
+    /// Latest dao version available.
     pub version_count: u8,
+    pub versions: LookupMap<u8, VersionBlobs>,
+    pub version_metadata: LookupMap<u8, VersionMetadata>,
 }
 
 #[near_bindgen]
 impl Contract {
     #[init]
-    pub fn new(tags: Vec<String>) -> Self {
-        env::storage_write(
-            &StorageKeys::CurrentVersion.into_storage_key(),
-            NEWEST_DAO_VERSION,
-        );
-        Self {
+    pub fn new(tags: Vec<String>, version_metadata: Option<VersionMetadata>) -> Self {
+        let mut factory = Self {
             daos: UnorderedMap::new(StorageKeys::Daos),
-            latest_upgrade_version_idx: 1,
-            latest_migration_version_idx: 1,
-            version_count: 1,
+            version_count: 0,
+            versions: LookupMap::new(StorageKeys::Versions),
+            version_metadata: LookupMap::new(StorageKeys::VersionMetadata),
             tags,
-        }
+        };
+        factory.add_version(
+            MIGRATION_BLOB.to_vec(),
+            NEWEST_DAO_VERSION.to_vec(),
+            version_metadata.unwrap_or_else(|| VersionMetadata {
+                semver: "1.0.0".to_string(),
+                changelog_hash: None,
+                required_version: 0,
+            }),
+        );
+        factory
     }
 
+    /// Migration binary is stored as pending until upgrade binary is provided.
+    /// Both then make new dao version described by `version_metadata`.
+    /// New migration binary replaces the pending one.
+    /// State with versions stored in the old slots is converted first.
     #[private]
     #[init(ignore_state)]
-    pub fn migrate(r#type: MigrationType) -> Self {
-        let mut factory: Contract = env::state_read().expect("failed to read contract state");
-        if r#type != MigrationType::OnlyMigration {
-            let is_upgrade = r#type == MigrationType::NewUpgradeBin;
-            let key = factory.update_version_and_get_slot(is_upgrade);
-            if is_upgrade {
-                env::storage_write(&key.into_storage_key(), NEWEST_DAO_VERSION);
-            } else {
-                env::storage_write(&key.into_storage_key(), MIGRATION_BLOB);
+    pub fn migrate(r#type: MigrationType, version_metadata: Option<VersionMetadata>) -> Self {
+        let state = env::storage_read(b"STATE").expect("failed to read contract state");
+        let mut factory = match OldContract::try_from_slice(&state) {
+            Ok(old) => Self::migrate_old_contract(old),
+            Err(_) => {
+                Contract::try_from_slice(&state).expect("failed to deserialize contract state")
+            }
+        };
+        let pending_key = StorageKeys::PendingMigration.into_storage_key();
+        match r#type {
+            MigrationType::OnlyMigration => {}
+            MigrationType::NewMigrationBin => {
+                env::storage_write(&pending_key, MIGRATION_BLOB);
             }
-            if factory.latest_migration_version_idx == factory.latest_upgrade_version_idx {
-                env::storage_write(
-                    &StorageKeys::LatestVersion.into_storage_key(),
-                    &factory.version_count.to_le_bytes(),
+            MigrationType::NewUpgradeBin => {
+                let migration_bin =
+                    env::storage_read(&pending_key).expect("load next migration bin first");
+                env::storage_remove(&pending_key);
+                factory.add_version(
+                    migration_bin,
+                    NEWEST_DAO_VERSION.to_vec(),
+                    version_metadata.expect("missing version metadata"),
                 );
             }
         }
@@ -122,10 +181,27 @@ impl Contract {
     pub fn get_stats(self) -> FactoryStats {
         FactoryStats {
             latest_dao_version: self.version_count,
-            migration_versions: self.latest_migration_version_idx,
-            upgrade_versions: self.latest_upgrade_version_idx,
-            versions_stored: MAX_DAO_VERSIONS,
+            latest_dao_semver: self
+                .version_metadata
+                .get(&self.version_count)
+                .expect("version not found")
+                .semver,
+        }
+    }
+
+    pub fn get_version_metadata(&self, version: u8) -> Option<VersionMetadata> {
+        self.version_metadata.get(&version)
+    }
+
+    /// Versions a dao on `version` goes through to get to the latest one.
+    pub fn get_upgrade_path(&self, version: u8) -> Vec<u8> {
+        let mut path = vec![];
+        let mut current = version;
+        while let Some(next) = self.next_version(current) {
+            path.push(next);
+            current = next;
         }
+        path
     }
 
     #[payable]
@@ -190,82 +266,131 @@ impl Contract {
 
     /// Removes all version blobs so we can delete factory account
     #[private]
-    pub fn clean_self() {
-        env::storage_remove(&StorageKeys::V1Migration.into_storage_key());
-        env::storage_remove(&StorageKeys::V1Upgrade.into_storage_key());
-        env::storage_remove(&StorageKeys::V2Migration.into_storage_key());
-        env::storage_remove(&StorageKeys::V2Upgrade.into_storage_key());
-        env::storage_remove(&StorageKeys::V3Migration.into_storage_key());
-        env::storage_remove(&StorageKeys::V3Upgrade.into_storage_key());
-        env::storage_remove(&StorageKeys::V4Migration.into_storage_key());
-        env::storage_remove(&StorageKeys::V4Upgrade.into_storage_key());
-        env::storage_remove(&StorageKeys::V5Migration.into_storage_key());
-        env::storage_remove(&StorageKeys::V5Upgrade.into_storage_key());
+    pub fn clean_self(&mut self) {
+        for version in 1..=self.version_count {
+            self.versions.remove(&version);
+            self.version_metadata.remove(&version);
+        }
+        env::storage_remove(&StorageKeys::PendingMigration.into_storage_key());
     }
 }
 
 impl Contract {
-    pub fn update_version_and_get_slot(&mut self, upgrade: bool) -> StorageKeys {
-        if upgrade {
-            self.latest_upgrade_version_idx += 1;
+    /// Moves versions still stored in the old slots into `versions`.
+    /// Legacy versions allow upgrade only from the previous one as before.
+    fn migrate_old_contract(old: OldContract) -> Self {
+        let pending_migration = if old.latest_migration_version_idx > old.latest_upgrade_version_idx
+        {
+            let (slot, _) = OldStorageKeys::version_slots(old.latest_migration_version_idx);
+            env::storage_read(&slot.into_storage_key())
         } else {
-            self.latest_migration_version_idx += 1;
+            None
+        };
+        // Pending migration binary took the slot of the oldest version.
+        let kept_versions = if pending_migration.is_some() { 4 } else { 5 };
+        let first_version = old.version_count.saturating_sub(kept_versions - 1).max(1);
+        let blobs: Vec<(u8, VersionBlobs)> = (first_version..=old.version_count)
+            .filter_map(|version| {
+                let blobs = if version == 1 {
+                    env::storage_read(&OldStorageKeys::CurrentVersion.into_storage_key())
+                        .map(|upgrade_bin| (MIGRATION_BLOB.to_vec(), upgrade_bin))
+                } else {
+                    let (migration_slot, upgrade_slot) = OldStorageKeys::version_slots(version);
+                    env::storage_read(&migration_slot.into_storage_key())
+                        .zip(env::storage_read(&upgrade_slot.into_storage_key()))
+                };
+                blobs.map(|blobs| (version, blobs))
+            })
+            .collect();
+        // Old keys overlap with the new ones.
+        for key in [
+            OldStorageKeys::CurrentVersion,
+            OldStorageKeys::LatestVersion,
+            OldStorageKeys::V1Migration,
+            OldStorageKeys::V1Upgrade,
+            OldStorageKeys::V2Migration,
+            OldStorageKeys::V2Upgrade,
+            OldStorageKeys::V3Migration,
+            OldStorageKeys::V3Upgrade,
+            OldStorageKeys::V4Migration,
+            OldStorageKeys::V4Upgrade,
+            OldStorageKeys::V5Migration,
+            OldStorageKeys::V5Upgrade,
+        ] {
+            env::storage_remove(&key.into_storage_key());
         }
 
-        if self.latest_migration_version_idx == self.latest_upgrade_version_idx {
-            self.version_count += 1;
+        let mut factory = Self {
+            daos: old.daos,
+            version_count: old.version_count,
+            versions: LookupMap::new(StorageKeys::Versions),
+            version_metadata: LookupMap::new(StorageKeys::VersionMetadata),
+            tags: old.tags,
+        };
+        for (version, blobs) in blobs {
+            factory.versions.insert(&version, &blobs);
+            factory.version_metadata.insert(
+                &version,
+                &VersionMetadata {
+                    semver: format!("1.{}.0", version - 1),
+                    changelog_hash: None,
+                    required_version: version - 1,
+                },
+            );
+        }
+        if let Some(migration_bin) = pending_migration {
+            env::storage_write(
+                &StorageKeys::PendingMigration.into_storage_key(),
+                &migration_bin,
+            );
         }
+        factory
+    }
+
+    /// Stores binaries as the next dao version.
+    pub fn add_version(
+        &mut self,
+        migration_bin: Vec<u8>,
+        upgrade_bin: Vec<u8>,
+        metadata: VersionMetadata,
+    ) {
+        let version = self
+            .version_count
+            .checked_add(1)
+            .expect("max version reached");
+        metadata.assert_valid();
         require!(
-            self.latest_migration_version_idx
-                .checked_sub(self.latest_upgrade_version_idx)
-                .unwrap_or(2)
-                <= 1,
-            "load next upgrade bin first"
+            metadata.required_version < version && (version == 1 || metadata.required_version > 0),
+            "invalid required version"
         );
-        if upgrade {
-            match self.latest_upgrade_version_idx % 5 {
-                1 => StorageKeys::V1Upgrade,
-                2 => StorageKeys::V2Upgrade,
-                3 => StorageKeys::V3Upgrade,
-                4 => StorageKeys::V4Upgrade,
-                0 => StorageKeys::V5Upgrade,
-                _ => unreachable!(),
-            }
-        } else {
-            match self.latest_migration_version_idx % 5 {
-                1 => StorageKeys::V1Migration,
-                2 => StorageKeys::V2Migration,
-                3 => StorageKeys::V3Migration,
-                4 => StorageKeys::V4Migration,
-                0 => StorageKeys::V5Migration,
-                _ => unreachable!(),
-            }
+        if let Some(previous) = self.version_metadata.get(&self.version_count) {
+            require!(
+                metadata.semver_parts() > previous.semver_parts(),
+                "semver must be greater than previous version one"
+            );
         }
+        self.versions
+            .insert(&version, &(migration_bin, upgrade_bin));
+        self.version_metadata.insert(&version, &metadata);
+        self.version_count = version;
     }
-}
 
-#[cfg(any(target_arch = "wasm32", test))]
-fn get_next_version_keys(current_version: u8) -> (StorageKeys, StorageKeys) {
-    let key_migration = match current_version % 5 {
-        0 => StorageKeys::V1Migration,
-        1 => StorageKeys::V2Migration,
-        2 => StorageKeys::V3Migration,
-        3 => StorageKeys::V4Migration,
-        4 => StorageKeys::V5Migration,
-        _ => unreachable!(),
-    };
-    let key_upgrade = match current_version % 5 {
-        0 => StorageKeys::V1Upgrade,
-        1 => StorageKeys::V2Upgrade,
-        2 => StorageKeys::V3Upgrade,
-        3 => StorageKeys::V4Upgrade,
-        4 => StorageKeys::V5Upgrade,
-        _ => unreachable!(),
-    };
-    (key_migration, key_upgrade)
+    /// Returns the furthest version which can be upgraded to directly from `version`.
+    pub fn next_version(&self, version: u8) -> Option<u8> {
+        (version.saturating_add(1)..=self.version_count)
+            .rev()
+            .find(|next| {
+                self.version_metadata
+                    .get(next)
+                    .map(|m| m.required_version <= version)
+                    .unwrap_or(false)
+            })
+    }
 }
 
-/// Sends wasm blobs back to caller (dao) based on provided dao version
+/// Sends wasm blobs back to caller (dao) based on provided dao version.
+/// Blobs are of the furthest version reachable from the provided one,
+/// so dao far behind gets to the latest version in multiple upgrades.
 /// Dao must implement necessary store methods to be able to save the blobs.
 /// Prepaid gas should be 200+ TGas
 #[cfg(target_arch = "wasm32")]
@@ -280,25 +405,15 @@ pub extern "C" fn download_new_version() {
     let method_store_upgrade_bin = "store_upgrade_bin";
 
     log!("Got version: {:?}", version);
-    let last_version_stored: u8 = u8::from_le(
-        env::storage_read(&StorageKeys::LatestVersion.into_storage_key())
-            .expect("no upgrade stored yet")[0],
-    );
-    require!(
-        last_version_stored > version,
-        "next version is not available"
-    );
-    require!(
-        last_version_stored - version <= MAX_DAO_VERSIONS,
-        "upgrade is not possible"
-    );
-
-    let (key_migration, key_upgrade) = get_next_version_keys(version);
-
-    let migration_bin = env::storage_read(key_migration.into_storage_key().as_slice())
-        .expect("migration code not found");
-    let upgrade_bin = env::storage_read(key_upgrade.into_storage_key().as_slice())
-        .expect("upgrade code not found");
+    let factory: Contract = env::state_read().expect("failed to read contract state");
+    let next_version = factory
+        .next_version(version)
+        .unwrap_or_else(|| env::panic_str("next version is not available"));
+    log!("Sending version: {:?}", next_version);
+    let (migration_bin, upgrade_bin) = factory
+        .versions
+        .get(&next_version)
+        .expect("version code not found");
     let promise_id = env::promise_batch_create(&caller);
     env::promise_batch_action_function_call(
         promise_id,
@@ -328,14 +443,43 @@ pub struct DaoInfo {
     pub tags: Vec<u8>,
 }
 
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq, Clone))]
+#[serde(crate = "near_sdk::serde")]
+pub struct VersionMetadata {
+    /// Semantic version in "major.minor.patch" format.
+    pub semver: String,
+    /// Hash of the changelog.
+    pub changelog_hash: Option<Base64VecU8>,
+    /// Minimal dao version required to upgrade directly to this version.
+    pub required_version: u8,
+}
+
+impl VersionMetadata {
+    pub fn assert_valid(&self) {
+        require!(self.semver_parts().is_some(), "invalid semver");
+        if let Some(changelog_hash) = &self.changelog_hash {
+            require!(changelog_hash.0.len() == 32, "hash has to be 32 bytes");
+        }
+    }
+
+    fn semver_parts(&self) -> Option<(u32, u32, u32)> {
+        let mut parts = self.semver.split('.').map(|p| p.parse::<u32>().ok());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) => {
+                Some((major, minor, patch))
+            }
+            _ => None,
+        }
+    }
+}
+
 #[derive(Serialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq, Clone))]
 #[serde(crate = "near_sdk::serde")]
 pub struct FactoryStats {
     latest_dao_version: u8,
-    migration_versions: u8,
-    upgrade_versions: u8,
-    versions_stored: u8,
+    latest_dao_semver: String,
 }
 
 #[cfg(test)]
@@ -343,142 +487,223 @@ mod tests {
     use near_sdk::{test_utils::VMContextBuilder, testing_env};
 
     use super::*;
+
+    fn metadata(semver: &str, required_version: u8) -> VersionMetadata {
+        VersionMetadata {
+            semver: semver.to_string(),
+            changelog_hash: None,
+            required_version,
+        }
+    }
+
     #[test]
-    fn rotate_slots() {
+    fn add_versions() {
         let context = VMContextBuilder::new();
         testing_env!(context.build());
 
-        let mut factory = Contract::new(vec![]);
+        let mut factory = Contract::new(vec![], None);
         assert_eq!(factory.version_count, 1);
-        assert_eq!(factory.latest_migration_version_idx, 1);
-        assert_eq!(factory.latest_upgrade_version_idx, 1);
+        assert_eq!(factory.get_version_metadata(1), Some(metadata("1.0.0", 0)));
+        assert!(factory.get_upgrade_path(1).is_empty());
+
+        for version in 2..=7 {
+            factory.add_version(
+                vec![version],
+                vec![version],
+                metadata(&format!("1.{}.0", version), version - 1),
+            );
+            assert_eq!(factory.version_count, version);
+            assert_eq!(
+                factory.versions.get(&version),
+                Some((vec![version], vec![version]))
+            );
+        }
+        assert_eq!(factory.get_upgrade_path(1), vec![2, 3, 4, 5, 6, 7]);
+        assert_eq!(factory.get_upgrade_path(6), vec![7]);
+    }
 
-        assert_eq!(
-            factory.update_version_and_get_slot(false),
-            StorageKeys::V2Migration
-        );
+    #[test]
+    fn multi_hop_upgrade_path() {
+        let context = VMContextBuilder::new();
+        testing_env!(context.build());
+
+        let mut factory = Contract::new(vec![], None);
+        factory.add_version(vec![], vec![2], metadata("1.1.0", 1));
+        factory.add_version(vec![], vec![3], metadata("1.2.0", 1));
+        factory.add_version(vec![], vec![4], metadata("2.0.0", 3));
+        factory.add_version(vec![], vec![5], metadata("2.1.0", 3));
+        factory.add_version(vec![], vec![6], metadata("3.0.0", 5));
+
+        assert_eq!(factory.next_version(1), Some(3));
+        assert_eq!(factory.next_version(2), Some(3));
+        assert_eq!(factory.next_version(3), Some(5));
+        assert_eq!(factory.next_version(6), None);
+        assert_eq!(factory.get_upgrade_path(1), vec![3, 5, 6]);
+        assert_eq!(factory.get_upgrade_path(4), vec![5, 6]);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid required version")]
+    fn add_version_invalid_required_version() {
+        let context = VMContextBuilder::new();
+        testing_env!(context.build());
+
+        let mut factory = Contract::new(vec![], None);
+        factory.add_version(vec![], vec![], metadata("1.1.0", 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "semver must be greater than previous version one")]
+    fn add_version_lower_semver() {
+        let context = VMContextBuilder::new();
+        testing_env!(context.build());
+
+        let mut factory = Contract::new(vec![], None);
+        factory.add_version(vec![], vec![], metadata("0.9.0", 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid semver")]
+    fn add_version_invalid_semver() {
+        let context = VMContextBuilder::new();
+        testing_env!(context.build());
+
+        let mut factory = Contract::new(vec![], None);
+        factory.add_version(vec![], vec![], metadata("1.1", 1));
+    }
+
+    #[test]
+    fn migrate_new_version() {
+        let context = VMContextBuilder::new();
+        testing_env!(context.build());
+
+        let factory = Contract::new(vec![], None);
+        env::state_write(&factory);
+        let factory = Contract::migrate(MigrationType::NewMigrationBin, None);
         assert_eq!(factory.version_count, 1);
-        assert_eq!(factory.latest_migration_version_idx, 2);
-        assert_eq!(factory.latest_upgrade_version_idx, 1);
-        assert_eq!(
-            factory.update_version_and_get_slot(true),
-            StorageKeys::V2Upgrade
-        );
+        env::state_write(&factory);
+        let factory = Contract::migrate(MigrationType::NewUpgradeBin, Some(metadata("1.1.0", 1)));
         assert_eq!(factory.version_count, 2);
-        assert_eq!(factory.latest_migration_version_idx, 2);
-        assert_eq!(factory.latest_upgrade_version_idx, 2);
+        assert_eq!(factory.get_upgrade_path(1), vec![2]);
+        assert!(!env::storage_has_key(
+            &StorageKeys::PendingMigration.into_storage_key()
+        ));
+    }
 
-        assert_eq!(
-            factory.update_version_and_get_slot(false),
-            StorageKeys::V3Migration
-        );
-        assert_eq!(factory.version_count, 2);
-        assert_eq!(factory.latest_migration_version_idx, 3);
-        assert_eq!(factory.latest_upgrade_version_idx, 2);
+    #[test]
+    #[should_panic(expected = "load next migration bin first")]
+    fn migrate_upgrade_bin_without_migration_bin() {
+        let context = VMContextBuilder::new();
+        testing_env!(context.build());
 
-        assert_eq!(
-            factory.update_version_and_get_slot(true),
-            StorageKeys::V3Upgrade
-        );
-        assert_eq!(factory.version_count, 3);
-        assert_eq!(factory.latest_migration_version_idx, 3);
-        assert_eq!(factory.latest_upgrade_version_idx, 3);
+        let factory = Contract::new(vec![], None);
+        env::state_write(&factory);
+        Contract::migrate(MigrationType::NewUpgradeBin, Some(metadata("1.1.0", 1)));
+    }
 
-        assert_eq!(
-            factory.update_version_and_get_slot(false),
-            StorageKeys::V4Migration
+    fn write_old_contract(version_count: u8, latest_migration_version_idx: u8) {
+        env::storage_write(&OldStorageKeys::CurrentVersion.into_storage_key(), &[1]);
+        env::storage_write(
+            &OldStorageKeys::LatestVersion.into_storage_key(),
+            &[version_count],
         );
-        assert_eq!(factory.version_count, 3);
-        assert_eq!(factory.latest_migration_version_idx, 4);
-        assert_eq!(factory.latest_upgrade_version_idx, 3);
+        for version in 2..=version_count {
+            let (migration_slot, upgrade_slot) = OldStorageKeys::version_slots(version);
+            env::storage_write(&migration_slot.into_storage_key(), &[version, 0]);
+            env::storage_write(&upgrade_slot.into_storage_key(), &[version]);
+        }
+        if latest_migration_version_idx > version_count {
+            let (migration_slot, _) = OldStorageKeys::version_slots(latest_migration_version_idx);
+            env::storage_write(
+                &migration_slot.into_storage_key(),
+                &[latest_migration_version_idx, 0],
+            );
+        }
+        env::state_write(&OldContract {
+            daos: UnorderedMap::new(StorageKeys::Daos),
+            tags: vec!["tag".to_string()],
+            latest_migration_version_idx,
+            latest_upgrade_version_idx: version_count,
+            version_count,
+        });
+    }
 
-        assert_eq!(
-            factory.update_version_and_get_slot(true),
-            StorageKeys::V4Upgrade
-        );
-        assert_eq!(factory.version_count, 4);
-        assert_eq!(factory.latest_migration_version_idx, 4);
-        assert_eq!(factory.latest_upgrade_version_idx, 4);
+    #[test]
+    fn migrate_old_contract() {
+        let context = VMContextBuilder::new();
+        testing_env!(context.build());
+        write_old_contract(7, 7);
+
+        let factory = Contract::migrate(MigrationType::OnlyMigration, None);
+        assert_eq!(factory.version_count, 7);
+        assert_eq!(factory.tags, vec!["tag".to_string()]);
+        assert_eq!(factory.versions.get(&2), None);
+        for version in 3..=7 {
+            assert_eq!(
+                factory.versions.get(&version),
+                Some((vec![version, 0], vec![version]))
+            );
+        }
+        assert_eq!(factory.get_version_metadata(7), Some(metadata("1.6.0", 6)));
+        assert_eq!(factory.get_upgrade_path(2), vec![3, 4, 5, 6, 7]);
+        assert!(factory.get_upgrade_path(1).is_empty());
+        assert!(!env::storage_has_key(
+            &StorageKeys::PendingMigration.into_storage_key()
+        ));
+        assert!(!env::storage_has_key(
+            &OldStorageKeys::V5Upgrade.into_storage_key()
+        ));
+    }
 
-        assert_eq!(
-            factory.update_version_and_get_slot(false),
-            StorageKeys::V5Migration
-        );
-        assert_eq!(factory.version_count, 4);
-        assert_eq!(factory.latest_migration_version_idx, 5);
-        assert_eq!(factory.latest_upgrade_version_idx, 4);
+    #[test]
+    fn migrate_old_contract_with_pending_migration() {
+        let context = VMContextBuilder::new();
+        testing_env!(context.build());
+        write_old_contract(5, 6);
 
-        assert_eq!(
-            factory.update_version_and_get_slot(true),
-            StorageKeys::V5Upgrade
-        );
+        let factory = Contract::migrate(MigrationType::OnlyMigration, None);
         assert_eq!(factory.version_count, 5);
-        assert_eq!(factory.latest_migration_version_idx, 5);
-        assert_eq!(factory.latest_upgrade_version_idx, 5);
-
+        assert_eq!(factory.versions.get(&1), None);
+        assert_eq!(factory.versions.get(&2), Some((vec![2, 0], vec![2])));
         assert_eq!(
-            factory.update_version_and_get_slot(false),
-            StorageKeys::V1Migration
+            env::storage_read(&StorageKeys::PendingMigration.into_storage_key()),
+            Some(vec![6, 0])
         );
-        assert_eq!(factory.version_count, 5);
-        assert_eq!(factory.latest_migration_version_idx, 6);
-        assert_eq!(factory.latest_upgrade_version_idx, 5);
-
+        env::state_write(&factory);
+        let factory = Contract::migrate(MigrationType::NewUpgradeBin, Some(metadata("2.0.0", 5)));
+        assert_eq!(factory.version_count, 6);
         assert_eq!(
-            factory.update_version_and_get_slot(true),
-            StorageKeys::V1Upgrade
+            factory.versions.get(&6),
+            Some((vec![6, 0], NEWEST_DAO_VERSION.to_vec()))
         );
-        assert_eq!(factory.version_count, 6);
-        assert_eq!(factory.latest_migration_version_idx, 6);
-        assert_eq!(factory.latest_upgrade_version_idx, 6);
     }
 
     #[test]
-    #[should_panic]
-    fn rotate_slots_invalid_use() {
+    fn migrate_old_contract_first_version() {
         let context = VMContextBuilder::new();
         testing_env!(context.build());
+        write_old_contract(1, 1);
 
-        let mut factory = Contract::new(vec![]);
+        let factory = Contract::migrate(MigrationType::OnlyMigration, None);
         assert_eq!(factory.version_count, 1);
-        assert_eq!(factory.latest_migration_version_idx, 1);
-        assert_eq!(factory.latest_upgrade_version_idx, 1);
-
-        assert_eq!(
-            factory.update_version_and_get_slot(false),
-            StorageKeys::V2Migration
-        );
-        assert_eq!(factory.version_count, 1);
-        assert_eq!(factory.latest_migration_version_idx, 2);
-        assert_eq!(factory.latest_upgrade_version_idx, 1);
-        factory.update_version_and_get_slot(false);
+        assert_eq!(factory.versions.get(&1), Some((vec![], vec![1])));
+        assert_eq!(factory.get_stats().latest_dao_semver, "1.0.0");
     }
 
     #[test]
-    fn get_next_version() {
-        assert_eq!(
-            get_next_version_keys(1),
-            (StorageKeys::V2Migration, StorageKeys::V2Upgrade)
-        );
-        assert_eq!(
-            get_next_version_keys(2),
-            (StorageKeys::V3Migration, StorageKeys::V3Upgrade)
-        );
-        assert_eq!(
-            get_next_version_keys(3),
-            (StorageKeys::V4Migration, StorageKeys::V4Upgrade)
-        );
-        assert_eq!(
-            get_next_version_keys(4),
-            (StorageKeys::V5Migration, StorageKeys::V5Upgrade)
-        );
-        assert_eq!(
-            get_next_version_keys(5),
-            (StorageKeys::V1Migration, StorageKeys::V1Upgrade)
-        );
+    fn migrate_replaces_pending_migration_bin() {
+        let context = VMContextBuilder::new();
+        testing_env!(context.build());
+
+        let factory = Contract::new(vec![], None);
+        env::state_write(&factory);
+        let factory = Contract::migrate(MigrationType::NewMigrationBin, None);
+        env::state_write(&factory);
+        let factory = Contract::migrate(MigrationType::NewMigrationBin, None);
+        assert_eq!(factory.version_count, 1);
         assert_eq!(
-            get_next_version_keys(6),
-            (StorageKeys::V2Migration, StorageKeys::V2Upgrade)
+            env::storage_read(&StorageKeys::PendingMigration.into_storage_key()),
+            Some(MIGRATION_BLOB.to_vec())
         );
     }
 }
@@ -1,9 +1,15 @@
+use std::collections::HashSet;
+
+use library::locking::{LockInput, UnlockingDB, UnlockingInput};
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
-    collections::UnorderedSet,
+    collections::{LookupMap, UnorderedMap, UnorderedSet},
     env, ext_contract,
     json_types::{Base64VecU8, U128},
-    log, near_bindgen, AccountId, BorshStorageKey, Gas, PanicOnDefault, Promise,
+    log, near_bindgen, require,
+    serde::{Deserialize, Serialize},
+    serde_json::json,
+    AccountId, BorshStorageKey, Gas, PanicOnDefault, Promise,
 };
 
 /// Gas spent on the call & account creation.
@@ -15,6 +21,16 @@ const FUNGIBLE_TOKEN_WASM: &[u8] = include_bytes!("../../../res/fungible_token_o
 
 const DEPOSIT_CREATE: u128 = 2_200_000_000_000_000_000_000_000;
 
+pub const FT_METADATA_SPEC: &str = "ft-1.0.0";
+
+#[derive(Serialize, Deserialize, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationType {
+    OnlyMigration,
+    NewUpgradeBin,
+}
+
 #[ext_contract(ext_self)]
 pub trait ExtSelf {
     fn on_create(
@@ -22,6 +38,7 @@ pub trait ExtSelf {
         account_id: AccountId,
         attached_deposit: U128,
         predecessor_account_id: AccountId,
+        token_info: TokenInfo,
     ) -> bool;
 }
 
@@ -29,11 +46,27 @@ pub trait ExtSelf {
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 pub enum StorageKeys {
     DeployedTokens,
+    Tokens,
+    Versions,
+    VersionMetadata,
 }
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
+    /// Deployed tokens.
+    /// Info is None for tokens created before it was recorded.
+    pub tokens: UnorderedMap<AccountId, Option<TokenInfo>>,
+    /// Latest fungible token version available.
+    pub version_count: u8,
+    pub versions: LookupMap<u8, Vec<u8>>,
+    pub version_metadata: LookupMap<u8, VersionMetadata>,
+}
+
+/// Contract state before token info and versions were stored.
+#[derive(BorshDeserialize)]
+#[cfg_attr(test, derive(BorshSerialize))]
+pub struct OldContract {
     pub accounts: UnorderedSet<AccountId>,
 }
 
@@ -41,12 +74,68 @@ pub struct Contract {
 impl Contract {
     #[init]
     pub fn new() -> Self {
-        Self {
-            accounts: UnorderedSet::new(StorageKeys::DeployedTokens),
+        let mut factory = Self {
+            tokens: UnorderedMap::new(StorageKeys::Tokens),
+            version_count: 0,
+            versions: LookupMap::new(StorageKeys::Versions),
+            version_metadata: LookupMap::new(StorageKeys::VersionMetadata),
+        };
+        factory.add_version(
+            FUNGIBLE_TOKEN_WASM.to_vec(),
+            VersionMetadata {
+                semver: "1.0.0".to_string(),
+                changelog_hash: None,
+                required_version: 0,
+            },
+        );
+        factory
+    }
+
+    /// Stores included fungible token binary as the next version described by `version_metadata`
+    /// when `NewUpgradeBin` is provided.
+    /// State of the factory without stored versions is converted
+    /// and the included binary is stored as the first version.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(r#type: MigrationType, version_metadata: Option<VersionMetadata>) -> Self {
+        let state = env::storage_read(b"STATE").expect("failed to read contract state");
+        if let Ok(old) = OldContract::try_from_slice(&state) {
+            return Self::migrate_old_contract(old);
+        }
+        let mut factory =
+            Contract::try_from_slice(&state).expect("failed to deserialize contract state");
+        if r#type == MigrationType::NewUpgradeBin {
+            factory.add_version(
+                FUNGIBLE_TOKEN_WASM.to_vec(),
+                version_metadata.expect("missing version metadata"),
+            );
+        }
+        factory
+    }
+
+    fn migrate_old_contract(mut old: OldContract) -> Self {
+        let mut factory = Self::new();
+        for account_id in old.accounts.iter() {
+            factory.tokens.insert(&account_id, &None);
         }
+        old.accounts.clear();
+        factory
     }
+
+    /// Creates new fungible token contract on `name`.factory account.
+    /// All provided args are checked before the account is created.
+    /// If `settings` are not provided, the token is owned by `owner_id`
+    /// and this factory is set as its upgrade provider.
     #[payable]
-    pub fn create(&mut self, name: String, args: Base64VecU8) -> Promise {
+    pub fn create(
+        &mut self,
+        name: String,
+        owner_id: AccountId,
+        total_supply: U128,
+        metadata: FungibleTokenMetadata,
+        settings: Option<Settings>,
+        init_distribution: Vec<InitDistribution>,
+    ) -> Promise {
         assert!(
             env::attached_deposit() >= DEPOSIT_CREATE,
             "Not enough attached deposit."
@@ -55,10 +144,36 @@ impl Contract {
             .try_into()
             .expect("Account is not valid.");
         assert!(
-            !self.accounts.contains(&account_id),
+            self.tokens.get(&account_id).is_none(),
             "{}",
             "Account already exists."
         );
+        let settings = settings.unwrap_or_else(|| Settings {
+            owner_id: owner_id.clone(),
+            mint_allowed: false,
+            burn_allowed: false,
+            upgrade_provider: Some(env::current_account_id()),
+            max_supply: None,
+            mint_rate_limit: None,
+        });
+        let args = json!({
+            "owner_id": owner_id,
+            "total_supply": total_supply,
+            "metadata": metadata,
+            "settings": settings,
+            "init_distribution": init_distribution,
+        })
+        .to_string()
+        .into_bytes();
+        assert_valid_args(&total_supply, &metadata, &settings, init_distribution);
+        let token_info = TokenInfo {
+            owner_id,
+            created_by: env::predecessor_account_id(),
+            created_at: env::block_timestamp() / 10u64.pow(9),
+            version: self.version_count,
+            total_supply,
+            metadata,
+        };
         let promise = Promise::new(account_id.clone())
             .create_account()
             .deploy_contract(FUNGIBLE_TOKEN_WASM.to_vec())
@@ -66,7 +181,7 @@ impl Contract {
         promise
             .function_call(
                 "new".to_string(),
-                args.into(),
+                args,
                 0,
                 env::prepaid_gas() - CREATE_CALL_GAS - ON_CREATE_CALL_GAS,
             )
@@ -77,17 +192,21 @@ impl Contract {
                         account_id,
                         U128(env::attached_deposit()),
                         env::predecessor_account_id(),
+                        token_info,
                     ),
             )
     }
+
+    #[private]
     pub fn on_create(
         &mut self,
         account_id: AccountId,
         attached_deposit: U128,
         predecessor_account_id: AccountId,
+        token_info: TokenInfo,
     ) -> bool {
         if near_sdk::is_promise_success() {
-            self.accounts.insert(&account_id);
+            self.tokens.insert(&account_id, &Some(token_info));
             log!("Created fungible token contract: {}", &account_id);
             true
         } else {
@@ -95,7 +214,563 @@ impl Contract {
             false
         }
     }
-    pub fn accounts(&self) -> Vec<AccountId> {
-        self.accounts.to_vec()
+
+    /// Deployed tokens with their info.
+    /// Info is None for tokens created before it was recorded.
+    pub fn tokens(&self, from_index: u64, limit: u64) -> Vec<(AccountId, Option<TokenInfo>)> {
+        let keys = self.tokens.keys_as_vector();
+        let values = self.tokens.values_as_vector();
+        (from_index..std::cmp::min(from_index + limit, self.tokens.len()))
+            .map(|index| (keys.get(index).unwrap(), values.get(index).unwrap()))
+            .collect()
+    }
+
+    pub fn token_info(&self, account_id: AccountId) -> Option<TokenInfo> {
+        self.tokens.get(&account_id).flatten()
+    }
+
+    pub fn get_stats(&self) -> FactoryStats {
+        FactoryStats {
+            latest_ft_version: self.version_count,
+            latest_ft_semver: self
+                .version_metadata
+                .get(&self.version_count)
+                .expect("version not found")
+                .semver,
+            tokens_count: self.tokens.len(),
+        }
+    }
+
+    pub fn get_version_metadata(&self, version: u8) -> Option<VersionMetadata> {
+        self.version_metadata.get(&version)
+    }
+
+    /// Removes all version blobs so we can delete factory account
+    #[private]
+    pub fn clean_self(&mut self) {
+        for version in 1..=self.version_count {
+            self.versions.remove(&version);
+            self.version_metadata.remove(&version);
+        }
+    }
+}
+
+impl Contract {
+    /// Stores binary as the next fungible token version.
+    pub fn add_version(&mut self, upgrade_bin: Vec<u8>, metadata: VersionMetadata) {
+        let version = self
+            .version_count
+            .checked_add(1)
+            .expect("max version reached");
+        metadata.assert_valid();
+        require!(
+            metadata.required_version < version && (version == 1 || metadata.required_version > 0),
+            "invalid required version"
+        );
+        if let Some(previous) = self.version_metadata.get(&self.version_count) {
+            require!(
+                metadata.semver_parts() > previous.semver_parts(),
+                "semver must be greater than previous version one"
+            );
+        }
+        self.versions.insert(&version, &upgrade_bin);
+        self.version_metadata.insert(&version, &metadata);
+        self.version_count = version;
+    }
+
+    /// Returns the furthest version which can be upgraded to directly from `version`.
+    pub fn next_version(&self, version: u8) -> Option<u8> {
+        (version.saturating_add(1)..=self.version_count)
+            .rev()
+            .find(|next| {
+                self.version_metadata
+                    .get(next)
+                    .map(|m| m.required_version <= version)
+                    .unwrap_or(false)
+            })
+    }
+}
+
+/// Checks that the fungible token contract init will not fail with provided args.
+fn assert_valid_args(
+    total_supply: &U128,
+    metadata: &FungibleTokenMetadata,
+    settings: &Settings,
+    init_distribution: Vec<InitDistribution>,
+) {
+    metadata.assert_valid();
+    let decimals_multiplier = 10u128
+        .checked_pow(metadata.decimals as u32)
+        .expect("Invalid decimals.");
+    require!(
+        total_supply.0 / decimals_multiplier >= 1,
+        "Invalid total_supply/decimals ratio."
+    );
+    if let Some(max_supply) = &settings.max_supply {
+        require!(total_supply.0 <= max_supply.0, "Max supply exceeded.");
+    }
+    let mut distributed: u128 = 0;
+    let mut accounts = HashSet::with_capacity(init_distribution.len());
+    for d in init_distribution {
+        require!(
+            accounts.insert(d.account_id),
+            "Duplicate init distribution account."
+        );
+        distributed = distributed
+            .checked_add(d.amount.0)
+            .expect("Init distribution overflow.");
+        if let Some(lock) = d.vesting {
+            let vesting = UnlockingDB::try_from(UnlockingInput {
+                amount_init_unlock: 0,
+                lock: Some(lock),
+            })
+            .unwrap_or_else(|e| env::panic_str(e));
+            distributed = (vesting.total_locked() as u128)
+                .checked_mul(decimals_multiplier)
+                .and_then(|amount| distributed.checked_add(amount))
+                .expect("Init distribution overflow.");
+        }
+    }
+    require!(
+        distributed <= total_supply.0,
+        "Init distribution exceeds total supply."
+    );
+}
+
+/// Sends wasm blob back to caller (fungible token) via its `store_upgrade_bin` method.
+/// Input is optional current version of the caller.
+/// If missing, the latest version is sent,
+/// otherwise the furthest version reachable from the provided one.
+/// Prepaid gas should be 200+ TGas
+#[cfg(target_arch = "wasm32")]
+#[no_mangle]
+pub extern "C" fn download_new_version() {
+    const GAS_DOWNLOAD_ACTION: Gas = Gas(100_000_000_000_000);
+    env::setup_panic_hook();
+
+    let caller = env::predecessor_account_id();
+    let method_store_upgrade_bin = "store_upgrade_bin";
+    let factory: Contract = env::state_read().expect("failed to read contract state");
+    let next_version = match env::input().unwrap_or_default().first() {
+        Some(version) => {
+            log!("Got version: {:?}", version);
+            factory
+                .next_version(*version)
+                .unwrap_or_else(|| env::panic_str("next version is not available"))
+        }
+        None => factory.version_count,
+    };
+    let upgrade_bin = factory
+        .versions
+        .get(&next_version)
+        .expect("upgrade code not found");
+    let promise_id = env::promise_batch_create(&caller);
+    env::promise_batch_action_function_call(
+        promise_id,
+        method_store_upgrade_bin,
+        upgrade_bin.as_slice(),
+        0,
+        GAS_DOWNLOAD_ACTION,
+    );
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Deserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct FungibleTokenMetadata {
+    pub spec: String,
+    pub name: String,
+    pub symbol: String,
+    pub icon: Option<String>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+    pub decimals: u8,
+}
+
+impl FungibleTokenMetadata {
+    pub fn assert_valid(&self) {
+        require!(self.spec == FT_METADATA_SPEC, "Invalid metadata spec.");
+        require!(
+            !self.name.is_empty() && !self.symbol.is_empty(),
+            "Missing name or symbol."
+        );
+        require!(
+            self.reference.is_some() == self.reference_hash.is_some(),
+            "Reference and reference hash must be provided together."
+        );
+        if let Some(reference_hash) = &self.reference_hash {
+            require!(reference_hash.0.len() == 32, "Hash has to be 32 bytes");
+        }
+    }
+}
+
+/// Mirror of fungible token contract settings.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq, Clone))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Settings {
+    pub owner_id: AccountId,
+    pub mint_allowed: bool,
+    pub burn_allowed: bool,
+    pub upgrade_provider: Option<AccountId>,
+    #[serde(default)]
+    pub max_supply: Option<U128>,
+    #[serde(default)]
+    pub mint_rate_limit: Option<MintRateLimit>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq, Clone))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintRateLimit {
+    pub amount: U128,
+    pub period: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq, Clone))]
+#[serde(crate = "near_sdk::serde")]
+pub struct InitDistribution {
+    pub account_id: AccountId,
+    pub amount: U128,
+    /// Amounts are in whole tokens.
+    #[serde(default)]
+    pub vesting: Option<LockInput>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq, Clone))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenInfo {
+    pub owner_id: AccountId,
+    pub created_by: AccountId,
+    /// Timestamp in seconds.
+    pub created_at: u64,
+    /// Factory version of the deployed binary.
+    pub version: u8,
+    pub total_supply: U128,
+    pub metadata: FungibleTokenMetadata,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq, Clone))]
+#[serde(crate = "near_sdk::serde")]
+pub struct FactoryStats {
+    latest_ft_version: u8,
+    latest_ft_semver: String,
+    tokens_count: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq, Clone))]
+#[serde(crate = "near_sdk::serde")]
+pub struct VersionMetadata {
+    /// Semantic version in "major.minor.patch" format.
+    pub semver: String,
+    /// Hash of the changelog.
+    pub changelog_hash: Option<Base64VecU8>,
+    /// Minimal fungible token version required to upgrade directly to this version.
+    pub required_version: u8,
+}
+
+impl VersionMetadata {
+    pub fn assert_valid(&self) {
+        require!(self.semver_parts().is_some(), "invalid semver");
+        if let Some(changelog_hash) = &self.changelog_hash {
+            require!(changelog_hash.0.len() == 32, "hash has to be 32 bytes");
+        }
+    }
+
+    fn semver_parts(&self) -> Option<(u32, u32, u32)> {
+        let mut parts = self.semver.split('.').map(|p| p.parse::<u32>().ok());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) => {
+                Some((major, minor, patch))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use library::locking::{UnlockMethod, UnlockPeriodInput};
+    use near_sdk::{
+        test_utils::{accounts, VMContextBuilder},
+        testing_env, PromiseResult, RuntimeFeesConfig, VMConfig,
+    };
+
+    use super::*;
+
+    const DECIMALS: u8 = 24;
+
+    fn metadata() -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Example Token Name".to_string(),
+            symbol: "EXLT".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: DECIMALS,
+        }
+    }
+
+    fn supply(amount: u128) -> U128 {
+        U128(amount * 10u128.pow(DECIMALS as u32))
+    }
+
+    fn context() -> VMContextBuilder {
+        let mut context = VMContextBuilder::new();
+        context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(DEPOSIT_CREATE);
+        context
+    }
+
+    fn create(
+        factory: &mut Contract,
+        total_supply: U128,
+        metadata: FungibleTokenMetadata,
+        settings: Option<Settings>,
+        init_distribution: Vec<InitDistribution>,
+    ) {
+        factory.create(
+            "token".to_string(),
+            accounts(1),
+            total_supply,
+            metadata,
+            settings,
+            init_distribution,
+        );
+    }
+
+    #[test]
+    fn create_and_register_token() {
+        testing_env!(context().build());
+        let mut factory = Contract::new();
+        create(&mut factory, supply(1_000), metadata(), None, vec![]);
+
+        let token_id: AccountId = format!("token.{}", accounts(0)).try_into().unwrap();
+        let token_info = TokenInfo {
+            owner_id: accounts(1),
+            created_by: accounts(1),
+            created_at: 0,
+            version: 1,
+            total_supply: supply(1_000),
+            metadata: metadata(),
+        };
+        testing_env!(
+            context().predecessor_account_id(accounts(0)).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])]
+        );
+        assert!(factory.on_create(
+            token_id.clone(),
+            U128(DEPOSIT_CREATE),
+            accounts(1),
+            token_info.clone()
+        ));
+        assert_eq!(
+            factory.token_info(token_id.clone()),
+            Some(token_info.clone())
+        );
+        assert_eq!(factory.tokens(0, 10), vec![(token_id, Some(token_info))]);
+        assert!(factory.tokens(1, 10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid metadata spec.")]
+    fn create_invalid_metadata() {
+        testing_env!(context().build());
+        let mut factory = Contract::new();
+        let mut metadata = metadata();
+        metadata.spec = "ft-0.0.1".to_string();
+        create(&mut factory, supply(1_000), metadata, None, vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid total_supply/decimals ratio.")]
+    fn create_invalid_total_supply() {
+        testing_env!(context().build());
+        let mut factory = Contract::new();
+        create(&mut factory, U128(1_000), metadata(), None, vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max supply exceeded.")]
+    fn create_max_supply_exceeded() {
+        testing_env!(context().build());
+        let mut factory = Contract::new();
+        let settings = Settings {
+            owner_id: accounts(1),
+            mint_allowed: true,
+            burn_allowed: false,
+            upgrade_provider: None,
+            max_supply: Some(supply(999)),
+            mint_rate_limit: None,
+        };
+        create(
+            &mut factory,
+            supply(1_000),
+            metadata(),
+            Some(settings),
+            vec![],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Init distribution exceeds total supply.")]
+    fn create_init_distribution_exceeded() {
+        testing_env!(context().build());
+        let mut factory = Contract::new();
+        let init_distribution = vec![
+            InitDistribution {
+                account_id: accounts(2),
+                amount: supply(500),
+                vesting: None,
+            },
+            InitDistribution {
+                account_id: accounts(3),
+                amount: supply(400),
+                vesting: Some(LockInput {
+                    amount_total_lock: 200,
+                    start_from: 0,
+                    duration: 100,
+                    periods: vec![UnlockPeriodInput {
+                        r#type: UnlockMethod::Linear,
+                        duration: 100,
+                        amount: 200,
+                    }],
+                }),
+            },
+        ];
+        create(
+            &mut factory,
+            supply(1_000),
+            metadata(),
+            None,
+            init_distribution,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate init distribution account.")]
+    fn create_init_distribution_duplicate_account() {
+        testing_env!(context().build());
+        let mut factory = Contract::new();
+        let init_distribution = vec![
+            InitDistribution {
+                account_id: accounts(2),
+                amount: supply(100),
+                vesting: None,
+            },
+            InitDistribution {
+                account_id: accounts(2),
+                amount: supply(100),
+                vesting: None,
+            },
+        ];
+        create(
+            &mut factory,
+            supply(1_000),
+            metadata(),
+            None,
+            init_distribution,
+        );
+    }
+
+    fn version_metadata(semver: &str, required_version: u8) -> VersionMetadata {
+        VersionMetadata {
+            semver: semver.to_string(),
+            changelog_hash: None,
+            required_version,
+        }
+    }
+
+    #[test]
+    fn migrate_stores_new_version() {
+        testing_env!(context().build());
+        let factory = Contract::new();
+        env::state_write(&factory);
+        for version in 2..=7 {
+            let factory = Contract::migrate(
+                MigrationType::NewUpgradeBin,
+                Some(version_metadata(&format!("1.{}.0", version - 1), 1)),
+            );
+            assert_eq!(factory.version_count, version);
+            env::state_write(&factory);
+        }
+        let factory = Contract::migrate(MigrationType::OnlyMigration, None);
+        assert_eq!(factory.version_count, 7);
+        // All versions are kept.
+        for version in 1..=7 {
+            assert_eq!(
+                factory.versions.get(&version),
+                Some(FUNGIBLE_TOKEN_WASM.to_vec())
+            );
+        }
+        assert_eq!(
+            factory.get_version_metadata(7),
+            Some(version_metadata("1.6.0", 1))
+        );
+        assert_eq!(factory.next_version(1), Some(7));
+    }
+
+    #[test]
+    fn next_version_follows_required_version() {
+        testing_env!(context().build());
+        let mut factory = Contract::new();
+        factory.add_version(vec![2], version_metadata("1.1.0", 1));
+        factory.add_version(vec![3], version_metadata("1.2.0", 1));
+        factory.add_version(vec![4], version_metadata("2.0.0", 3));
+        factory.add_version(vec![5], version_metadata("2.1.0", 3));
+
+        assert_eq!(factory.next_version(1), Some(3));
+        assert_eq!(factory.next_version(2), Some(3));
+        assert_eq!(factory.next_version(3), Some(5));
+        assert_eq!(factory.next_version(5), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "semver must be greater than previous version one")]
+    fn add_version_lower_semver() {
+        testing_env!(context().build());
+        let mut factory = Contract::new();
+        factory.add_version(vec![], version_metadata("0.9.0", 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "max version reached")]
+    fn add_version_max_version() {
+        testing_env!(context().build());
+        let mut factory = Contract::new();
+        factory.version_count = u8::MAX;
+        factory.add_version(vec![], version_metadata("1.1.0", 1));
+    }
+
+    #[test]
+    fn migrate_old_contract() {
+        testing_env!(context().build());
+        let mut old = OldContract {
+            accounts: UnorderedSet::new(StorageKeys::DeployedTokens),
+        };
+        old.accounts.insert(&accounts(2));
+        old.accounts.insert(&accounts(3));
+        env::state_write(&old);
+
+        let factory = Contract::migrate(MigrationType::OnlyMigration, None);
+        assert_eq!(
+            factory.tokens(0, 10),
+            vec![(accounts(2), None), (accounts(3), None)]
+        );
+        assert_eq!(factory.token_info(accounts(2)), None);
+        assert_eq!(factory.version_count, 1);
+        assert_eq!(factory.versions.get(&1), Some(FUNGIBLE_TOKEN_WASM.to_vec()));
+        let old_accounts: UnorderedSet<AccountId> = UnorderedSet::new(StorageKeys::DeployedTokens);
+        assert!(!old_accounts.contains(&accounts(2)));
     }
 }
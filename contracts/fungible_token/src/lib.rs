@@ -15,19 +15,20 @@ NOTES:
   - To prevent the deployed contract from being modified or deleted, it should not have any access
     keys on its account.
 */
+use library::locking::{LockInput, UnlockingDB, UnlockingInput};
 use near_contract_standards::fungible_token::core::FungibleTokenCore;
-use near_contract_standards::fungible_token::events::FtMint;
+use near_contract_standards::fungible_token::events::{FtBurn, FtMint};
 use near_contract_standards::fungible_token::resolver::FungibleTokenResolver;
 use near_contract_standards::storage_management::{
     StorageBalance, StorageBalanceBounds, StorageManagement,
 };
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LazyOption;
+use near_sdk::collections::{LazyOption, LookupMap};
 use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
-    env, log, near_bindgen, require, AccountId, Balance, BorshStorageKey, Gas, PanicOnDefault,
-    PromiseOrValue,
+    assert_one_yocto, env, log, near_bindgen, require, AccountId, Balance, BorshStorageKey, Gas,
+    PanicOnDefault, PromiseOrValue,
 };
 
 use standard_impl::impl_ft_metadata::{FungibleTokenMetadata, FungibleTokenMetadataProvider};
@@ -46,6 +47,7 @@ pub enum StorageKeys {
     Token,
     TokenMeta,
     Settings,
+    Vestings,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -53,6 +55,21 @@ pub enum StorageKeys {
 pub struct InitDistribution {
     pub account_id: AccountId,
     pub amount: U128,
+    /// Locked amount held by the contract and released to the account via `claim_vested`.
+    /// Amounts are in whole tokens.
+    #[serde(default)]
+    pub vesting: Option<LockInput>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingInfo {
+    /// Total vested amount.
+    pub total: U128,
+    /// Amount still locked.
+    pub locked: U128,
+    /// Unlocked amount which can be claimed.
+    pub unlocked: U128,
 }
 
 #[near_bindgen]
@@ -61,6 +78,27 @@ pub struct Contract {
     token: FungibleToken,
     metadata: LazyOption<FungibleTokenMetadata>,
     settings: LazyOption<Settings>,
+    /// Tokens minted in the current mint rate limit period.
+    minted_in_period: MintedInPeriod,
+    /// Vested init distributions.
+    vestings: LookupMap<AccountId, UnlockingDB>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintRateLimit {
+    /// Maximal amount of tokens minted in one period.
+    pub amount: U128,
+    /// Length of period in seconds.
+    pub period: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintedInPeriod {
+    /// Start of the current period in seconds.
+    pub period_start: u64,
+    pub amount: U128,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
@@ -75,6 +113,65 @@ pub struct Settings {
     /// Account of contract allowed to provide new version.
     /// If not set then upgrade is not allowed.
     upgrade_provider: Option<AccountId>,
+    /// Total supply cannot exceed this amount.
+    /// Once set, it can be only lowered.
+    #[serde(default)]
+    max_supply: Option<U128>,
+    /// Limit of minting new FT per period.
+    /// Once set, it can be only tightened.
+    #[serde(default)]
+    mint_rate_limit: Option<MintRateLimit>,
+}
+
+/// Contract state before supply policy and vestings were added.
+#[derive(BorshDeserialize)]
+struct ContractV1 {
+    token: FungibleToken,
+    metadata: LazyOption<FungibleTokenMetadata>,
+    settings: LazyOption<SettingsV1>,
+}
+
+/// Settings before supply policy was added.
+#[derive(BorshDeserialize, BorshSerialize)]
+struct SettingsV1 {
+    owner_id: AccountId,
+    mint_allowed: bool,
+    burn_allowed: bool,
+    upgrade_provider: Option<AccountId>,
+}
+
+impl From<SettingsV1> for Settings {
+    fn from(settings: SettingsV1) -> Self {
+        Self {
+            owner_id: settings.owner_id,
+            mint_allowed: settings.mint_allowed,
+            burn_allowed: settings.burn_allowed,
+            upgrade_provider: settings.upgrade_provider,
+            max_supply: None,
+            mint_rate_limit: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Checks that `new` settings do not loosen supply policy of these settings.
+    fn assert_supply_policy_kept(&self, new: &Settings) {
+        if let Some(max_supply) = &self.max_supply {
+            require!(
+                new.max_supply.map(|s| s.0 <= max_supply.0).unwrap_or(false),
+                "Max supply cannot be raised."
+            );
+        }
+        if let Some(limit) = &self.mint_rate_limit {
+            require!(
+                new.mint_rate_limit
+                    .as_ref()
+                    .map(|l| l.amount.0 <= limit.amount.0 && l.period >= limit.period)
+                    .unwrap_or(false),
+                "Mint rate limit cannot be loosened."
+            );
+        }
+    }
 }
 
 #[near_bindgen]
@@ -102,24 +199,59 @@ impl Contract {
             mint_allowed: false,
             burn_allowed: false,
             upgrade_provider: None,
+            max_supply: None,
+            mint_rate_limit: None,
         });
+        if let Some(max_supply) = &settings.max_supply {
+            require!(total_supply.0 <= max_supply.0, "Max supply exceeded.");
+        }
         let mut this = Self {
             token: FungibleToken::new(StorageKeys::Token),
             metadata: LazyOption::new(StorageKeys::TokenMeta, Some(&metadata)),
             settings: LazyOption::new(StorageKeys::Settings, Some(&settings)),
+            minted_in_period: MintedInPeriod {
+                period_start: 0,
+                amount: U128(0),
+            },
+            vestings: LookupMap::new(StorageKeys::Vestings),
         };
         this.token.internal_register_account(&owner_id);
         this.token.internal_deposit(&owner_id, total_supply.into());
         if !init_distribution.is_empty() {
             let memo = "init distribution".to_string();
+            let vesting_memo = "init distribution vesting".to_string();
+            let contract_id = env::current_account_id();
             for d in init_distribution {
-                this.token.internal_register_account(&d.account_id);
-                this.token.internal_transfer(
-                    &owner_id,
-                    &d.account_id,
-                    d.amount.0,
-                    Some(memo.clone()),
+                require!(
+                    this.token.accounts.get(&d.account_id).is_none(),
+                    "Duplicate init distribution account."
                 );
+                this.token.internal_register_account(&d.account_id);
+                if d.amount.0 > 0 {
+                    this.token.internal_transfer(
+                        &owner_id,
+                        &d.account_id,
+                        d.amount.0,
+                        Some(memo.clone()),
+                    );
+                }
+                if let Some(lock) = d.vesting {
+                    let vesting = UnlockingDB::try_from(UnlockingInput {
+                        amount_init_unlock: 0,
+                        lock: Some(lock),
+                    })
+                    .unwrap_or_else(|e| env::panic_str(e));
+                    if this.token.accounts.get(&contract_id).is_none() {
+                        this.token.internal_register_account(&contract_id);
+                    }
+                    this.token.internal_transfer(
+                        &owner_id,
+                        &contract_id,
+                        vesting.total_locked() as u128 * 10u128.pow(metadata.decimals as u32),
+                        Some(vesting_memo.clone()),
+                    );
+                    this.vestings.insert(&d.account_id, &vesting);
+                }
             }
         }
         FtMint {
@@ -131,6 +263,30 @@ impl Contract {
         this
     }
 
+    /// Migrates state of the previous version.
+    /// Called by `start_upgrade` once the new version is deployed.
+    /// Supply policy of migrated settings is not set.
+    #[private]
+    #[init(ignore_state)]
+    pub fn deploy_upgrade() -> Self {
+        let old: ContractV1 = env::state_read().expect("failed to read contract state");
+        let settings: Option<Settings> = old.settings.get().map(Settings::from);
+        Self {
+            token: old.token,
+            metadata: old.metadata,
+            settings: LazyOption::new(StorageKeys::Settings, settings.as_ref()),
+            minted_in_period: MintedInPeriod {
+                period_start: 0,
+                amount: U128(0),
+            },
+            vestings: LookupMap::new(StorageKeys::Vestings),
+        }
+    }
+
+    fn decimals_multiplier(&self) -> Balance {
+        10u128.pow(self.metadata.get().unwrap().decimals as u32)
+    }
+
     fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
         log!("Closed @{} with {}", account_id, balance);
     }
@@ -140,6 +296,7 @@ impl Contract {
     }
 
     /// Changes current settings to `settings` provided.
+    /// Max supply and mint rate limit cannot be loosened.
     /// Only owner is allowed to call this function.
     pub fn change_settings(&mut self, settings: Settings) {
         let prev_settings = self.settings.get().expect("No settings.");
@@ -147,9 +304,12 @@ impl Contract {
             prev_settings.owner_id == env::predecessor_account_id(),
             "No rights."
         );
+        prev_settings.assert_supply_policy_kept(&settings);
         self.settings.set(&settings);
     }
 
+    /// Mints `amount` of new tokens to the owner.
+    /// Fails if it would exceed max supply or mint rate limit.
     pub fn mint_new_ft(&mut self, amount: U128, msg: Option<String>) {
         let settings = self.settings.get().expect("No settings.");
         require!(
@@ -157,6 +317,24 @@ impl Contract {
             "No rights."
         );
         require!(settings.mint_allowed, "Minting new tokens is not allowed.");
+        if let Some(max_supply) = settings.max_supply {
+            require!(
+                self.token.total_supply + amount.0 <= max_supply.0,
+                "Max supply exceeded."
+            );
+        }
+        if let Some(limit) = settings.mint_rate_limit {
+            let now = env::block_timestamp() / 10u64.pow(9);
+            if now >= self.minted_in_period.period_start + limit.period {
+                self.minted_in_period = MintedInPeriod {
+                    period_start: now,
+                    amount: U128(0),
+                };
+            }
+            let minted = self.minted_in_period.amount.0 + amount.0;
+            require!(minted <= limit.amount.0, "Mint rate limit exceeded.");
+            self.minted_in_period.amount = U128(minted);
+        }
         self.token.internal_deposit(&settings.owner_id, amount.0);
         let msg = format!(
             "Minted {} new tokens. {}",
@@ -171,6 +349,62 @@ impl Contract {
         .emit();
     }
 
+    /// Burns `amount` of caller's tokens.
+    /// Requires exactly 1 yoctoNEAR attached.
+    #[payable]
+    pub fn ft_burn(&mut self, amount: U128, memo: Option<String>) {
+        assert_one_yocto();
+        let settings = self.settings.get().expect("No settings.");
+        require!(settings.burn_allowed, "Burning tokens is not allowed.");
+        require!(amount.0 > 0, "The amount should be a positive number");
+        let account_id = env::predecessor_account_id();
+        self.token.internal_withdraw(&account_id, amount.0);
+        FtBurn {
+            owner_id: &account_id,
+            amount: &amount,
+            memo: memo.as_deref(),
+        }
+        .emit();
+    }
+
+    /// Transfers all unlocked vested tokens to the caller.
+    /// Returns claimed amount.
+    pub fn claim_vested(&mut self) -> U128 {
+        let account_id = env::predecessor_account_id();
+        let mut vesting = self.vestings.get(&account_id).expect("No vesting.");
+        vesting.unlock(env::block_timestamp() / 10u64.pow(9));
+        let amount = vesting.withdraw(vesting.available()) as u128 * self.decimals_multiplier();
+        self.vestings.insert(&account_id, &vesting);
+        if amount > 0 {
+            self.token.internal_transfer(
+                &env::current_account_id(),
+                &account_id,
+                amount,
+                Some("vesting claim".to_string()),
+            );
+        }
+        amount.into()
+    }
+
+    /// Returns vesting of `account_id` at current time.
+    pub fn vesting_of(&self, account_id: AccountId) -> Option<VestingInfo> {
+        self.vestings.get(&account_id).map(|mut vesting| {
+            vesting.unlock(env::block_timestamp() / 10u64.pow(9));
+            let multiplier = self.decimals_multiplier();
+            VestingInfo {
+                total: (vesting.total_locked() as u128 * multiplier).into(),
+                locked: ((vesting.total_locked() - vesting.total_unlocked()) as u128 * multiplier)
+                    .into(),
+                unlocked: (vesting.available() as u128 * multiplier).into(),
+            }
+        })
+    }
+
+    /// Tokens minted in the current mint rate limit period.
+    pub fn minted_in_period(&self) -> MintedInPeriod {
+        self.minted_in_period.clone()
+    }
+
     pub fn settings(&self) -> Option<Settings> {
         self.settings.get()
     }
@@ -292,6 +526,7 @@ mod tests {
     use near_sdk::{testing_env, Balance};
 
     use super::*;
+    use library::locking::{UnlockMethod, UnlockPeriodInput};
 
     const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
     pub const FT_METADATA_SPEC: &str = "ft-1.0.0";
@@ -332,14 +567,17 @@ mod tests {
                 InitDistribution {
                     account_id: accounts(2),
                     amount: U128(1_000),
+                    vesting: None,
                 },
                 InitDistribution {
                     account_id: accounts(3),
                     amount: U128(7_000),
+                    vesting: None,
                 },
                 InitDistribution {
                     account_id: accounts(4),
                     amount: U128(2_000),
+                    vesting: None,
                 },
             ],
         );
@@ -351,6 +589,30 @@ mod tests {
         assert_eq!(contract.ft_balance_of(accounts(4)).0, 2_000);
     }
 
+    #[test]
+    #[should_panic(expected = "Duplicate init distribution account.")]
+    fn test_new_duplicate_distribution() {
+        testing_env!(get_context(accounts(1)).build());
+        Contract::new(
+            accounts(1),
+            TOTAL_SUPPLY.into(),
+            default_ft_metadata(),
+            None,
+            vec![
+                InitDistribution {
+                    account_id: accounts(2),
+                    amount: U128(1_000),
+                    vesting: None,
+                },
+                InitDistribution {
+                    account_id: accounts(2),
+                    amount: U128(2_000),
+                    vesting: None,
+                },
+            ],
+        );
+    }
+
     #[test]
     #[should_panic(expected = "The contract is not initialized")]
     fn test_default() {
@@ -398,4 +660,220 @@ mod tests {
         );
         assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount);
     }
+
+    fn settings_with_policy(
+        owner_id: AccountId,
+        max_supply: Option<Balance>,
+        mint_rate_limit: Option<MintRateLimit>,
+    ) -> Settings {
+        Settings {
+            owner_id,
+            mint_allowed: true,
+            burn_allowed: true,
+            upgrade_provider: None,
+            max_supply: max_supply.map(U128),
+            mint_rate_limit,
+        }
+    }
+
+    #[test]
+    fn test_burn() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new(
+            accounts(1),
+            TOTAL_SUPPLY.into(),
+            default_ft_metadata(),
+            Some(settings_with_policy(accounts(1), None, None)),
+            vec![],
+        );
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_burn(U128(1_000), Some("burn".to_string()));
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY - 1_000);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY - 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Burning tokens is not allowed.")]
+    fn test_burn_not_allowed() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new(
+            accounts(1),
+            TOTAL_SUPPLY.into(),
+            default_ft_metadata(),
+            None,
+            vec![],
+        );
+        testing_env!(context.attached_deposit(1).build());
+        contract.ft_burn(U128(1_000), None);
+    }
+
+    fn contract_with_policy(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.build());
+        Contract::new(
+            accounts(1),
+            TOTAL_SUPPLY.into(),
+            default_ft_metadata(),
+            Some(settings_with_policy(
+                accounts(1),
+                Some(TOTAL_SUPPLY + 1_500),
+                Some(MintRateLimit {
+                    amount: U128(1_000),
+                    period: 100,
+                }),
+            )),
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_mint_supply_policy() {
+        let mut context = get_context(accounts(1));
+        let mut contract = contract_with_policy(&mut context);
+        testing_env!(context.block_timestamp(10 * 10u64.pow(9)).build());
+        contract.mint_new_ft(U128(600), None);
+        assert_eq!(contract.minted_in_period().amount.0, 600);
+
+        // Next period.
+        testing_env!(context.block_timestamp(110 * 10u64.pow(9)).build());
+        contract.mint_new_ft(U128(900), None);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_500);
+        assert_eq!(contract.minted_in_period().amount.0, 900);
+
+        // Policy can be tightened.
+        contract.change_settings(settings_with_policy(
+            accounts(1),
+            Some(TOTAL_SUPPLY + 1_500),
+            Some(MintRateLimit {
+                amount: U128(500),
+                period: 200,
+            }),
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Mint rate limit exceeded.")]
+    fn test_mint_rate_limit_exceeded() {
+        let mut context = get_context(accounts(1));
+        let mut contract = contract_with_policy(&mut context);
+        testing_env!(context.block_timestamp(10 * 10u64.pow(9)).build());
+        contract.mint_new_ft(U128(600), None);
+        contract.mint_new_ft(U128(600), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max supply exceeded.")]
+    fn test_mint_max_supply_exceeded() {
+        let mut context = get_context(accounts(1));
+        let mut contract = contract_with_policy(&mut context);
+        testing_env!(context.block_timestamp(10 * 10u64.pow(9)).build());
+        contract.mint_new_ft(U128(1_000), None);
+        testing_env!(context.block_timestamp(110 * 10u64.pow(9)).build());
+        contract.mint_new_ft(U128(1_000), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max supply cannot be raised.")]
+    fn test_max_supply_cannot_be_raised() {
+        let mut context = get_context(accounts(1));
+        let mut contract = contract_with_policy(&mut context);
+        contract.change_settings(settings_with_policy(accounts(1), None, None));
+    }
+
+    #[test]
+    fn test_vesting() {
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let unit = 10u128.pow(24);
+        let mut contract = Contract::new(
+            accounts(1),
+            TOTAL_SUPPLY.into(),
+            default_ft_metadata(),
+            None,
+            vec![InitDistribution {
+                account_id: accounts(2),
+                amount: U128(1_000),
+                vesting: Some(LockInput {
+                    amount_total_lock: 100,
+                    start_from: 0,
+                    duration: 100,
+                    periods: vec![UnlockPeriodInput {
+                        r#type: UnlockMethod::Linear,
+                        duration: 100,
+                        amount: 100,
+                    }],
+                }),
+            }],
+        );
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 100 * unit);
+        assert_eq!(
+            contract.ft_balance_of(accounts(1)).0,
+            TOTAL_SUPPLY - 1_000 - 100 * unit
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .block_timestamp(50 * 10u64.pow(9))
+            .build());
+        let vesting = contract.vesting_of(accounts(2)).unwrap();
+        assert_eq!(vesting.total.0, 100 * unit);
+        assert_eq!(vesting.locked.0, 50 * unit);
+        assert_eq!(vesting.unlocked.0, 50 * unit);
+        assert_eq!(contract.claim_vested().0, 50 * unit);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 1_000 + 50 * unit);
+
+        testing_env!(context.block_timestamp(200 * 10u64.pow(9)).build());
+        assert_eq!(contract.claim_vested().0, 50 * unit);
+        let vesting = contract.vesting_of(accounts(2)).unwrap();
+        assert_eq!(vesting.locked.0, 0);
+        assert_eq!(vesting.unlocked.0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(0)).0, 0);
+    }
+
+    /// Contract state layout as deployed before supply policy and vestings were added.
+    #[derive(BorshSerialize)]
+    struct ContractBaseline {
+        token: FungibleToken,
+        metadata: LazyOption<FungibleTokenMetadata>,
+        settings: LazyOption<SettingsV1>,
+    }
+
+    #[test]
+    fn test_deploy_upgrade() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut token = FungibleToken::new(StorageKeys::Token);
+        token.internal_register_account(&accounts(1));
+        token.internal_deposit(&accounts(1), TOTAL_SUPPLY);
+        let metadata = LazyOption::new(StorageKeys::TokenMeta, Some(&default_ft_metadata()));
+        let settings = LazyOption::new(
+            StorageKeys::Settings,
+            Some(&SettingsV1 {
+                owner_id: accounts(1),
+                mint_allowed: true,
+                burn_allowed: false,
+                upgrade_provider: None,
+            }),
+        );
+        env::state_write(&ContractBaseline {
+            token,
+            metadata,
+            settings,
+        });
+
+        let mut contract = Contract::deploy_upgrade();
+        let settings = contract.settings().unwrap();
+        assert_eq!(settings.owner_id, accounts(1));
+        assert!(settings.mint_allowed);
+        assert!(settings.max_supply.is_none());
+        assert!(settings.mint_rate_limit.is_none());
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY);
+        assert!(contract.vesting_of(accounts(1)).is_none());
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.mint_new_ft(U128(1_000), None);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1_000);
+    }
 }
@@ -1,3 +1,4 @@
+use library::TimestampSec;
 use near_sdk::{Balance, Gas, StorageUsage};
 
 pub const U64_LEN: StorageUsage = 8;
@@ -16,6 +17,20 @@ pub const GAS_FOR_REGISTER: Gas = Gas(15_000_000_000_000);
 /// Amount of gas for undelegate action.
 pub const GAS_FOR_UNDELEGATE: Gas = Gas(15_000_000_000_000);
 
+/// Minimal duration of vote lock in seconds (1 week).
+pub const MIN_VOTE_LOCK_DURATION: TimestampSec = 7 * 24 * 3600;
+/// Maximal duration of vote lock in seconds (4 years).
+pub const MAX_VOTE_LOCK_DURATION: TimestampSec = 4 * 365 * 24 * 3600;
+/// Extra vote weight multiplier of tokens locked for the maximal duration.
+pub const MAX_VOTE_LOCK_BOOST: u128 = 3;
+
+/// Maximal number of vote weight checkpoints kept per user.
+/// The oldest checkpoints are pruned.
+pub const MAX_VOTE_WEIGHT_CHECKPOINTS: usize = 64;
+
+/// Default number of users processed in one mass withdraw batch.
+pub const DEFAULT_MASS_WITHDRAW_LIMIT: u32 = 10;
+
 //pub const STANDARD_FT_STORAGE_DEPOSIT: Balance = 1_250_000_000_000_000_000_000;
 pub const FT_STORAGE_DEPOSIT: Balance = 3_020_000_000_000_000_000_000;
 
@@ -1,30 +1,102 @@
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
     collections::UnorderedMap,
+    env,
     json_types::U128,
-    require, AccountId, Balance,
+    require,
+    serde::{Deserialize, Serialize},
+    AccountId, Balance,
 };
 
-use crate::{User, VersionedUser};
+use library::{functions::utils::mul_div_u128, TimestampSec};
+
+use crate::{user::WeightChange, User, VersionedUser};
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct Dao {
     pub account_id: AccountId,
-    /// Vote token account.
-    pub vote_token_id: AccountId,
+    /// Accepted vote tokens.
+    /// The first one is the token the dao was registered with.
+    pub vote_tokens: Vec<VoteToken>,
     /// Recording user deposits.
     pub users: UnorderedMap<AccountId, VersionedUser>,
-    /// Total token amount deposited.
+    /// Total vote weight of deposited tokens.
     pub total_amount: Balance,
+    /// Time in seconds for which undelegated and withdrawn tokens stay locked.
+    pub unbonding_period: TimestampSec,
+    /// Dao is being unregistered and its users are withdrawn.
+    pub unregistering: bool,
+    /// Number of mass withdraw transfers waiting for their callback.
+    /// Unregistering dao is removed only after all of them are resolved.
+    pub pending_withdraws: u32,
+}
+
+/// Dao data before unbonding period and multiple vote tokens were supported.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct DaoV1 {
+    pub account_id: AccountId,
+    pub vote_token_id: AccountId,
+    pub users: UnorderedMap<AccountId, VersionedUser>,
+    pub total_amount: Balance,
+}
+
+impl From<DaoV1> for Dao {
+    fn from(dao: DaoV1) -> Self {
+        Self {
+            account_id: dao.account_id,
+            vote_tokens: vec![VoteToken::new(dao.vote_token_id, 1, 1)],
+            users: dao.users,
+            total_amount: dao.total_amount,
+            unbonding_period: 0,
+            unregistering: false,
+            pending_withdraws: 0,
+        }
+    }
+}
+
+/// Vote token accepted by dao.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteToken {
+    pub token_id: AccountId,
+    /// Vote weight of one token is `weight_numerator / weight_denominator`.
+    pub weight_numerator: u128,
+    pub weight_denominator: u128,
+}
+
+impl VoteToken {
+    pub fn new(token_id: AccountId, weight_numerator: u128, weight_denominator: u128) -> Self {
+        require!(
+            weight_numerator > 0 && weight_denominator > 0,
+            "invalid vote token weight"
+        );
+        Self {
+            token_id,
+            weight_numerator,
+            weight_denominator,
+        }
+    }
+
+    /// Vote weight of `amount` tokens.
+    pub fn weight_of(&self, amount: u128) -> u128 {
+        mul_div_u128(amount, self.weight_numerator, self.weight_denominator)
+            .expect("vote weight overflow")
+    }
 }
 
 impl Dao {
     fn save_user(&mut self, account_id: &AccountId, user: User) {
-        self.users.insert(account_id, &VersionedUser::V1(user));
+        self.users.insert(account_id, &VersionedUser::V2(user));
     }
 
     /// Delegate `amount` of votes from `sender_id` to `delegate_id` account.
-    pub fn delegate_owned(&mut self, sender_id: AccountId, delegate_id: AccountId, amount: u128) {
+    /// Returns changed delegation weights of the sender.
+    pub fn delegate_owned(
+        &mut self,
+        sender_id: AccountId,
+        delegate_id: AccountId,
+        amount: u128,
+    ) -> Vec<WeightChange> {
         let mut sender = self.get_user(&sender_id);
         sender.delegate_owned(delegate_id.clone(), amount);
         if sender_id != delegate_id {
@@ -34,11 +106,34 @@ impl Dao {
         } else {
             sender.add_delegator(sender_id.clone(), amount);
         }
+        let changes = sender.sync_weights(current_timestamp());
         self.save_user(&sender_id, sender);
+        self.apply_weight_changes(&changes);
+        changes
     }
 
     /// Undelegates `amount` from `delegate_id` account back to `sender`id account.
-    pub fn undelegate(&mut self, sender_id: AccountId, delegate_id: AccountId, amount: u128) {
+    /// Returns changed delegation weights of the sender.
+    pub fn undelegate(
+        &mut self,
+        sender_id: AccountId,
+        delegate_id: AccountId,
+        amount: u128,
+    ) -> Vec<WeightChange> {
+        self.internal_undelegate(sender_id, delegate_id, amount, None)
+    }
+
+    /// Undelegates `amount` from `delegate_id` account.
+    /// If dao has unbonding period, the amount stays locked until it ends
+    /// and is delegated to `redelegate_to` once claimed.
+    /// Returns changed delegation weights of the sender.
+    fn internal_undelegate(
+        &mut self,
+        sender_id: AccountId,
+        delegate_id: AccountId,
+        amount: u128,
+        redelegate_to: Option<AccountId>,
+    ) -> Vec<WeightChange> {
         let mut sender = self.get_user(&sender_id);
         let remaining_amount = sender.undelegate(&delegate_id, amount);
         if sender_id != delegate_id {
@@ -48,30 +143,203 @@ impl Dao {
         } else {
             sender.remove_delegated_amount(&sender_id, amount, remaining_amount);
         }
+        if self.unbonding_period > 0 {
+            sender.add_pending_unbond(amount, self.release_timestamp(), None, redelegate_to);
+        }
+        let changes = sender.sync_weights(current_timestamp());
         self.save_user(&sender_id, sender);
+        self.apply_weight_changes(&changes);
+        changes
+    }
+
+    /// Locks `amount` of owned tokens of `sender_id` for `duration` seconds from now.
+    /// Returns changed delegation weights of the sender.
+    pub fn lock(
+        &mut self,
+        sender_id: &AccountId,
+        amount: u128,
+        duration: TimestampSec,
+    ) -> Vec<WeightChange> {
+        let now = current_timestamp();
+        let mut sender = self.get_user(sender_id);
+        sender.lock(amount, now + duration, now);
+        let changes = sender.sync_weights(now);
+        self.save_user(sender_id, sender);
+        self.apply_weight_changes(&changes);
+        changes
+    }
+
+    /// Recalculates decayed delegation weights of `account_id`.
+    pub fn poke(&mut self, account_id: &AccountId) -> Vec<WeightChange> {
+        let mut user = self.get_user(account_id);
+        let changes = user.sync_weights(current_timestamp());
+        self.save_user(account_id, user);
+        self.apply_weight_changes(&changes);
+        changes
+    }
+
+    /// Moves `amount` of owned delegation from `from_id` to `to_id` account.
+    /// Must not be used when dao has unbonding period, see `redelegate_unbonding`.
+    /// Returns moved delegation weight.
+    pub fn redelegate(
+        &mut self,
+        sender_id: &AccountId,
+        from_id: &AccountId,
+        to_id: &AccountId,
+        amount: u128,
+    ) -> u128 {
+        require!(from_id != to_id, "same delegate");
+        // Users are loaded and saved one by one as any of them might be the same account.
+        let mut sender = self.get_user(sender_id);
+        let weight = proportional_weight(&sender, from_id, amount);
+        let remaining_amount = sender.undelegate(from_id, amount);
+        sender.add_delegation(to_id.clone(), amount);
+        sender.move_delegation_weight(from_id, to_id, weight);
+        self.save_user(sender_id, sender);
+        let mut from = self.get_user(from_id);
+        from.remove_delegated_amount(sender_id, amount, remaining_amount);
+        self.save_user(from_id, from);
+        let mut to = self.get_user(to_id);
+        to.add_delegator(sender_id.clone(), amount);
+        self.save_user(to_id, to);
+        self.transfer_vote_weight(from_id, to_id, weight);
+        weight
+    }
+
+    /// Undelegates `amount` of owned delegation from `from_id` into pending unbond.
+    /// The amount is delegated to `to_id` once claimed after the unbonding period,
+    /// so it cannot be used for voting by both delegates within one period.
+    /// Returns changed delegation weights of the sender.
+    pub fn redelegate_unbonding(
+        &mut self,
+        sender_id: &AccountId,
+        from_id: &AccountId,
+        to_id: &AccountId,
+        amount: u128,
+    ) -> Vec<WeightChange> {
+        require!(from_id != to_id, "same delegate");
+        require!(
+            self.users.get(to_id).is_some(),
+            "account not registered in dao"
+        );
+        self.internal_undelegate(
+            sender_id.clone(),
+            from_id.clone(),
+            amount,
+            Some(to_id.clone()),
+        )
     }
 
     /// Delegate all delegated tokens aka transitive delegation.
-    /// Once delegated - cannot be undeleged.
+    /// Forwarded delegations are recorded so they can be restored by `undelegate_transitive`.
+    /// Returns forwarded delegation weight.
     pub fn delegate(&mut self, sender_id: &AccountId, delegate_id: AccountId) -> u128 {
+        require!(*sender_id != delegate_id, "cannot delegate to self");
         let mut sender = self.get_user(sender_id);
-        let (amount, delegators) = sender.forward_delegated();
-        self.update_user_delegations(sender_id, &delegate_id, &delegators);
-        self.update_delegate(&delegate_id, amount, delegators);
+        let (_, delegators) = sender.forward_delegated();
         self.save_user(sender_id, sender);
-        amount
+        let mut weight = 0;
+        let delegations: Vec<(AccountId, u128)> = delegators
+            .into_iter()
+            .map(|account_id| {
+                let user = self.get_user(&account_id);
+                weight += user.delegation_weight(sender_id);
+                let amount = user.delegated_amount_to(sender_id);
+                (account_id, amount)
+            })
+            .collect();
+        self.update_user_delegations(sender_id, &delegate_id, &delegations);
+        self.update_delegate(&delegate_id, &delegations);
+        let mut sender = self.get_user(sender_id);
+        sender.add_forwarded(delegate_id.clone(), delegations);
+        self.save_user(sender_id, sender);
+        self.transfer_vote_weight(sender_id, &delegate_id, weight);
+        weight
     }
 
-    /// Update delegate with delegators.
-    fn update_delegate(
+    /// Restores delegations forwarded by `sender_id` to `delegate_id` back to the original delegators.
+    /// Delegations which the delegate has forwarded further are not restored.
+    /// Returns restored delegation weight.
+    pub fn undelegate_transitive(
         &mut self,
+        sender_id: &AccountId,
         delegate_id: &AccountId,
-        amount: u128,
-        delegators: Vec<AccountId>,
-    ) {
+    ) -> u128 {
+        let mut sender = self.get_user(sender_id);
+        let delegations = sender.take_forwarded(delegate_id);
+        self.save_user(sender_id, sender);
+        let mut total_weight = 0;
+        for (account_id, amount) in delegations {
+            let mut user = self.get_user(&account_id);
+            let amount = amount.min(user.delegated_amount_to(delegate_id));
+            if amount == 0 {
+                continue;
+            }
+            let weight = proportional_weight(&user, delegate_id, amount);
+            let remaining_amount = user.undelegate(delegate_id, amount);
+            user.add_delegation(sender_id.clone(), amount);
+            user.move_delegation_weight(delegate_id, sender_id, weight);
+            self.save_user(&account_id, user);
+            let mut delegate = self.get_user(delegate_id);
+            delegate.remove_delegated_amount(&account_id, amount, remaining_amount);
+            self.save_user(delegate_id, delegate);
+            let mut sender = self.get_user(sender_id);
+            sender.add_delegator(account_id, amount);
+            self.save_user(sender_id, sender);
+            total_weight += weight;
+        }
+        self.transfer_vote_weight(delegate_id, sender_id, total_weight);
+        total_weight
+    }
+
+    /// Undelegates delegations forwarded by `sender_id` to `delegate_id` into pending unbonds
+    /// of the original delegators. They are delegated back to `sender_id` once claimed
+    /// after the unbonding period.
+    /// Returns changed delegation weights of the original delegators.
+    pub fn undelegate_transitive_unbonding(
+        &mut self,
+        sender_id: &AccountId,
+        delegate_id: &AccountId,
+    ) -> Vec<WeightChange> {
+        let mut sender = self.get_user(sender_id);
+        let delegations = sender.take_forwarded(delegate_id);
+        self.save_user(sender_id, sender);
+        let mut changes = vec![];
+        for (account_id, amount) in delegations {
+            let amount = amount.min(self.get_user(&account_id).delegated_amount_to(delegate_id));
+            if amount == 0 {
+                continue;
+            }
+            changes.extend(self.internal_undelegate(
+                account_id,
+                delegate_id.clone(),
+                amount,
+                Some(sender_id.clone()),
+            ));
+        }
+        changes
+    }
+
+    /// Applies changed delegation weights to the delegates and records their checkpoints.
+    fn apply_weight_changes(&mut self, changes: &[WeightChange]) {
+        let now = current_timestamp();
+        for (delegate_id, prev_weight, new_weight) in changes {
+            let mut delegate = self.get_user(delegate_id);
+            delegate.update_vote_weight(*prev_weight, *new_weight, now);
+            self.save_user(delegate_id, delegate);
+        }
+    }
+
+    /// Moves `weight` of delegated vote weight from `from_id` to `to_id`.
+    fn transfer_vote_weight(&mut self, from_id: &AccountId, to_id: &AccountId, weight: u128) {
+        self.apply_weight_changes(&[(from_id.clone(), weight, 0), (to_id.clone(), 0, weight)]);
+    }
+
+    /// Update delegate with delegators.
+    fn update_delegate(&mut self, delegate_id: &AccountId, delegations: &[(AccountId, u128)]) {
         let mut delegate = self.get_user(delegate_id);
-        for user in delegators {
-            delegate.add_delegator(user, amount);
+        for (user, amount) in delegations {
+            delegate.add_delegator(user.clone(), *amount);
         }
         self.save_user(delegate_id, delegate);
     }
@@ -81,41 +349,137 @@ impl Dao {
         &mut self,
         prev_delegate_id: &AccountId,
         new_delegate_id: &AccountId,
-        users: &[AccountId],
+        users: &[(AccountId, u128)],
     ) {
-        for acc in users {
+        for (acc, _) in users {
             let mut user = self.get_user(acc);
             user.update_delegation(prev_delegate_id, new_delegate_id);
             self.save_user(acc, user);
         }
     }
 
+    /// Returns vote token `token_id`.
+    /// Fails if dao does not accept the token.
+    pub fn vote_token(&self, token_id: &AccountId) -> &VoteToken {
+        self.vote_tokens
+            .iter()
+            .find(|t| t.token_id == *token_id)
+            .expect("invalid token")
+    }
+
+    /// Adds new accepted vote token.
+    pub fn add_vote_token(&mut self, token: VoteToken) {
+        require!(
+            self.vote_tokens
+                .iter()
+                .all(|t| t.token_id != token.token_id),
+            "vote token already added"
+        );
+        self.vote_tokens.push(token);
+    }
+
     /// Deposit voting token.
-    pub fn user_deposit(&mut self, sender_id: &AccountId, amount: u128) {
+    /// Returns deposited vote weight.
+    pub fn user_deposit(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &AccountId,
+        amount: u128,
+    ) -> u128 {
+        let token = self.vote_token(token_id).clone();
         let mut sender = self.get_user(sender_id);
-        sender.deposit(amount);
+        let weight = sender.deposit(&token, amount);
         self.save_user(sender_id, sender);
-        self.total_amount += amount;
+        self.total_amount += weight;
+        weight
     }
 
     /// Withdraw owned tokens.
-    pub fn user_withdraw(&mut self, sender_id: &AccountId, amount: u128) {
+    pub fn user_withdraw(&mut self, sender_id: &AccountId, token_id: &AccountId, amount: u128) {
+        let token = self.vote_token(token_id).clone();
+        let mut sender = self.get_user(sender_id);
+        let weight = sender.withdraw(&token, amount, current_timestamp());
+        self.save_user(sender_id, sender);
+        require!(self.total_amount >= weight, "internal user withdraw");
+        self.total_amount -= weight;
+    }
+
+    /// Withdraw owned tokens into pending unbond.
+    /// Withdrawn amount no longer counts as staked and can be claimed after the unbonding period.
+    pub fn user_unbond(&mut self, sender_id: &AccountId, token_id: &AccountId, amount: u128) {
+        let token = self.vote_token(token_id).clone();
         let mut sender = self.get_user(sender_id);
-        sender.withdraw(amount);
+        let weight = sender.withdraw(&token, amount, current_timestamp());
+        sender.add_pending_unbond(amount, self.release_timestamp(), Some(token.token_id), None);
         self.save_user(sender_id, sender);
-        require!(self.total_amount >= amount, "internal user withdraw");
-        self.total_amount -= amount;
+        require!(self.total_amount >= weight, "internal user withdraw");
+        self.total_amount -= weight;
+    }
+
+    /// Claim all released pending unbonds.
+    /// Moved delegations are delegated to their new delegates.
+    /// They are released to the user if the delegate is no longer registered.
+    /// Returns amounts of each token to be transferred to the user and changed delegation weights.
+    pub fn user_claim_unbonded(
+        &mut self,
+        sender_id: &AccountId,
+    ) -> (Vec<(AccountId, u128)>, Vec<WeightChange>) {
+        let mut sender = self.get_user(sender_id);
+        let (amounts, delegations) = sender.claim_unbonded(current_timestamp());
+        self.save_user(sender_id, sender);
+        let mut changes = vec![];
+        for (delegate_id, amount) in delegations {
+            if self.users.get(&delegate_id).is_some() {
+                changes.extend(self.delegate_owned(sender_id.clone(), delegate_id, amount));
+            }
+        }
+        (amounts, changes)
+    }
+
+    /// Reverts failed claim transfer by adding the amount back as already released.
+    pub fn user_claim_revert(&mut self, sender_id: &AccountId, token_id: AccountId, amount: u128) {
+        let mut sender = self.get_user(sender_id);
+        sender.add_pending_unbond(amount, current_timestamp(), Some(token_id), None);
+        self.save_user(sender_id, sender);
+    }
+
+    fn release_timestamp(&self) -> TimestampSec {
+        current_timestamp() + self.unbonding_period
+    }
+
+    /// Fails if dao is being unregistered.
+    pub fn assert_active(&self) {
+        require!(!self.unregistering, "dao is unregistering");
+    }
+
+    /// Removes up to `limit` users.
+    /// Returns removed users with all their token amounts.
+    pub fn remove_users(&mut self, limit: usize) -> Vec<(AccountId, Vec<(AccountId, u128)>)> {
+        let account_ids: Vec<AccountId> = self.users.keys().take(limit).collect();
+        account_ids
+            .into_iter()
+            .map(|account_id| {
+                let user = self
+                    .users
+                    .remove(&account_id)
+                    .unwrap()
+                    .into_user(&self.vote_tokens[0].token_id);
+                self.total_amount = self.total_amount.saturating_sub(user.vote_amount);
+                (account_id, user.all_token_amounts())
+            })
+            .collect()
     }
 
     /// Register user in dao.
     pub fn register_user(&mut self, sender_id: &AccountId) {
         require!(self.users.get(sender_id).is_none(), "already registered");
-        let user = User::new();
+        let mut user = User::new();
+        user.update_vote_weight(0, 0, current_timestamp());
         self.save_user(sender_id, user);
     }
 
     /// Remove user from DAO.
-    /// Fails if `sender_id` account has non-zero owned/delegated tokens.
+    /// Fails if `sender_id` account has non-zero owned/delegated/unbonding tokens.
     pub fn unregister_user(&mut self, sender_id: &AccountId) {
         let user = self.get_user(sender_id);
         require!(user.vote_amount == 0, "non-zero amount of vote tokens");
@@ -123,27 +487,61 @@ impl Dao {
             user.delegated_vote_amount == 0,
             "non-zero amount of delegated vote tokens"
         );
+        require!(
+            user.pending_unbonds.is_empty(),
+            "non-zero amount of unbonding vote tokens"
+        );
         self.users.remove(sender_id);
     }
 
-    /// Total number of tokens staked in this dao.
+    /// Total vote weight of tokens staked in this dao.
     pub fn ft_total_supply(&self) -> U128 {
         U128(self.total_amount)
     }
 
-    /// Total number of tokens staked by given user.
+    /// Total vote weight of tokens staked by given user.
     pub fn ft_balance_of(&self, account_id: AccountId) -> U128 {
         let user = self.get_user(&account_id);
         user.vote_amount.into()
     }
 
+    /// Withdrawn amount of `token_id` in unbonding of given user.
+    pub fn ft_unbonding_of(&self, account_id: AccountId, token_id: &AccountId) -> U128 {
+        let user = self.get_user(&account_id);
+        user.pending_unbond_amount(token_id).into()
+    }
+
+    /// Delegated vote weight of given user at `timestamp`.
+    pub fn vote_weight_at(&self, account_id: &AccountId, timestamp: TimestampSec) -> Option<U128> {
+        self.get_user(account_id)
+            .vote_weight_at(timestamp)
+            .map(U128)
+    }
+
     /// Returns user information.
     pub fn get_user(&self, account_id: &AccountId) -> User {
         self.users
             .get(account_id)
-            .map(|versioned_user| match versioned_user {
-                VersionedUser::V1(user) => user,
-            })
+            .map(|versioned_user| versioned_user.into_user(&self.vote_tokens[0].token_id))
             .expect("account not registered in dao")
     }
 }
+
+fn current_timestamp() -> TimestampSec {
+    env::block_timestamp() / 10u64.pow(9)
+}
+
+/// Weight of `amount` out of `user`'s delegation to `delegate_id`.
+fn proportional_weight(user: &User, delegate_id: &AccountId, amount: u128) -> u128 {
+    let delegated_amount = user.delegated_amount_to(delegate_id);
+    require!(
+        delegated_amount >= amount,
+        "amount greater than delegated amount"
+    );
+    mul_div_u128(
+        user.delegation_weight(delegate_id),
+        amount,
+        delegated_amount,
+    )
+    .expect("internal delegation weight")
+}
@@ -6,8 +6,10 @@ use consts::{
     ACCOUNT_STATS_STORAGE, DAO_KEY_PREFIX, GAS_FOR_DELEGATE, GAS_FOR_FT_TRANSFER,
     GAS_FOR_UNDELEGATE, MIN_STORAGE,
 };
-use dao::Dao;
+pub use dao::VoteToken;
+use dao::{Dao, DaoV1};
 use library::functions::utils::into_storage_key_wrapper_u16;
+use library::TimestampSec;
 use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
 use near_contract_standards::storage_management::StorageBalance;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
@@ -15,14 +17,16 @@ use near_sdk::collections::{LookupMap, UnorderedMap};
 use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
-    env, ext_contract, near_bindgen, require, serde_json, AccountId, Balance, BorshStorageKey, Gas,
-    PanicOnDefault, Promise, PromiseOrValue, PromiseResult, StorageUsage,
+    env, ext_contract, log, near_bindgen, require, serde_json, AccountId, Balance, BorshStorageKey,
+    Gas, IntoStorageKey, PanicOnDefault, Promise, PromiseOrValue, PromiseResult, StorageUsage,
 };
 
-pub use user::{User, VersionedUser};
+use user::WeightChange;
+pub use user::{PendingUnbond, User, VersionedUser, VoteLock};
 
 use crate::consts::{
-    FT_STORAGE_DEPOSIT, GAS_FOR_REGISTER, MIN_REGISTER_DEPOSIT, MIN_STORAGE_FOR_DAO,
+    DEFAULT_MASS_WITHDRAW_LIMIT, FT_STORAGE_DEPOSIT, GAS_FOR_REGISTER, MAX_VOTE_LOCK_DURATION,
+    MIN_REGISTER_DEPOSIT, MIN_STORAGE_FOR_DAO, MIN_VOTE_LOCK_DURATION,
 };
 
 mod consts;
@@ -34,6 +38,7 @@ mod user;
 enum StorageKeys {
     Daos,
     StorageDeposit,
+    FailedWithdraws,
 }
 
 #[near_bindgen]
@@ -62,10 +67,41 @@ impl Contract {
         }
     }
 
+    /// Migrates `dao_ids` registered before unbonding period and multiple vote tokens were supported
+    /// to the current layout.
+    /// Already migrated daos are skipped. Users of the daos are converted when accessed.
+    /// Can be called repeatedly to migrate daos in batches.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(dao_ids: Vec<AccountId>) -> Self {
+        let mut contract: Contract = env::state_read().expect("failed to read contract state");
+        let prefix = StorageKeys::Daos.into_storage_key();
+        for dao_id in dao_ids {
+            let key = [prefix.as_slice(), &dao_id.try_to_vec().unwrap()].concat();
+            let old_dao = env::storage_read(&key).and_then(|raw| DaoV1::try_from_slice(&raw).ok());
+            if let Some(old_dao) = old_dao {
+                let storage_before = env::storage_usage();
+                // Old value cannot be deserialized as the current layout.
+                env::storage_remove(&key);
+                contract.save_dao(&dao_id, &old_dao.into());
+                let mut account_stats = contract.get_account_stats(&dao_id);
+                account_stats.add_storage_used(env::storage_usage() - storage_before);
+                contract.save_account_stats(&dao_id, &account_stats);
+            }
+        }
+        contract
+    }
+
     /// Registers new dao in contract.
     /// Dao must have done storage_deposit before this call.
+    /// Undelegated and withdrawn tokens are locked for `unbonding_period` seconds (default 0).
     #[payable]
-    pub fn register_new_dao(&mut self, dao_id: AccountId, vote_token_id: AccountId) -> Promise {
+    pub fn register_new_dao(
+        &mut self,
+        dao_id: AccountId,
+        vote_token_id: AccountId,
+        unbonding_period: Option<TimestampSec>,
+    ) -> Promise {
         let storage_deposit = env::attached_deposit();
         assert!(storage_deposit >= FT_STORAGE_DEPOSIT, "not enough deposit");
         let storage_before = env::storage_usage();
@@ -78,9 +114,12 @@ impl Contract {
 
         let dao_struct = Dao {
             account_id: dao_id.to_owned(),
-            vote_token_id: vote_token_id.clone(),
+            vote_tokens: vec![VoteToken::new(vote_token_id.clone(), 1, 1)],
             users,
             total_amount,
+            unbonding_period: unbonding_period.unwrap_or_default(),
+            unregistering: false,
+            pending_withdraws: 0,
         };
         require!(
             self.daos.insert(&dao_id, &dao_struct).is_none(),
@@ -90,18 +129,34 @@ impl Contract {
         let storage_diff = storage_after - storage_before;
         account_stats.add_storage_used(storage_diff);
         self.save_account_stats(&dao_id, &account_stats);
-        Promise::new(vote_token_id)
-            .function_call(
-                "storage_deposit".to_string(),
-                b"{\"registration_only\":true}".to_vec(),
-                storage_deposit,
-                Gas(10 * 10u64.pow(12)),
-            )
-            .then(
-                ext_self::ext(env::current_account_id())
-                    .with_static_gas(Gas(10 * 10u64.pow(12)))
-                    .return_deposit(env::predecessor_account_id(), storage_deposit),
-            )
+        internal_token_storage_deposit(vote_token_id, storage_deposit)
+    }
+
+    /// Adds another vote token accepted by dao.
+    /// Vote weight of one token is `weight_numerator / weight_denominator`
+    /// of the token the dao was registered with.
+    /// Only dao itself can add vote tokens.
+    #[payable]
+    pub fn add_vote_token(
+        &mut self,
+        dao_id: AccountId,
+        token_id: AccountId,
+        weight_numerator: U128,
+        weight_denominator: U128,
+    ) -> Promise {
+        require!(env::predecessor_account_id() == dao_id, "invalid caller");
+        let storage_deposit = env::attached_deposit();
+        require!(storage_deposit >= FT_STORAGE_DEPOSIT, "not enough deposit");
+        let storage_before = env::storage_usage();
+        let mut dao = self.get_dao(&dao_id);
+        dao.add_vote_token(VoteToken::new(
+            token_id.clone(),
+            weight_numerator.0,
+            weight_denominator.0,
+        ));
+        self.save_dao(&dao_id, &dao);
+        self.internal_update_storage_used(&dao_id, storage_before);
+        internal_token_storage_deposit(token_id, storage_deposit)
     }
 
     /// Registers caller in dao
@@ -118,6 +173,7 @@ impl Contract {
         let sender_id = env::predecessor_account_id();
         let mut account_stats = self.get_account_stats(&dao_id);
         let mut dao = self.get_dao(&dao_id);
+        dao.assert_active();
         dao.register_user(&sender_id);
         self.save_dao(&dao_id, &dao);
         let storage_after = env::storage_usage();
@@ -155,13 +211,90 @@ impl Contract {
         Promise::new(sender_id).transfer(MIN_REGISTER_DEPOSIT)
     }
 
+    /// Starts unregistering of dao.
+    /// New users and deposits are no longer accepted
+    /// and all users are withdrawn in batches of `limit` users by `dao_mass_withdraw`.
+    /// Only dao itself can unregister.
+    /// Returns number of remaining users.
+    pub fn unregister_dao(&mut self, dao_id: AccountId, limit: Option<u32>) -> u32 {
+        require!(env::predecessor_account_id() == dao_id, "invalid caller");
+        let mut dao = self.get_dao(&dao_id);
+        dao.unregistering = true;
+        self.save_dao(&dao_id, &dao);
+        self.dao_mass_withdraw(dao_id, limit)
+    }
+
+    /// Withdraws next batch of up to `limit` users of unregistering dao.
+    /// Their staked and pending tokens are transferred back together with their register deposit.
+    /// Failed transfers can be claimed by `claim_failed_withdraw`.
+    /// Once there are no users left and all transfers are resolved,
+    /// dao is removed and its storage deposit is refunded.
+    /// Can be called by anyone.
+    /// Returns number of remaining users.
+    pub fn dao_mass_withdraw(&mut self, dao_id: AccountId, limit: Option<u32>) -> u32 {
+        let storage_before = env::storage_usage();
+        let mut dao = self.get_dao(&dao_id);
+        require!(dao.unregistering, "dao is not unregistering");
+        let users = dao.remove_users(limit.unwrap_or(DEFAULT_MASS_WITHDRAW_LIMIT) as usize);
+        let mut account_stats = self.get_account_stats(&dao_id);
+        for (account_id, amounts) in users {
+            for (token_id, amount) in amounts {
+                dao.pending_withdraws += 1;
+                ext_fungible_token::ext(token_id.clone())
+                    .with_static_gas(GAS_FOR_FT_TRANSFER)
+                    .with_attached_deposit(1)
+                    .ft_transfer(account_id.clone(), amount.into(), None)
+                    .then(
+                        ext_self::ext(env::current_account_id())
+                            .with_static_gas(GAS_FOR_FT_TRANSFER)
+                            .exchange_callback_post_mass_withdraw(
+                                dao_id.clone(),
+                                account_id.clone(),
+                                token_id,
+                                amount.into(),
+                            ),
+                    );
+            }
+            Promise::new(account_id).transfer(MIN_REGISTER_DEPOSIT);
+            account_stats.dec_user_count();
+        }
+        self.save_dao(&dao_id, &dao);
+        account_stats.remove_storage_used(storage_before - env::storage_usage());
+        self.save_account_stats(&dao_id, &account_stats);
+        let remaining = dao.users.len() as u32;
+        self.internal_try_remove_dao(&dao_id, &dao);
+        remaining
+    }
+
+    /// Claims caller's `token_id` tokens whose mass withdraw transfer failed.
+    pub fn claim_failed_withdraw(&mut self, token_id: AccountId) -> Promise {
+        let sender_id = env::predecessor_account_id();
+        let mut failed_withdraws = failed_withdraws();
+        let amount = failed_withdraws
+            .remove(&(sender_id.clone(), token_id.clone()))
+            .expect("nothing to claim");
+        ext_fungible_token::ext(token_id.clone())
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .with_attached_deposit(1)
+            .ft_transfer(sender_id.clone(), amount.into(), None)
+            .then(
+                ext_self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_FT_TRANSFER)
+                    .exchange_callback_post_claim_failed_withdraw(
+                        sender_id,
+                        token_id,
+                        amount.into(),
+                    ),
+            )
+    }
+
     /// Delegates `amount` owned tokens to the `delegate_id`.
     pub fn delegate_owned(
         &mut self,
         dao_id: AccountId,
         delegate_id: AccountId,
         amount: U128,
-    ) -> Promise {
+    ) -> PromiseOrValue<()> {
         let sender_id = env::predecessor_account_id();
         self.internal_delegate_owned(dao_id, sender_id, delegate_id, amount.0)
     }
@@ -172,59 +305,194 @@ impl Contract {
         dao_id: AccountId,
         delegate_id: AccountId,
         amount: U128,
-    ) -> Promise {
+    ) -> PromiseOrValue<()> {
         let storage_before = env::storage_usage();
         let sender_id = env::predecessor_account_id();
         let mut dao = self.get_dao(&dao_id);
-        dao.undelegate(sender_id, delegate_id.clone(), amount.0);
+        let changes = dao.undelegate(sender_id, delegate_id, amount.0);
         self.save_dao(&dao_id, &dao);
-        let storage_after = env::storage_usage();
-        let mut account_stats = self.get_account_stats(&dao_id);
-        account_stats.remove_storage_used(storage_before - storage_after);
-        self.save_account_stats(&dao_id, &account_stats);
-        account_stats.assert_enough_deposit();
+        self.internal_update_storage_used(&dao_id, storage_before);
+        internal_report_weights(dao.account_id, changes)
+    }
+
+    /// Locks `amount` more of caller's owned tokens for `duration` seconds for boosted vote weight.
+    /// Duration must be between 1 week and 4 years and cannot shorten the current lock.
+    /// Locked tokens are added to the current lock and the whole lock is prolonged.
+    pub fn lock(
+        &mut self,
+        dao_id: AccountId,
+        amount: U128,
+        duration: TimestampSec,
+    ) -> PromiseOrValue<()> {
+        require!(
+            (MIN_VOTE_LOCK_DURATION..=MAX_VOTE_LOCK_DURATION).contains(&duration),
+            "invalid lock duration"
+        );
+        let storage_before = env::storage_usage();
+        let sender_id = env::predecessor_account_id();
+        let mut dao = self.get_dao(&dao_id);
+        let changes = dao.lock(&sender_id, amount.0, duration);
+        self.save_dao(&dao_id, &dao);
+        self.internal_update_storage_used(&dao_id, storage_before);
+        internal_report_weights(dao.account_id, changes)
+    }
+
+    /// Refreshes decayed vote weights of `account_id` delegations in dao.
+    /// Can be called by anyone.
+    pub fn poke(&mut self, dao_id: AccountId, account_id: AccountId) -> PromiseOrValue<()> {
+        let storage_before = env::storage_usage();
+        let mut dao = self.get_dao(&dao_id);
+        let changes = dao.poke(&account_id);
+        self.save_dao(&dao_id, &dao);
+        self.internal_update_storage_used(&dao_id, storage_before);
+        internal_report_weights(dao.account_id, changes)
+    }
+
+    /// Moves `amount` of caller's owned tokens delegated to `from_id` over to `to_id`.
+    /// If dao has unbonding period, the amount is undelegated from `from_id` immediately
+    /// and delegated to `to_id` once claimed by `claim_unbonded` after the period.
+    pub fn redelegate(
+        &mut self,
+        dao_id: AccountId,
+        from_id: AccountId,
+        to_id: AccountId,
+        amount: U128,
+    ) -> PromiseOrValue<()> {
+        let storage_before = env::storage_usage();
+        let sender_id = env::predecessor_account_id();
+        let mut dao = self.get_dao(&dao_id);
+        if dao.unbonding_period > 0 {
+            let changes = dao.redelegate_unbonding(&sender_id, &from_id, &to_id, amount.0);
+            self.save_dao(&dao_id, &dao);
+            self.internal_update_storage_used(&dao_id, storage_before);
+            return internal_report_weights(dao.account_id, changes);
+        }
+        let weight = dao.redelegate(&sender_id, &from_id, &to_id, amount.0);
+        self.save_dao(&dao_id, &dao);
+        self.internal_update_storage_used(&dao_id, storage_before);
         ext_dao::ext(dao.account_id)
             .with_static_gas(GAS_FOR_UNDELEGATE)
-            .undelegate(delegate_id, amount)
+            .transfer_amount(from_id, to_id, weight.into())
+            .into()
     }
 
     /// Delegate all delegated tokens from caller's delegators to `delegate_id`.
-    /// Once delegated, cannot be undelegated back.
+    /// Can be undone with `undelegate_transitive`.
     pub fn delegate(&mut self, dao_id: AccountId, delegate_id: AccountId) -> Promise {
         let storage_before = env::storage_usage();
         let sender_id = env::predecessor_account_id();
-        let mut account_stats = self.get_account_stats(&dao_id);
         let mut dao = self.get_dao(&dao_id);
         let amount = dao.delegate(&sender_id, delegate_id.clone());
         self.save_dao(&dao_id, &dao);
-        let storage_after = env::storage_usage();
-        if storage_after >= storage_before {
-            account_stats.add_storage_used(storage_after - storage_before);
-        } else {
-            account_stats.remove_storage_used(storage_before - storage_after);
-        }
-        self.save_account_stats(&dao_id, &account_stats);
-        account_stats.assert_enough_deposit();
+        self.internal_update_storage_used(&dao_id, storage_before);
         ext_dao::ext(dao.account_id.clone())
             .with_static_gas(GAS_FOR_UNDELEGATE)
             .transfer_amount(sender_id, delegate_id, amount.into())
     }
+
+    /// Returns delegations forwarded by caller to `delegate_id` back to their original delegators.
+    /// If dao has unbonding period, the delegations are undelegated from `delegate_id` immediately
+    /// and returned to the caller once claimed by `claim_unbonded` of each original delegator after the period.
+    pub fn undelegate_transitive(
+        &mut self,
+        dao_id: AccountId,
+        delegate_id: AccountId,
+    ) -> PromiseOrValue<()> {
+        let storage_before = env::storage_usage();
+        let sender_id = env::predecessor_account_id();
+        let mut dao = self.get_dao(&dao_id);
+        if dao.unbonding_period > 0 {
+            let changes = dao.undelegate_transitive_unbonding(&sender_id, &delegate_id);
+            self.save_dao(&dao_id, &dao);
+            self.internal_update_storage_used(&dao_id, storage_before);
+            return internal_report_weights(dao.account_id, changes);
+        }
+        let amount = dao.undelegate_transitive(&sender_id, &delegate_id);
+        self.save_dao(&dao_id, &dao);
+        self.internal_update_storage_used(&dao_id, storage_before);
+        ext_dao::ext(dao.account_id.clone())
+            .with_static_gas(GAS_FOR_UNDELEGATE)
+            .transfer_amount(delegate_id, sender_id, amount.into())
+            .into()
+    }
+
     /// Withdraw vote tokens.
-    /// Only vote amount which is not delegated can be withdrawn.
-    pub fn withdraw(&mut self, dao_id: AccountId, amount: U128) -> Promise {
+    /// Only vote amount which is not delegated nor unbonding can be withdrawn.
+    /// Withdraws the token the dao was registered with if `token_id` is not provided.
+    /// If dao has unbonding period, the amount is moved to pending unbonds
+    /// and returned as value. Otherwise it is transferred to the caller.
+    pub fn withdraw(
+        &mut self,
+        dao_id: AccountId,
+        amount: U128,
+        token_id: Option<AccountId>,
+    ) -> PromiseOrValue<U128> {
         let sender_id = env::predecessor_account_id();
         let mut dao = self.get_dao(&dao_id);
-        dao.user_withdraw(&sender_id, amount.0);
+        let token_id = token_id.unwrap_or_else(|| dao.vote_tokens[0].token_id.clone());
+        if dao.unbonding_period > 0 {
+            let storage_before = env::storage_usage();
+            dao.user_unbond(&sender_id, &token_id, amount.0);
+            self.save_dao(&dao_id, &dao);
+            self.internal_update_storage_used(&dao_id, storage_before);
+            return PromiseOrValue::Value(amount);
+        }
+        dao.user_withdraw(&sender_id, &token_id, amount.0);
         self.save_dao(&dao_id, &dao);
-        ext_fungible_token::ext(dao.vote_token_id.clone())
+        ext_fungible_token::ext(token_id.clone())
             .with_static_gas(GAS_FOR_FT_TRANSFER)
             .with_attached_deposit(1)
             .ft_transfer(sender_id.clone(), amount, None)
             .then(
                 ext_self::ext(env::current_account_id())
                     .with_static_gas(GAS_FOR_FT_TRANSFER)
-                    .exchange_callback_post_withdraw(dao_id, sender_id, amount),
+                    .exchange_callback_post_withdraw(dao_id, sender_id, token_id, amount),
             )
+            .into()
+    }
+
+    /// Claims all pending unbonds of `account_id` (defaults to caller) whose unbonding period has ended.
+    /// Undelegated amounts become available again or are delegated to the new delegate if they were moved.
+    /// Withdrawn amounts are transferred to the account.
+    /// Can be called by anyone.
+    /// Fails if there is nothing to claim yet.
+    pub fn claim_unbonded(
+        &mut self,
+        dao_id: AccountId,
+        account_id: Option<AccountId>,
+    ) -> PromiseOrValue<()> {
+        let storage_before = env::storage_usage();
+        let sender_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let mut dao = self.get_dao(&dao_id);
+        let (amounts, changes) = dao.user_claim_unbonded(&sender_id);
+        self.save_dao(&dao_id, &dao);
+        self.internal_update_storage_used(&dao_id, storage_before);
+        let weights = match internal_report_weights(dao.account_id, changes) {
+            PromiseOrValue::Promise(promise) => Some(promise),
+            PromiseOrValue::Value(_) => None,
+        };
+        amounts
+            .into_iter()
+            .map(|(token_id, amount)| {
+                ext_fungible_token::ext(token_id.clone())
+                    .with_static_gas(GAS_FOR_FT_TRANSFER)
+                    .with_attached_deposit(1)
+                    .ft_transfer(sender_id.clone(), amount.into(), None)
+                    .then(
+                        ext_self::ext(env::current_account_id())
+                            .with_static_gas(GAS_FOR_FT_TRANSFER)
+                            .exchange_callback_post_claim(
+                                dao_id.clone(),
+                                sender_id.clone(),
+                                token_id,
+                                amount.into(),
+                            ),
+                    )
+            })
+            .chain(weights)
+            .reduce(|promise, next| promise.and(next))
+            .map(PromiseOrValue::Promise)
+            .unwrap_or(PromiseOrValue::Value(()))
     }
     /// Checks if withdraw was succesful.
     /// Reverts changes if not.
@@ -233,6 +501,7 @@ impl Contract {
         &mut self,
         dao_id: AccountId,
         sender_id: AccountId,
+        token_id: AccountId,
         amount: U128,
     ) {
         require!(
@@ -245,11 +514,78 @@ impl Contract {
             PromiseResult::Failed => {
                 let mut dao = self.get_dao(&dao_id);
                 // This reverts the changes from withdraw function.
-                dao.user_deposit(&sender_id, amount.0);
+                dao.user_deposit(&sender_id, &token_id, amount.0);
                 self.save_dao(&dao_id, &dao);
             }
         };
     }
+    /// Checks if claim transfer was succesful.
+    /// Returns the amount back to pending unbonds if not.
+    #[private]
+    pub fn exchange_callback_post_claim(
+        &mut self,
+        dao_id: AccountId,
+        sender_id: AccountId,
+        token_id: AccountId,
+        amount: U128,
+    ) {
+        require!(env::promise_results_count() == 1, "internal claim callback",);
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => {}
+            PromiseResult::Failed => {
+                let mut dao = self.get_dao(&dao_id);
+                dao.user_claim_revert(&sender_id, token_id, amount.0);
+                self.save_dao(&dao_id, &dao);
+            }
+        };
+    }
+    /// Records failed mass withdraw transfer so it can be claimed by the user.
+    /// Removes unregistering dao once it was the last pending transfer.
+    #[private]
+    pub fn exchange_callback_post_mass_withdraw(
+        &mut self,
+        dao_id: AccountId,
+        account_id: AccountId,
+        token_id: AccountId,
+        amount: U128,
+    ) {
+        require!(
+            env::promise_results_count() == 1,
+            "internal mass withdraw callback",
+        );
+        if let PromiseResult::Failed = env::promise_result(0) {
+            log!(
+                "EVENT: Mass withdraw of {} {} tokens to {} failed.",
+                amount.0,
+                token_id,
+                account_id
+            );
+            internal_add_failed_withdraw(account_id, token_id, amount.0);
+        }
+        if let Some(mut dao) = self.daos.get(&dao_id) {
+            dao.pending_withdraws = dao.pending_withdraws.saturating_sub(1);
+            self.save_dao(&dao_id, &dao);
+            self.internal_try_remove_dao(&dao_id, &dao);
+        }
+    }
+    /// Checks if claim of failed mass withdraw was succesful.
+    /// Records the amount back if not.
+    #[private]
+    pub fn exchange_callback_post_claim_failed_withdraw(
+        &mut self,
+        account_id: AccountId,
+        token_id: AccountId,
+        amount: U128,
+    ) {
+        require!(
+            env::promise_results_count() == 1,
+            "internal claim failed withdraw callback",
+        );
+        if let PromiseResult::Failed = env::promise_result(0) {
+            internal_add_failed_withdraw(account_id, token_id, amount.0);
+        }
+    }
     #[private]
     pub fn return_deposit(&mut self, account_id: AccountId, amount: u128) {
         require!(
@@ -265,27 +601,70 @@ impl Contract {
         };
     }
 
-    /// Total staked amount in dao.
+    /// Total vote weight staked in dao.
     pub fn dao_ft_total_supply(&self, dao_id: AccountId) -> U128 {
         let dao = self.get_dao(&dao_id);
         dao.ft_total_supply()
     }
-    /// Total number of tokens staked by given user in dao.
+    /// Total vote weight staked by given user in dao.
     pub fn dao_ft_balance_of(&self, dao_id: AccountId, account_id: AccountId) -> U128 {
         let dao = self.get_dao(&dao_id);
         dao.ft_balance_of(account_id)
     }
+    /// Withdrawn amount of `token_id` in unbonding of given user in dao.
+    /// Defaults to the token the dao was registered with.
+    pub fn dao_ft_unbonding_of(
+        &self,
+        dao_id: AccountId,
+        account_id: AccountId,
+        token_id: Option<AccountId>,
+    ) -> U128 {
+        let dao = self.get_dao(&dao_id);
+        let token_id = token_id.unwrap_or_else(|| dao.vote_tokens[0].token_id.clone());
+        dao.ft_unbonding_of(account_id, &token_id)
+    }
+    /// Vote weight delegated to given user in dao at `timestamp` in seconds.
+    /// Returns None if `timestamp` precedes the user's recorded history.
+    pub fn dao_vote_weight_at(
+        &self,
+        dao_id: AccountId,
+        account_id: AccountId,
+        timestamp: TimestampSec,
+    ) -> Option<U128> {
+        self.get_dao(&dao_id).vote_weight_at(&account_id, timestamp)
+    }
+    /// Vote tokens accepted by dao.
+    pub fn dao_vote_tokens(&self, dao_id: AccountId) -> Vec<VoteToken> {
+        self.get_dao(&dao_id).vote_tokens
+    }
+    /// Unbonding period of dao in seconds.
+    pub fn dao_unbonding_period(&self, dao_id: AccountId) -> TimestampSec {
+        let dao = self.get_dao(&dao_id);
+        dao.unbonding_period
+    }
     /// Returns user information.
     pub fn dao_get_user(&self, dao_id: AccountId, account_id: AccountId) -> User {
         let dao = self.get_dao(&dao_id);
         dao.get_user(&account_id)
     }
+    /// Amount of `token_id` tokens of failed mass withdraw transfers claimable by `account_id`.
+    pub fn failed_withdraw_of(&self, account_id: AccountId, token_id: AccountId) -> U128 {
+        failed_withdraws()
+            .get(&(account_id, token_id))
+            .unwrap_or_default()
+            .into()
+    }
     pub fn dao_user_list(&self, dao_id: AccountId) -> Vec<(AccountId, User)> {
         let dao = self.get_dao(&dao_id);
         dao.users
             .to_vec()
             .into_iter()
-            .map(|(account_id, versioned_user)| (account_id, versioned_user.into()))
+            .map(|(account_id, versioned_user)| {
+                (
+                    account_id,
+                    versioned_user.into_user(&dao.vote_tokens[0].token_id),
+                )
+            })
             .collect()
     }
 }
@@ -294,11 +673,11 @@ impl Contract {
 impl FungibleTokenReceiver for Contract {
     /// Method called by FT contract which adds `amount` of vote tokens
     /// to `sender_id` account in dao specified in `msg` as deserialized `TransferMsgInfo` object.
-    /// If msg has `delegate_id` key, then all deposited tokens are transfered to it.
+    /// If msg has `delegate_id` key, then vote weight of all deposited tokens is delegated to it.
     /// Fails if:
     /// - malformed/missing `TransferMsgInfo` object in `msg`
-    /// - dao is not registered
-    /// - dao does not have caller's account registered as vote token
+    /// - dao is not registered or is unregistering
+    /// - dao does not accept caller's account as vote token
     /// - sender_id or delegate_id is not registered in dao
     fn ft_on_transfer(
         &mut self,
@@ -309,14 +688,11 @@ impl FungibleTokenReceiver for Contract {
         let dao_transfer: TransferMsgInfo =
             serde_json::from_str(msg.as_str()).expect("invalid msg format");
         let mut dao = self.get_dao(&dao_transfer.dao_id);
-        require!(
-            dao.vote_token_id == env::predecessor_account_id(),
-            "invalid token"
-        );
-        dao.user_deposit(&sender_id, amount.0);
+        dao.assert_active();
+        let weight = dao.user_deposit(&sender_id, &env::predecessor_account_id(), amount.0);
         self.save_dao(&dao_transfer.dao_id, &dao);
         if let Some(delegate_id) = dao_transfer.delegate_id {
-            self.internal_delegate_owned(dao_transfer.dao_id, sender_id, delegate_id, amount.0);
+            self.internal_delegate_owned(dao_transfer.dao_id, sender_id, delegate_id, weight);
         }
         PromiseOrValue::Value(U128(0))
     }
@@ -411,6 +787,27 @@ pub trait ExtSelf {
         &mut self,
         dao_id: AccountId,
         sender_id: AccountId,
+        token_id: AccountId,
+        amount: U128,
+    );
+    fn exchange_callback_post_claim(
+        &mut self,
+        dao_id: AccountId,
+        sender_id: AccountId,
+        token_id: AccountId,
+        amount: U128,
+    );
+    fn exchange_callback_post_mass_withdraw(
+        &mut self,
+        dao_id: AccountId,
+        account_id: AccountId,
+        token_id: AccountId,
+        amount: U128,
+    );
+    fn exchange_callback_post_claim_failed_withdraw(
+        &mut self,
+        account_id: AccountId,
+        token_id: AccountId,
         amount: U128,
     );
     fn return_deposit(&self, account_id: AccountId, amount: u128);
@@ -423,6 +820,18 @@ pub trait ExtFungibleToken {
 }
 
 impl Contract {
+    /// Removes unregistering dao without users and pending withdraw transfers
+    /// and refunds its storage deposit.
+    fn internal_try_remove_dao(&mut self, dao_id: &AccountId, dao: &Dao) {
+        if dao.unregistering && dao.users.is_empty() && dao.pending_withdraws == 0 {
+            self.daos.remove(dao_id);
+            let account_stats = self
+                .dao_storage_balance
+                .remove(dao_id)
+                .expect("internal dao storage balance");
+            Promise::new(dao_id.to_owned()).transfer(account_stats.total_balance());
+        }
+    }
     pub fn get_dao(&self, dao_id: &AccountId) -> Dao {
         self.daos.get(dao_id).expect("dao not found")
     }
@@ -444,24 +853,569 @@ impl Contract {
     pub fn save_account_stats(&mut self, account_id: &AccountId, stats: &AccountStats) {
         self.dao_storage_balance.insert(account_id, stats);
     }
+    /// Updates dao's used storage by the difference since `storage_before`.
+    /// Fails if dao does not have enough storage deposit.
+    fn internal_update_storage_used(&mut self, dao_id: &AccountId, storage_before: StorageUsage) {
+        let storage_after = env::storage_usage();
+        let mut account_stats = self.get_account_stats(dao_id);
+        if storage_after >= storage_before {
+            account_stats.add_storage_used(storage_after - storage_before);
+        } else {
+            account_stats.remove_storage_used(storage_before - storage_after);
+        }
+        self.save_account_stats(dao_id, &account_stats);
+        account_stats.assert_enough_deposit();
+    }
     pub fn internal_delegate_owned(
         &mut self,
         dao_id: AccountId,
         sender_id: AccountId,
         delegate_id: AccountId,
         amount: u128,
-    ) -> Promise {
+    ) -> PromiseOrValue<()> {
         let storage_before = env::storage_usage();
         let mut dao = self.get_dao(&dao_id);
-        dao.delegate_owned(sender_id, delegate_id.clone(), amount);
-        let mut account_stats = self.get_account_stats(&dao_id);
+        let changes = dao.delegate_owned(sender_id, delegate_id, amount);
         self.save_dao(&dao_id, &dao);
-        let storage_after = env::storage_usage();
-        account_stats.add_storage_used(storage_after - storage_before);
-        self.save_account_stats(&dao_id, &account_stats);
-        account_stats.assert_enough_deposit();
-        ext_dao::ext(dao.account_id)
-            .with_static_gas(GAS_FOR_DELEGATE)
-            .delegate_owned(delegate_id, amount.into())
+        self.internal_update_storage_used(&dao_id, storage_before);
+        internal_report_weights(dao.account_id, changes)
+    }
+}
+
+/// Registers this contract in `token_id` and returns the deposit to the caller if it fails.
+fn internal_token_storage_deposit(token_id: AccountId, storage_deposit: Balance) -> Promise {
+    Promise::new(token_id)
+        .function_call(
+            "storage_deposit".to_string(),
+            b"{\"registration_only\":true}".to_vec(),
+            storage_deposit,
+            Gas(10 * 10u64.pow(12)),
+        )
+        .then(
+            ext_self::ext(env::current_account_id())
+                .with_static_gas(Gas(10 * 10u64.pow(12)))
+                .return_deposit(env::predecessor_account_id(), storage_deposit),
+        )
+}
+
+/// Reports changed delegation weights to the dao.
+fn internal_report_weights(dao_id: AccountId, changes: Vec<WeightChange>) -> PromiseOrValue<()> {
+    changes
+        .into_iter()
+        .map(|(delegate_id, prev_weight, new_weight)| {
+            if new_weight > prev_weight {
+                ext_dao::ext(dao_id.clone())
+                    .with_static_gas(GAS_FOR_DELEGATE)
+                    .delegate_owned(delegate_id, (new_weight - prev_weight).into())
+            } else {
+                ext_dao::ext(dao_id.clone())
+                    .with_static_gas(GAS_FOR_UNDELEGATE)
+                    .undelegate(delegate_id, (prev_weight - new_weight).into())
+            }
+        })
+        .reduce(|promise, next| promise.and(next))
+        .map(PromiseOrValue::Promise)
+        .unwrap_or(PromiseOrValue::Value(()))
+}
+
+/// Amounts of failed mass withdraw transfers keyed by (account, token).
+fn failed_withdraws() -> LookupMap<(AccountId, AccountId), Balance> {
+    LookupMap::new(StorageKeys::FailedWithdraws)
+}
+
+fn internal_add_failed_withdraw(account_id: AccountId, token_id: AccountId, amount: Balance) {
+    let mut failed_withdraws = failed_withdraws();
+    let key = (account_id, token_id);
+    let prev_amount = failed_withdraws.get(&key).unwrap_or_default();
+    failed_withdraws.insert(&key, &(prev_amount + amount));
+}
+
+#[cfg(test)]
+mod tests {
+    use near_contract_standards::storage_management::StorageManagement;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult, RuntimeFeesConfig, VMConfig, ONE_NEAR};
+
+    use super::*;
+    use crate::consts::MAX_VOTE_WEIGHT_CHECKPOINTS;
+    use crate::user::UserV1;
+
+    const UNBONDING_PERIOD: TimestampSec = 100;
+
+    fn staking_id() -> AccountId {
+        "staking.near".parse().unwrap()
+    }
+
+    fn dao_id() -> AccountId {
+        accounts(0)
+    }
+
+    fn token_id() -> AccountId {
+        accounts(1)
+    }
+
+    fn context(predecessor_id: AccountId, timestamp: TimestampSec) -> VMContextBuilder {
+        let mut context = VMContextBuilder::new();
+        context
+            .current_account_id(staking_id())
+            .predecessor_account_id(predecessor_id)
+            .block_timestamp(timestamp * 10u64.pow(9));
+        context
+    }
+
+    /// Sets up contract with registered dao and `users` registered in it.
+    fn setup(unbonding_period: Option<TimestampSec>, users: &[AccountId]) -> Contract {
+        testing_env!(context(dao_id(), 0).attached_deposit(5 * ONE_NEAR).build());
+        let mut contract = Contract::new();
+        contract.storage_deposit(None, None);
+        testing_env!(context(dao_id(), 0)
+            .attached_deposit(FT_STORAGE_DEPOSIT)
+            .build());
+        contract.register_new_dao(dao_id(), token_id(), unbonding_period);
+        for user in users {
+            testing_env!(context(user.clone(), 0)
+                .attached_deposit(MIN_REGISTER_DEPOSIT)
+                .build());
+            contract.register_in_dao(dao_id());
+        }
+        contract
+    }
+
+    fn deposit(contract: &mut Contract, token_id: AccountId, sender_id: AccountId, amount: u128) {
+        testing_env!(context(token_id, 0).build());
+        let msg = format!("{{\"dao_id\":\"{}\"}}", dao_id());
+        contract.ft_on_transfer(sender_id, amount.into(), msg);
+    }
+
+    /// Sets context of callback with the `result` of the previous promise.
+    fn callback_context(timestamp: TimestampSec, result: PromiseResult) {
+        testing_env!(
+            context(staking_id(), timestamp).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result]
+        );
+    }
+
+    #[test]
+    fn claim_unbonded_after_unbonding_period() {
+        let user = accounts(2);
+        let mut contract = setup(Some(UNBONDING_PERIOD), &[user.clone(), accounts(3)]);
+        deposit(&mut contract, token_id(), user.clone(), 100);
+
+        testing_env!(context(user.clone(), 0).build());
+        contract.delegate_owned(dao_id(), accounts(3), 60.into());
+        contract.undelegate(dao_id(), accounts(3), 60.into());
+        assert!(matches!(
+            contract.withdraw(dao_id(), 40.into(), None),
+            PromiseOrValue::Value(U128(40))
+        ));
+        assert_eq!(contract.dao_ft_balance_of(dao_id(), user.clone()), U128(60));
+        assert_eq!(
+            contract.dao_ft_unbonding_of(dao_id(), user.clone(), None),
+            U128(40)
+        );
+        assert_eq!(
+            contract
+                .dao_get_user(dao_id(), user.clone())
+                .locked_amount(),
+            60
+        );
+
+        testing_env!(context(user.clone(), UNBONDING_PERIOD).build());
+        assert!(matches!(
+            contract.claim_unbonded(dao_id(), None),
+            PromiseOrValue::Promise(_)
+        ));
+        let user_data = contract.dao_get_user(dao_id(), user.clone());
+        assert!(user_data.pending_unbonds.is_empty());
+        assert_eq!(
+            contract.dao_ft_unbonding_of(dao_id(), user.clone(), None),
+            U128(0)
+        );
+        // Undelegated amount can be delegated again.
+        contract.delegate_owned(dao_id(), accounts(3), 60.into());
+
+        // Failed transfer returns the amount back as claimable.
+        callback_context(UNBONDING_PERIOD, PromiseResult::Failed);
+        contract.exchange_callback_post_claim(dao_id(), user.clone(), token_id(), 40.into());
+        assert_eq!(
+            contract.dao_ft_unbonding_of(dao_id(), user.clone(), None),
+            U128(40)
+        );
+        testing_env!(context(user, UNBONDING_PERIOD).build());
+        assert!(matches!(
+            contract.claim_unbonded(dao_id(), None),
+            PromiseOrValue::Promise(_)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "nothing to claim")]
+    fn claim_unbonded_before_release() {
+        let mut contract = setup(Some(UNBONDING_PERIOD), &[accounts(2)]);
+        let user = accounts(2);
+        deposit(&mut contract, token_id(), user.clone(), 100);
+        testing_env!(context(user.clone(), 0).build());
+        contract.withdraw(dao_id(), 40.into(), None);
+        testing_env!(context(user, UNBONDING_PERIOD - 1).build());
+        contract.claim_unbonded(dao_id(), None);
+    }
+
+    fn vote_weight(contract: &Contract, account_id: AccountId) -> u128 {
+        contract
+            .dao_get_user(dao_id(), account_id)
+            .delegated_vote_weight
+    }
+
+    #[test]
+    fn redelegate_waits_for_unbonding_period() {
+        let (user, from, to) = (accounts(2), accounts(3), accounts(4));
+        let mut contract = setup(
+            Some(UNBONDING_PERIOD),
+            &[user.clone(), from.clone(), to.clone()],
+        );
+        deposit(&mut contract, token_id(), user.clone(), 100);
+        testing_env!(context(user.clone(), 0).build());
+        contract.delegate_owned(dao_id(), from.clone(), 100.into());
+        contract.redelegate(dao_id(), from.clone(), to.clone(), 40.into());
+        assert_eq!(vote_weight(&contract, from.clone()), 60);
+        assert_eq!(vote_weight(&contract, to.clone()), 0);
+        // Amount is locked until claimed.
+        let user_data = contract.dao_get_user(dao_id(), user.clone());
+        assert_eq!(user_data.locked_amount(), 40);
+        assert_eq!(user_data.pending_unbonds[0].delegate_id, Some(to.clone()));
+
+        // Anyone can claim.
+        testing_env!(context(to.clone(), UNBONDING_PERIOD).build());
+        contract.claim_unbonded(dao_id(), Some(user.clone()));
+        assert_eq!(vote_weight(&contract, from), 60);
+        assert_eq!(vote_weight(&contract, to.clone()), 40);
+        let user_data = contract.dao_get_user(dao_id(), user);
+        assert_eq!(user_data.locked_amount(), 0);
+        assert_eq!(user_data.delegated_amount_to(&to), 40);
+    }
+
+    #[test]
+    fn undelegate_transitive_waits_for_unbonding_period() {
+        let (user, sender, delegate) = (accounts(2), accounts(3), accounts(4));
+        let mut contract = setup(
+            Some(UNBONDING_PERIOD),
+            &[user.clone(), sender.clone(), delegate.clone()],
+        );
+        deposit(&mut contract, token_id(), user.clone(), 100);
+        testing_env!(context(user.clone(), 0).build());
+        contract.delegate_owned(dao_id(), sender.clone(), 100.into());
+        testing_env!(context(sender.clone(), 0).build());
+        contract.delegate(dao_id(), delegate.clone());
+        assert_eq!(vote_weight(&contract, sender.clone()), 0);
+        assert_eq!(vote_weight(&contract, delegate.clone()), 100);
+
+        contract.undelegate_transitive(dao_id(), delegate.clone());
+        assert_eq!(vote_weight(&contract, sender.clone()), 0);
+        assert_eq!(vote_weight(&contract, delegate.clone()), 0);
+
+        testing_env!(context(sender.clone(), UNBONDING_PERIOD).build());
+        contract.claim_unbonded(dao_id(), Some(user.clone()));
+        assert_eq!(vote_weight(&contract, sender.clone()), 100);
+        assert_eq!(vote_weight(&contract, delegate), 0);
+        assert_eq!(
+            contract
+                .dao_get_user(dao_id(), user)
+                .delegated_amount_to(&sender),
+            100
+        );
+    }
+
+    #[test]
+    fn redelegate_reports_boosted_weight() {
+        let (user, from, to) = (accounts(2), accounts(3), accounts(4));
+        let mut contract = setup(None, &[user.clone(), from.clone(), to.clone()]);
+        deposit(&mut contract, token_id(), user.clone(), 100);
+        testing_env!(context(user.clone(), 0).build());
+        contract.lock(dao_id(), 100.into(), MAX_VOTE_LOCK_DURATION);
+        contract.delegate_owned(dao_id(), from.clone(), 100.into());
+        let weight = vote_weight(&contract, from.clone());
+        assert!(weight > 100);
+
+        testing_env!(context(user, 0).build());
+        contract.redelegate(dao_id(), from.clone(), to.clone(), 50.into());
+        assert_eq!(vote_weight(&contract, from), weight - weight / 2);
+        assert_eq!(vote_weight(&contract, to), weight / 2);
+        let receipts = get_created_receipts();
+        let args = match &receipts.last().unwrap().actions[0] {
+            VmAction::FunctionCall {
+                function_name,
+                args,
+                ..
+            } if function_name == "transfer_amount" => args,
+            action => panic!("unexpected action: {:?}", action),
+        };
+        let args: near_sdk::serde_json::Value = near_sdk::serde_json::from_slice(args).unwrap();
+        assert_eq!(args["amount"], (weight / 2).to_string());
+    }
+
+    /// Adds `token_id` with weight `numerator / denominator` to the dao.
+    fn add_vote_token(
+        contract: &mut Contract,
+        token_id: AccountId,
+        numerator: u128,
+        denominator: u128,
+    ) {
+        testing_env!(context(dao_id(), 0)
+            .attached_deposit(FT_STORAGE_DEPOSIT)
+            .build());
+        contract.add_vote_token(dao_id(), token_id, numerator.into(), denominator.into());
+    }
+
+    #[test]
+    fn weighted_deposit_and_withdraw_of_several_tokens() {
+        let (user, delegate, token_id_2) = (accounts(2), accounts(3), accounts(5));
+        let mut contract = setup(None, &[user.clone(), delegate.clone()]);
+        add_vote_token(&mut contract, token_id_2.clone(), 3, 2);
+        deposit(&mut contract, token_id(), user.clone(), 100);
+        deposit(&mut contract, token_id_2.clone(), user.clone(), 40);
+        assert_eq!(
+            contract.dao_ft_balance_of(dao_id(), user.clone()),
+            U128(160)
+        );
+        assert_eq!(contract.dao_ft_total_supply(dao_id()), U128(160));
+
+        testing_env!(context(user.clone(), 0).build());
+        contract.delegate_owned(dao_id(), delegate.clone(), 150.into());
+        assert_eq!(
+            contract
+                .dao_get_user(dao_id(), delegate)
+                .delegated_vote_amount,
+            150
+        );
+
+        // 6 tokens of weight 3/2 free 9 of the remaining 10 vote weight.
+        contract.withdraw(dao_id(), 6.into(), Some(token_id_2.clone()));
+        assert_eq!(
+            contract.dao_ft_balance_of(dao_id(), user.clone()),
+            U128(151)
+        );
+        assert_eq!(contract.dao_ft_total_supply(dao_id()), U128(151));
+        assert_eq!(
+            contract.dao_get_user(dao_id(), user).token_amounts,
+            vec![(token_id(), 100), (token_id_2, 34)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "not enough free vote amount")]
+    fn withdraw_weighted_token_over_free_amount() {
+        let mut contract = setup(None, &[accounts(2), accounts(3)]);
+        let (user, token_id_2) = (accounts(2), accounts(5));
+        add_vote_token(&mut contract, token_id_2.clone(), 3, 2);
+        deposit(&mut contract, token_id(), user.clone(), 100);
+        deposit(&mut contract, token_id_2.clone(), user.clone(), 40);
+        testing_env!(context(user, 0).build());
+        contract.delegate_owned(dao_id(), accounts(3), 150.into());
+        // 10 tokens have vote weight 15.
+        contract.withdraw(dao_id(), 10.into(), Some(token_id_2));
+    }
+
+    #[test]
+    fn lock_boost_decays_until_poke() {
+        let (user, delegate) = (accounts(2), accounts(3));
+        let mut contract = setup(None, &[user.clone(), delegate.clone()]);
+        deposit(&mut contract, token_id(), user.clone(), 100);
+        testing_env!(context(user.clone(), 0).build());
+        contract.lock(dao_id(), 100.into(), MAX_VOTE_LOCK_DURATION);
+        contract.delegate_owned(dao_id(), delegate.clone(), 100.into());
+        assert_eq!(vote_weight(&contract, delegate.clone()), 400);
+
+        // Weight is not decayed until poked.
+        let half = MAX_VOTE_LOCK_DURATION / 2;
+        testing_env!(context(delegate.clone(), half).build());
+        assert_eq!(vote_weight(&contract, delegate.clone()), 400);
+        contract.poke(dao_id(), user.clone());
+        assert_eq!(vote_weight(&contract, delegate.clone()), 250);
+        assert_eq!(
+            contract.dao_vote_weight_at(dao_id(), delegate.clone(), half),
+            Some(U128(250))
+        );
+
+        testing_env!(context(delegate.clone(), MAX_VOTE_LOCK_DURATION).build());
+        contract.poke(dao_id(), user.clone());
+        assert_eq!(vote_weight(&contract, delegate.clone()), 100);
+        assert_eq!(
+            contract
+                .dao_get_user(dao_id(), user)
+                .vote_lock_amount(MAX_VOTE_LOCK_DURATION),
+            0
+        );
+        assert_eq!(
+            contract.dao_vote_weight_at(dao_id(), delegate, half),
+            Some(U128(250))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "vote tokens are locked")]
+    fn withdraw_locked_tokens() {
+        let mut contract = setup(None, &[accounts(2)]);
+        let user = accounts(2);
+        deposit(&mut contract, token_id(), user.clone(), 100);
+        testing_env!(context(user.clone(), 0).build());
+        contract.lock(dao_id(), 60.into(), MIN_VOTE_LOCK_DURATION);
+        contract.withdraw(dao_id(), 40.into(), None);
+        testing_env!(context(user, MIN_VOTE_LOCK_DURATION - 1).build());
+        contract.withdraw(dao_id(), 1.into(), None);
+    }
+
+    #[test]
+    fn migrate_v1_dao_and_users() {
+        let (user, delegate) = (accounts(2), accounts(3));
+        testing_env!(context(dao_id(), 0).build());
+        let mut contract = Contract::new();
+        contract.register_account(&dao_id(), 5 * ONE_NEAR);
+        let mut users = UnorderedMap::new(into_storage_key_wrapper_u16(DAO_KEY_PREFIX, 1));
+        users.insert(
+            &user,
+            &VersionedUser::V1(UserV1 {
+                vote_amount: 100,
+                delegated_amounts: vec![(delegate.clone(), 60)],
+                delegated_vote_amount: 0,
+                delegators: vec![],
+            }),
+        );
+        users.insert(
+            &delegate,
+            &VersionedUser::V1(UserV1 {
+                vote_amount: 0,
+                delegated_amounts: vec![],
+                delegated_vote_amount: 60,
+                delegators: vec![user.clone()],
+            }),
+        );
+        let mut old_daos: LookupMap<AccountId, DaoV1> = LookupMap::new(StorageKeys::Daos);
+        old_daos.insert(
+            &dao_id(),
+            &DaoV1 {
+                account_id: dao_id(),
+                vote_token_id: token_id(),
+                users,
+                total_amount: 100,
+            },
+        );
+        contract.last_dao_key_suffix = 1;
+        env::state_write(&contract);
+
+        testing_env!(context(staking_id(), 0).build());
+        let contract = Contract::migrate(vec![dao_id(), dao_id()]);
+        assert_eq!(
+            contract.dao_vote_tokens(dao_id()),
+            vec![VoteToken::new(token_id(), 1, 1)]
+        );
+        assert_eq!(contract.dao_unbonding_period(dao_id()), 0);
+        assert_eq!(
+            contract.dao_ft_balance_of(dao_id(), user.clone()),
+            U128(100)
+        );
+        let user_data = contract.dao_get_user(dao_id(), user);
+        assert_eq!(user_data.token_amounts, vec![(token_id(), 100)]);
+        assert_eq!(user_data.delegated_weights, vec![(delegate.clone(), 60)]);
+        assert!(user_data.pending_unbonds.is_empty());
+        assert_eq!(
+            contract
+                .dao_get_user(dao_id(), delegate)
+                .delegated_vote_amount,
+            60
+        );
+    }
+
+    #[test]
+    fn mass_withdraw_failed_transfer_is_claimable() {
+        let mut contract = setup(None, &[accounts(2)]);
+        let user = accounts(2);
+        deposit(&mut contract, token_id(), user.clone(), 100);
+
+        testing_env!(context(dao_id(), 0).build());
+        assert_eq!(contract.unregister_dao(dao_id(), None), 0);
+        // Dao waits for the transfer callback.
+        assert_eq!(contract.get_dao(&dao_id()).pending_withdraws, 1);
+
+        callback_context(0, PromiseResult::Failed);
+        contract.exchange_callback_post_mass_withdraw(
+            dao_id(),
+            user.clone(),
+            token_id(),
+            100.into(),
+        );
+        assert!(contract.daos.get(&dao_id()).is_none());
+        assert!(contract.dao_storage_balance.get(&dao_id()).is_none());
+        assert_eq!(
+            contract.failed_withdraw_of(user.clone(), token_id()),
+            U128(100)
+        );
+
+        testing_env!(context(user.clone(), 0).build());
+        contract.claim_failed_withdraw(token_id());
+        assert_eq!(
+            contract.failed_withdraw_of(user.clone(), token_id()),
+            U128(0)
+        );
+        callback_context(0, PromiseResult::Failed);
+        contract.exchange_callback_post_claim_failed_withdraw(user.clone(), token_id(), 100.into());
+        assert_eq!(contract.failed_withdraw_of(user, token_id()), U128(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "nothing to claim")]
+    fn claim_failed_withdraw_without_failure() {
+        let mut contract = setup(None, &[accounts(2)]);
+        let user = accounts(2);
+        testing_env!(context(user, 0).build());
+        contract.claim_failed_withdraw(token_id());
+    }
+
+    #[test]
+    fn vote_weight_checkpoints() {
+        let (user, delegate) = (accounts(2), accounts(3));
+        let mut contract = setup(None, &[user.clone(), delegate.clone()]);
+        deposit(&mut contract, token_id(), user.clone(), 100);
+        testing_env!(context(user.clone(), 10).build());
+        contract.delegate_owned(dao_id(), delegate.clone(), 60.into());
+        testing_env!(context(user.clone(), 20).build());
+        contract.delegate_owned(dao_id(), delegate.clone(), 40.into());
+        testing_env!(context(user, 30).build());
+        contract.undelegate(dao_id(), delegate.clone(), 100.into());
+
+        let weight_at =
+            |timestamp| contract.dao_vote_weight_at(dao_id(), delegate.clone(), timestamp);
+        assert_eq!(weight_at(5), Some(U128(0)));
+        assert_eq!(weight_at(10), Some(U128(60)));
+        assert_eq!(weight_at(19), Some(U128(60)));
+        assert_eq!(weight_at(25), Some(U128(100)));
+        assert_eq!(weight_at(30), Some(U128(0)));
+    }
+
+    #[test]
+    fn vote_weight_checkpoints_pruned() {
+        let (user, delegate) = (accounts(2), accounts(3));
+        let mut contract = setup(None, &[user.clone(), delegate.clone()]);
+        let updates = MAX_VOTE_WEIGHT_CHECKPOINTS as u64 + 5;
+        deposit(&mut contract, token_id(), user.clone(), updates as u128);
+        for t in 1..=updates {
+            testing_env!(context(user.clone(), t).build());
+            contract.delegate_owned(dao_id(), delegate.clone(), 1.into());
+        }
+        let checkpoints = contract
+            .dao_get_user(dao_id(), delegate.clone())
+            .vote_weight_checkpoints;
+        assert_eq!(checkpoints.len(), MAX_VOTE_WEIGHT_CHECKPOINTS);
+        assert_eq!(
+            contract.dao_vote_weight_at(dao_id(), delegate.clone(), 5),
+            None
+        );
+        assert_eq!(
+            contract.dao_vote_weight_at(dao_id(), delegate, updates),
+            Some(U128(updates as u128))
+        );
     }
 }
@@ -2,11 +2,17 @@ use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{require, AccountId, Balance};
 
+use library::{functions::utils::mul_div_u128, TimestampSec};
+
+use crate::consts::{MAX_VOTE_LOCK_BOOST, MAX_VOTE_LOCK_DURATION, MAX_VOTE_WEIGHT_CHECKPOINTS};
+use crate::dao::VoteToken;
+
 /// User data.
 /// Recording deposited voting tokens, storage used and delegations and received delegations for voting.
 /// Once delegated - the tokens are used in the votes. It records for each delegate when was the last vote.
 /// It's possible to transfer all received delegations to another user.
-/// Once transfered - cannot be taken back. At least for now.
+/// Transfered delegations can be taken back by `undelegate_transitive`.
+/// If the DAO has unbonding period, moved delegations reach the new delegate only after it ends.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug)]
 #[serde(crate = "near_sdk::serde")]
 pub struct User {
@@ -20,17 +26,102 @@ pub struct User {
     pub delegated_vote_amount: u128,
     /// List of users whom delegated their tokens to this user.
     pub delegators: Vec<AccountId>,
+    /// Total vote weight delegated to this user as reported to the DAO.
+    pub delegated_vote_weight: u128,
+    /// Checkpoints of `delegated_vote_weight` as (timestamp, weight), oldest first.
+    /// Only `MAX_VOTE_WEIGHT_CHECKPOINTS` latest ones are kept.
+    pub vote_weight_checkpoints: Vec<(TimestampSec, u128)>,
+    /// Staked amount of each vote token.
+    /// `vote_amount` is their combined vote weight.
+    pub token_amounts: Vec<(AccountId, u128)>,
+    /// Undelegated and withdrawn amounts waiting for the end of the DAO's unbonding period.
+    pub pending_unbonds: Vec<PendingUnbond>,
+    /// Delegations forwarded by this user to other delegates by transitive delegation.
+    /// Recorded per original delegator so they can be restored back.
+    pub forwarded_delegations: Vec<(AccountId, Vec<(AccountId, u128)>)>,
+    /// Owned tokens locked for boosted vote weight.
+    pub vote_lock: Option<VoteLock>,
+    /// Vote weights reported to the DAO for each delegation, including the vote lock boost.
+    pub delegated_weights: Vec<(AccountId, u128)>,
+}
+
+/// Changed delegation weight as (delegate, previous weight, new weight).
+pub type WeightChange = (AccountId, u128, u128);
+
+/// Claimed pending unbonds as (token amounts to transfer, amounts to delegate to each delegate).
+pub type ClaimedUnbonds = (Vec<(AccountId, Balance)>, Vec<(AccountId, Balance)>);
+
+/// Vote-escrow lock of owned tokens.
+/// Locked tokens cannot be withdrawn and give extra vote weight decaying linearly to the unlock.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteLock {
+    pub amount: u128,
+    /// Timestamp in seconds when the tokens are unlocked.
+    pub unlock_at: TimestampSec,
+}
+
+/// Amount of vote tokens locked until the end of the unbonding period.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingUnbond {
+    pub amount: u128,
+    /// Timestamp in seconds since which the amount can be claimed.
+    pub release_at: TimestampSec,
+    /// Token of withdrawn amount which is transferred back to the user on claim.
+    /// None for undelegated vote amount which only becomes available for delegation or withdraw again.
+    pub token_id: Option<AccountId>,
+    /// Delegate the undelegated amount is delegated to on claim.
+    /// Set when delegation is moved to another delegate.
+    pub delegate_id: Option<AccountId>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum VersionedUser {
-    V1(User),
+    V1(UserV1),
+    V2(User),
+}
+
+impl VersionedUser {
+    /// Converts user to the latest version.
+    /// `token_id` is the token the dao was registered with, V1 users staked only this one.
+    pub fn into_user(self, token_id: &AccountId) -> User {
+        match self {
+            VersionedUser::V1(u) => u.into_user(token_id),
+            VersionedUser::V2(u) => u,
+        }
+    }
 }
 
-impl From<VersionedUser> for User {
-    fn from(v: VersionedUser) -> Self {
-        match v {
-            VersionedUser::V1(u) => u,
+/// User data before multiple vote tokens, unbonding and vote locks were supported.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct UserV1 {
+    pub vote_amount: u128,
+    pub delegated_amounts: Vec<(AccountId, u128)>,
+    pub delegated_vote_amount: u128,
+    pub delegators: Vec<AccountId>,
+}
+
+impl UserV1 {
+    /// Vote weight of V1 user equals its amounts as the only token has weight 1.
+    pub fn into_user(self, token_id: &AccountId) -> User {
+        let token_amounts = if self.vote_amount > 0 {
+            vec![(token_id.to_owned(), self.vote_amount)]
+        } else {
+            vec![]
+        };
+        User {
+            vote_amount: self.vote_amount,
+            delegated_weights: self.delegated_amounts.clone(),
+            delegated_amounts: self.delegated_amounts,
+            delegated_vote_amount: self.delegated_vote_amount,
+            delegators: self.delegators,
+            delegated_vote_weight: self.delegated_vote_amount,
+            vote_weight_checkpoints: vec![],
+            token_amounts,
+            pending_unbonds: vec![],
+            forwarded_delegations: vec![],
+            vote_lock: None,
         }
     }
 }
@@ -42,6 +133,13 @@ impl User {
             delegated_amounts: vec![],
             delegated_vote_amount: 0,
             delegators: vec![],
+            delegated_vote_weight: 0,
+            vote_weight_checkpoints: vec![],
+            token_amounts: vec![],
+            pending_unbonds: vec![],
+            forwarded_delegations: vec![],
+            vote_lock: None,
+            delegated_weights: vec![],
         }
     }
 
@@ -51,15 +149,100 @@ impl User {
             .fold(0, |total, (_, amount)| total + amount)
     }
 
+    /// Amount delegated to `delegate_id`.
+    pub(crate) fn delegated_amount_to(&self, delegate_id: &AccountId) -> Balance {
+        self.delegated_amounts
+            .iter()
+            .find(|(account_id, _)| account_id == delegate_id)
+            .map(|(_, amount)| *amount)
+            .unwrap_or_default()
+    }
+
+    /// Undelegated amount which cannot be used until its unbonding period ends.
+    pub(crate) fn locked_amount(&self) -> Balance {
+        self.pending_unbonds
+            .iter()
+            .filter(|u| u.token_id.is_none())
+            .fold(0, |total, u| total + u.amount)
+    }
+
+    /// Amount locked by vote lock at `current_timestamp`.
+    pub(crate) fn vote_lock_amount(&self, current_timestamp: TimestampSec) -> Balance {
+        match &self.vote_lock {
+            Some(lock) if lock.unlock_at > current_timestamp => lock.amount,
+            _ => 0,
+        }
+    }
+
+    /// Extra vote weight from vote lock at `current_timestamp`.
+    /// Decays linearly from `MAX_VOTE_LOCK_BOOST` times the locked amount at the maximal duration to zero at unlock.
+    pub(crate) fn vote_lock_boost(&self, current_timestamp: TimestampSec) -> Balance {
+        match &self.vote_lock {
+            Some(lock) if lock.unlock_at > current_timestamp => mul_div_u128(
+                lock.amount,
+                MAX_VOTE_LOCK_BOOST * (lock.unlock_at - current_timestamp) as u128,
+                MAX_VOTE_LOCK_DURATION as u128,
+            )
+            .expect("internal vote lock boost"),
+            _ => 0,
+        }
+    }
+
+    /// Vote weight reported to the DAO for delegation to `delegate_id`.
+    pub(crate) fn delegation_weight(&self, delegate_id: &AccountId) -> Balance {
+        self.delegated_weights
+            .iter()
+            .find(|(account_id, _)| account_id == delegate_id)
+            .map(|(_, weight)| *weight)
+            .unwrap_or_default()
+    }
+
+    /// Withdrawn amount of `token_id` waiting in unbonding.
+    pub(crate) fn pending_unbond_amount(&self, token_id: &AccountId) -> Balance {
+        self.pending_unbonds
+            .iter()
+            .filter(|u| u.token_id.as_ref() == Some(token_id))
+            .fold(0, |total, u| total + u.amount)
+    }
+
+    /// Staked amount of `token_id`.
+    pub(crate) fn token_amount(&self, token_id: &AccountId) -> Balance {
+        self.token_amounts
+            .iter()
+            .find(|(account_id, _)| account_id == token_id)
+            .map(|(_, amount)| *amount)
+            .unwrap_or_default()
+    }
+
+    fn set_token_amount(&mut self, token_id: &AccountId, amount: Balance) {
+        let pos = self
+            .token_amounts
+            .iter()
+            .position(|(account_id, _)| account_id == token_id);
+        match pos {
+            Some(pos) if amount == 0 => {
+                self.token_amounts.swap_remove(pos);
+            }
+            Some(pos) => self.token_amounts[pos].1 = amount,
+            None if amount > 0 => self.token_amounts.push((token_id.clone(), amount)),
+            None => {}
+        }
+    }
+
     /// Record delegation owned tokens from this account to another account.
     /// Fail if not enough available balance to delegate.
     /// Return true if new delegate was added.
     pub fn delegate_owned(&mut self, delegate_id: AccountId, amount: Balance) -> bool {
         require!(
-            self.delegated_amount() + amount <= self.vote_amount,
+            self.delegated_amount() + self.locked_amount() + amount <= self.vote_amount,
             "not enough vote tokens"
         );
+        self.add_delegation(delegate_id, amount)
+    }
 
+    /// Record delegation to `delegate_id` without checking available balance.
+    /// Return true if new delegate was added.
+    pub fn add_delegation(&mut self, delegate_id: AccountId, amount: Balance) -> bool {
         if let Some(delegate_pos) = self
             .delegated_amounts
             .iter()
@@ -73,6 +256,129 @@ impl User {
         }
     }
 
+    /// Move `weight` reported for delegation to `prev_delegate_id` to `new_delegate_id`.
+    pub fn move_delegation_weight(
+        &mut self,
+        prev_delegate_id: &AccountId,
+        new_delegate_id: &AccountId,
+        weight: Balance,
+    ) {
+        if weight == 0 {
+            return;
+        }
+        let prev_pos = self
+            .delegated_weights
+            .iter()
+            .position(|(e, _)| e == prev_delegate_id)
+            .expect("internal delegation weight not found");
+        require!(
+            self.delegated_weights[prev_pos].1 >= weight,
+            "internal delegation weight"
+        );
+        self.delegated_weights[prev_pos].1 -= weight;
+        if self.delegated_weights[prev_pos].1 == 0 {
+            self.delegated_weights.swap_remove(prev_pos);
+        }
+        if let Some(e) = self
+            .delegated_weights
+            .iter_mut()
+            .find(|(e, _)| e == new_delegate_id)
+        {
+            e.1 += weight;
+        } else {
+            self.delegated_weights
+                .push((new_delegate_id.clone(), weight));
+        }
+    }
+
+    /// Recalculate weights of all delegations at `current_timestamp`.
+    /// Boost is split among delegations proportionally to the delegated amounts.
+    /// Returns changed weights.
+    pub fn sync_weights(&mut self, current_timestamp: TimestampSec) -> Vec<WeightChange> {
+        let boost = self.vote_lock_boost(current_timestamp);
+        let mut changes = vec![];
+        let mut weights = Vec::with_capacity(self.delegated_amounts.len());
+        for (delegate_id, amount) in self.delegated_amounts.iter() {
+            let prev_weight = self.delegation_weight(delegate_id);
+            let new_weight = if boost == 0 {
+                *amount
+            } else {
+                amount
+                    + mul_div_u128(boost, *amount, self.vote_amount)
+                        .expect("internal delegation weight")
+            };
+            if prev_weight != new_weight {
+                changes.push((delegate_id.clone(), prev_weight, new_weight));
+            }
+            weights.push((delegate_id.clone(), new_weight));
+        }
+        for (delegate_id, prev_weight) in self.delegated_weights.iter() {
+            if !weights.iter().any(|(e, _)| e == delegate_id) {
+                changes.push((delegate_id.clone(), *prev_weight, 0));
+            }
+        }
+        self.delegated_weights = weights;
+        changes
+    }
+
+    /// Lock `amount` more owned tokens and set unlock to `unlock_at`.
+    /// Fails if the unlock would be sooner than the current one or there are not enough vote tokens.
+    pub fn lock(
+        &mut self,
+        amount: Balance,
+        unlock_at: TimestampSec,
+        current_timestamp: TimestampSec,
+    ) {
+        let (locked_amount, prev_unlock_at) = match &self.vote_lock {
+            Some(lock) if lock.unlock_at > current_timestamp => (lock.amount, lock.unlock_at),
+            _ => (0, 0),
+        };
+        require!(unlock_at >= prev_unlock_at, "cannot shorten vote lock");
+        require!(
+            locked_amount + amount <= self.vote_amount,
+            "not enough vote tokens"
+        );
+        self.vote_lock = Some(VoteLock {
+            amount: locked_amount + amount,
+            unlock_at,
+        });
+    }
+
+    /// Replace `prev_weight` part of delegated vote weight with `new_weight`
+    /// and record checkpoint at `current_timestamp`.
+    pub fn update_vote_weight(
+        &mut self,
+        prev_weight: Balance,
+        new_weight: Balance,
+        current_timestamp: TimestampSec,
+    ) {
+        self.delegated_vote_weight = (self.delegated_vote_weight + new_weight)
+            .checked_sub(prev_weight)
+            .expect("internal vote weight");
+        match self.vote_weight_checkpoints.last_mut() {
+            Some(last) if last.0 == current_timestamp => last.1 = self.delegated_vote_weight,
+            _ => self
+                .vote_weight_checkpoints
+                .push((current_timestamp, self.delegated_vote_weight)),
+        }
+        if self.vote_weight_checkpoints.len() > MAX_VOTE_WEIGHT_CHECKPOINTS {
+            self.vote_weight_checkpoints.remove(0);
+        }
+    }
+
+    /// Delegated vote weight at `timestamp`.
+    /// Returns None if `timestamp` precedes the oldest checkpoint.
+    pub fn vote_weight_at(&self, timestamp: TimestampSec) -> Option<Balance> {
+        let pos = self
+            .vote_weight_checkpoints
+            .partition_point(|(t, _)| *t <= timestamp);
+        if pos == 0 {
+            None
+        } else {
+            Some(self.vote_weight_checkpoints[pos - 1].1)
+        }
+    }
+
     /// Remove all delegators and their delegations and returns them.
     pub fn forward_delegated(&mut self) -> (u128, Vec<AccountId>) {
         require!(self.delegated_vote_amount > 0, "no delegated tokens");
@@ -83,6 +389,36 @@ impl User {
         (amount, delegators)
     }
 
+    /// Record delegations forwarded to `delegate_id`.
+    pub fn add_forwarded(&mut self, delegate_id: AccountId, delegations: Vec<(AccountId, u128)>) {
+        if let Some((_, forwarded)) = self
+            .forwarded_delegations
+            .iter_mut()
+            .find(|(account_id, _)| *account_id == delegate_id)
+        {
+            for (delegator_id, amount) in delegations {
+                if let Some(e) = forwarded.iter_mut().find(|(e, _)| *e == delegator_id) {
+                    e.1 += amount;
+                } else {
+                    forwarded.push((delegator_id, amount));
+                }
+            }
+        } else {
+            self.forwarded_delegations.push((delegate_id, delegations));
+        }
+    }
+
+    /// Remove and return delegations forwarded to `delegate_id`.
+    /// Fails if there are none.
+    pub fn take_forwarded(&mut self, delegate_id: &AccountId) -> Vec<(AccountId, u128)> {
+        let pos = self
+            .forwarded_delegations
+            .iter()
+            .position(|(account_id, _)| account_id == delegate_id)
+            .expect("no forwarded delegations");
+        self.forwarded_delegations.swap_remove(pos).1
+    }
+
     /// Add new delegated vote amount.
     /// Add new delegator if he was not delegator before.
     pub fn add_delegator(&mut self, delegator_id: AccountId, amount: Balance) {
@@ -124,6 +460,11 @@ impl User {
             .iter()
             .position(|(e, _)| e == new_delegate_id);
 
+        let weight = self.delegation_weight(prev_delegate_id);
+        if weight > 0 {
+            self.move_delegation_weight(prev_delegate_id, new_delegate_id, weight);
+        }
+
         // If the new delegate is already in user's delegates, then just add amount of the old one.
         // Else just update account id.
         if let Some(new_pos) = delegate_new_pos {
@@ -156,20 +497,99 @@ impl User {
         }
     }
 
-    /// Withdraw the amount.
-    /// Fails if there is not enough available balance.
-    pub fn withdraw(&mut self, amount: Balance) {
+    /// Withdraw the amount of `token`.
+    /// Fails if there is not enough available balance or the amount is in vote lock.
+    /// Returns withdrawn vote weight.
+    pub fn withdraw(
+        &mut self,
+        token: &VoteToken,
+        amount: Balance,
+        current_timestamp: TimestampSec,
+    ) -> Balance {
+        let token_amount = self.token_amount(&token.token_id);
+        require!(token_amount >= amount, "not enough vote tokens");
+        let weight = token.weight_of(token_amount) - token.weight_of(token_amount - amount);
         require!(
-            self.delegated_amount() + amount <= self.vote_amount,
+            self.delegated_amount() + self.locked_amount() + weight <= self.vote_amount,
             "not enough free vote amount"
         );
+        require!(
+            self.vote_lock_amount(current_timestamp) + weight <= self.vote_amount,
+            "vote tokens are locked"
+        );
+
+        self.set_token_amount(&token.token_id, token_amount - amount);
+        self.vote_amount -= weight;
+        weight
+    }
 
-        self.vote_amount -= amount;
+    /// Add new pending unbond entry.
+    pub fn add_pending_unbond(
+        &mut self,
+        amount: Balance,
+        release_at: TimestampSec,
+        token_id: Option<AccountId>,
+        delegate_id: Option<AccountId>,
+    ) {
+        self.pending_unbonds.push(PendingUnbond {
+            amount,
+            release_at,
+            token_id,
+            delegate_id,
+        });
+    }
+
+    /// Remove all entries released at `current_timestamp`.
+    /// Fails if nothing can be claimed yet.
+    /// Returns withdrawn amounts of each token to be transferred to the user
+    /// and amounts to be delegated to each delegate.
+    pub fn claim_unbonded(&mut self, current_timestamp: TimestampSec) -> ClaimedUnbonds {
+        let (released, pending): (Vec<PendingUnbond>, Vec<PendingUnbond>) =
+            std::mem::take(&mut self.pending_unbonds)
+                .into_iter()
+                .partition(|u| u.release_at <= current_timestamp);
+        require!(!released.is_empty(), "nothing to claim");
+        self.pending_unbonds = pending;
+        let mut amounts: Vec<(AccountId, Balance)> = vec![];
+        let mut delegations: Vec<(AccountId, Balance)> = vec![];
+        for unbond in released {
+            let (account_id, target) = match (unbond.token_id, unbond.delegate_id) {
+                (Some(token_id), _) => (token_id, &mut amounts),
+                (None, Some(delegate_id)) => (delegate_id, &mut delegations),
+                (None, None) => continue,
+            };
+            if let Some(e) = target.iter_mut().find(|(e, _)| *e == account_id) {
+                e.1 += unbond.amount;
+            } else {
+                target.push((account_id, unbond.amount));
+            }
+        }
+        (amounts, delegations)
+    }
+
+    /// Staked and withdrawn pending amounts of each token.
+    pub fn all_token_amounts(&self) -> Vec<(AccountId, Balance)> {
+        let mut amounts = self.token_amounts.clone();
+        for unbond in self.pending_unbonds.iter() {
+            if let Some(token_id) = &unbond.token_id {
+                if let Some(e) = amounts.iter_mut().find(|(e, _)| e == token_id) {
+                    e.1 += unbond.amount;
+                } else {
+                    amounts.push((token_id.clone(), unbond.amount));
+                }
+            }
+        }
+        amounts
     }
 
-    /// Deposit given amount of vote tokens.
-    pub fn deposit(&mut self, amount: Balance) {
-        self.vote_amount += amount;
+    /// Deposit given amount of `token`.
+    /// Returns deposited vote weight.
+    pub fn deposit(&mut self, token: &VoteToken, amount: Balance) -> Balance {
+        let token_amount = self.token_amount(&token.token_id);
+        let weight = token.weight_of(token_amount + amount) - token.weight_of(token_amount);
+        self.set_token_amount(&token.token_id, token_amount + amount);
+        self.vote_amount += weight;
+        weight
     }
 
     fn is_delegator(&self, account_id: &AccountId) -> bool {
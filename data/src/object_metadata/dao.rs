@@ -7,15 +7,30 @@ use library::{
 
 pub fn member_roles_metadata() -> ObjectMetadata {
     ObjectMetadata {
-        arg_names: vec!["name".into(), "members".into()],
-        arg_types: vec![Datatype::String(false), Datatype::VecString],
+        arg_names: vec!["name".into(), "members".into(), "valid_to".into()],
+        arg_types: vec![
+            Datatype::String(false),
+            Datatype::VecString,
+            Datatype::U64(true),
+        ],
+    }
+}
+
+pub fn role_metadata_metadata() -> ObjectMetadata {
+    ObjectMetadata {
+        arg_names: vec!["description".into(), "permissions".into()],
+        arg_types: vec![Datatype::String(true), Datatype::VecString],
     }
 }
 
 pub fn group_member_metadata() -> ObjectMetadata {
     ObjectMetadata {
-        arg_names: vec!["account_id".into(), "tags".into()],
-        arg_types: vec![Datatype::String(false), Datatype::VecU64],
+        arg_names: vec!["account_id".into(), "tags".into(), "valid_to".into()],
+        arg_types: vec![
+            Datatype::String(false),
+            Datatype::VecU64,
+            Datatype::U64(true),
+        ],
     }
 }
 pub fn asset_near() -> ObjectMetadata {
@@ -82,6 +97,7 @@ pub fn action_idents_with_metadata() -> Vec<(DaoActionIdent, Vec<ObjectMetadata>
                     arg_names: vec![
                         "group_id".into(),
                         "role_id".into(),
+                        "tag_id".into(),
                         "partition_id".into(),
                         "type".into(),
                         "time_valid_from".into(),
@@ -91,6 +107,7 @@ pub fn action_idents_with_metadata() -> Vec<(DaoActionIdent, Vec<ObjectMetadata>
                     arg_types: vec![
                         Datatype::U64(false),
                         Datatype::U64(false),
+                        Datatype::U64(true),
                         Datatype::U64(false),
                         Datatype::Enum(vec![1, 2]),
                         Datatype::U64(false),
@@ -176,5 +193,164 @@ pub fn action_idents_with_metadata() -> Vec<(DaoActionIdent, Vec<ObjectMetadata>
                 member_roles_metadata(),
             ],
         ),
+        (
+            DaoActionIdent::GroupUpdate,
+            vec![
+                ObjectMetadata {
+                    arg_names: vec!["id".into(), "settings".into()],
+                    arg_types: vec![Datatype::U64(false), Datatype::Object(1)],
+                },
+                ObjectMetadata {
+                    arg_names: vec!["name".into(), "leader".into(), "parent_group".into()],
+                    arg_types: vec![
+                        Datatype::String(false),
+                        Datatype::String(true),
+                        Datatype::U64(false),
+                    ],
+                },
+            ],
+        ),
+        (
+            DaoActionIdent::GroupSetLeader,
+            vec![ObjectMetadata {
+                arg_names: vec!["id".into(), "leader".into()],
+                arg_types: vec![Datatype::U64(false), Datatype::String(true)],
+            }],
+        ),
+        (
+            DaoActionIdent::GroupAddRole,
+            vec![
+                ObjectMetadata {
+                    arg_names: vec!["id".into(), "name".into(), "metadata".into()],
+                    arg_types: vec![
+                        Datatype::U64(false),
+                        Datatype::String(false),
+                        Datatype::OptionalObject(1),
+                    ],
+                },
+                role_metadata_metadata(),
+            ],
+        ),
+        (
+            DaoActionIdent::GroupRenameRole,
+            vec![ObjectMetadata {
+                arg_names: vec!["id".into(), "role_id".into(), "name".into()],
+                arg_types: vec![
+                    Datatype::U64(false),
+                    Datatype::U64(false),
+                    Datatype::String(false),
+                ],
+            }],
+        ),
+        (
+            DaoActionIdent::GroupDescribeRole,
+            vec![
+                ObjectMetadata {
+                    arg_names: vec!["id".into(), "role_id".into(), "metadata".into()],
+                    arg_types: vec![
+                        Datatype::U64(false),
+                        Datatype::U64(false),
+                        Datatype::Object(1),
+                    ],
+                },
+                role_metadata_metadata(),
+            ],
+        ),
+        (
+            DaoActionIdent::GroupAddMemberTags,
+            vec![ObjectMetadata {
+                arg_names: vec!["id".into(), "account_id".into(), "tags".into()],
+                arg_types: vec![
+                    Datatype::U64(false),
+                    Datatype::String(false),
+                    Datatype::VecU64,
+                ],
+            }],
+        ),
+        (
+            DaoActionIdent::GroupRemoveMemberTags,
+            vec![ObjectMetadata {
+                arg_names: vec!["id".into(), "account_id".into(), "tags".into()],
+                arg_types: vec![
+                    Datatype::U64(false),
+                    Datatype::String(false),
+                    Datatype::VecU64,
+                ],
+            }],
+        ),
+        (
+            DaoActionIdent::SettingsUpdate,
+            vec![ObjectMetadata {
+                arg_names: vec![
+                    "name".into(),
+                    "purpose".into(),
+                    "tags".into(),
+                    "dao_admin_account_id".into(),
+                    "dao_admin_rights".into(),
+                    "workflow_provider".into(),
+                    "resource_provider".into(),
+                    "scheduler".into(),
+                ],
+                arg_types: vec![
+                    Datatype::String(true),
+                    Datatype::String(true),
+                    Datatype::VecU64,
+                    Datatype::String(true),
+                    Datatype::VecString,
+                    Datatype::String(true),
+                    Datatype::String(true),
+                    Datatype::String(true),
+                ],
+            }],
+        ),
+        (
+            DaoActionIdent::TagAdd,
+            vec![ObjectMetadata {
+                arg_names: vec!["category".into(), "values".into()],
+                arg_types: vec![Datatype::String(false), Datatype::VecString],
+            }],
+        ),
+        (
+            DaoActionIdent::TagUpdate,
+            vec![ObjectMetadata {
+                arg_names: vec!["category".into(), "id".into(), "value".into()],
+                arg_types: vec![
+                    Datatype::String(false),
+                    Datatype::U64(false),
+                    Datatype::String(false),
+                ],
+            }],
+        ),
+        (
+            DaoActionIdent::TagRemove,
+            vec![ObjectMetadata {
+                arg_names: vec!["category".into(), "id".into()],
+                arg_types: vec![Datatype::String(false), Datatype::U64(false)],
+            }],
+        ),
+        (
+            DaoActionIdent::MediaRemove,
+            vec![ObjectMetadata {
+                arg_names: vec!["id".into()],
+                arg_types: vec![Datatype::U64(false)],
+            }],
+        ),
+        (
+            DaoActionIdent::FtDistribute,
+            vec![ObjectMetadata {
+                arg_names: vec![
+                    "partition_id".into(),
+                    "group_id".into(),
+                    "role_id".into(),
+                    "amount".into(),
+                ],
+                arg_types: vec![
+                    Datatype::U64(false),
+                    Datatype::U64(false),
+                    Datatype::U64(true),
+                    Datatype::U128(false),
+                ],
+            }],
+        ),
     ]
 }
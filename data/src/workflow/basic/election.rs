@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use crate::TemplateData;
+use library::{
+    interpreter::expression::{EExpr, EOp, ExprTerm, Op, RelOp, TExpr},
+    types::Value,
+    workflow::{
+        action::{ActionData, DaoActionData, InputSource, TemplateAction},
+        activity::{Activity, TemplateActivity, Terminality, Transition, TransitionLimit},
+        settings::{ActivityBind, ProposeSettings, TemplateSettings},
+        template::SourceDataVariant,
+        template::Template,
+        types::{ActivityRight, DaoActionIdent, Src, ValueSrc, VoteScenario},
+        validator::{ObjectValidator, Validator},
+    },
+};
+use near_sdk::{ONE_NEAR, ONE_YOCTO};
+
+pub const DEFAULT_VOTING_DURATION: u32 = 10;
+
+pub const GROUP_ELECTION1_SETTINGS_DEPOSIT_PROPOSE: u128 = ONE_NEAR;
+pub const GROUP_ELECTION1_SETTINGS_DEPOSIT_VOTE: u128 = ONE_YOCTO;
+
+/// Workflow description:
+/// Group member self-nominates by creating proposal with own account as the candidate.
+/// Group votes on the candidacy.
+/// When accepted, only the candidate can execute the activity and become the group leader. (0 -> 1)
+pub struct GroupElection1;
+impl GroupElection1 {
+    pub fn template() -> TemplateData {
+        let template = Template {
+            code: "group_election1".into(),
+            version: "1".into(),
+            auto_exec: false,
+            need_storage: false,
+            receiver_storage_keys: vec![],
+            activities: vec![
+                Activity::Init,
+                Activity::Activity(TemplateActivity {
+                    code: "group_set_leader".into(),
+                    postprocessing: None,
+                    actions: vec![TemplateAction {
+                        exec_condition: None,
+                        validators: vec![Validator::Object(ObjectValidator {
+                            expression_id: 0,
+                            value: vec![
+                                ValueSrc::Src(Src::Input("leader".into())),
+                                ValueSrc::Src(Src::Runtime(2)),
+                            ],
+                        })],
+                        action_data: ActionData::Action(DaoActionData {
+                            code: None,
+                            expected_input: None,
+                            required_deposit: None,
+                            binds: vec![],
+                            name: DaoActionIdent::GroupSetLeader,
+                        }),
+                        postprocessing: None,
+                        optional: false,
+                        input_source: InputSource::PropSettings,
+                    }],
+                    automatic: false,
+                    terminal: Terminality::Automatic,
+                    is_sync: true,
+                }),
+            ],
+            expressions: vec![
+                // Validator: candidate is the caller.
+                EExpr::Boolean(TExpr {
+                    operators: vec![Op {
+                        op_type: EOp::Rel(RelOp::Eqs),
+                        operands_ids: [0, 1],
+                    }],
+                    terms: vec![ExprTerm::Arg(0), ExprTerm::Arg(1)],
+                }),
+            ],
+            transitions: vec![
+                // From 0.
+                vec![Transition {
+                    activity_id: 1,
+                    cond: None,
+                    time_from_cond: None,
+                    time_to_cond: None,
+                }],
+                // From 1.
+                vec![],
+            ],
+            constants: SourceDataVariant::Map(HashMap::new()),
+            end: vec![1],
+        };
+        (template, vec![], vec![], vec![])
+    }
+    pub fn propose_settings(group_id: u16, candidate: &str) -> ProposeSettings {
+        let mut inputs = HashMap::new();
+        inputs.insert("id".into(), Value::U64(group_id as u64));
+        inputs.insert("leader".into(), Value::String(candidate.into()));
+        ProposeSettings {
+            constants: None,
+            activity_constants: vec![
+                None,
+                Some(ActivityBind {
+                    constants: None,
+                    actions_constants: vec![Some(SourceDataVariant::Map(inputs))],
+                }),
+            ],
+            storage_key: None,
+        }
+    }
+
+    /// Members of the group propose and vote.
+    pub fn template_settings(group_id: u16, duration: Option<u32>) -> TemplateSettings {
+        TemplateSettings {
+            allowed_proposers: vec![ActivityRight::Group(group_id)],
+            allowed_voters: ActivityRight::Group(group_id),
+            activity_rights: vec![vec![], vec![ActivityRight::Group(group_id)]],
+            transition_limits: vec![vec![TransitionLimit { to: 1, limit: 1 }], vec![]],
+            scenario: VoteScenario::Democratic,
+            duration: duration.unwrap_or(DEFAULT_VOTING_DURATION),
+            quorum: 51,
+            approve_threshold: 51,
+            spam_threshold: 80,
+            vote_only_once: true,
+            deposit_propose: Some(Self::deposit_propose().into()),
+            deposit_vote: Some(Self::deposit_vote().into()),
+            deposit_propose_return: 0,
+            constants: None,
+        }
+    }
+    pub fn deposit_propose() -> u128 {
+        GROUP_ELECTION1_SETTINGS_DEPOSIT_PROPOSE
+    }
+    pub fn deposit_vote() -> u128 {
+        GROUP_ELECTION1_SETTINGS_DEPOSIT_VOTE
+    }
+}
@@ -157,6 +157,27 @@ impl Group1 {
                     terminal: Terminality::Automatic,
                     is_sync: true,
                 }),
+                Activity::Activity(TemplateActivity {
+                    code: "group_update".into(),
+                    postprocessing: None,
+                    actions: vec![TemplateAction {
+                        exec_condition: None,
+                        validators: vec![],
+                        action_data: ActionData::Action(DaoActionData {
+                            code: None,
+                            expected_input: None,
+                            required_deposit: None,
+                            binds: vec![],
+                            name: DaoActionIdent::GroupUpdate,
+                        }),
+                        postprocessing: None,
+                        optional: false,
+                        input_source: InputSource::PropSettings,
+                    }],
+                    automatic: false,
+                    terminal: Terminality::Automatic,
+                    is_sync: true,
+                }),
             ],
             expressions: vec![],
             transitions: vec![
@@ -198,6 +219,12 @@ impl Group1 {
                         time_from_cond: None,
                         time_to_cond: None,
                     },
+                    Transition {
+                        activity_id: 7,
+                        cond: None,
+                        time_from_cond: None,
+                        time_to_cond: None,
+                    },
                 ],
                 // From 1.
                 vec![],
@@ -209,15 +236,17 @@ impl Group1 {
                 vec![],
                 // From 6.
                 vec![],
+                // From 7.
+                vec![],
             ],
             constants: SourceDataVariant::Map(HashMap::new()),
-            end: vec![1, 2, 3, 4, 5, 6],
+            end: vec![1, 2, 3, 4, 5, 6, 7],
         };
         (template, vec![], vec![], vec![])
     }
     pub fn propose_settings(inputs: Vec<Option<HashMap<String, Value>>>) -> ProposeSettings {
         let mut activity_constants = vec![None];
-        for i in 0..6usize {
+        for i in 0..7usize {
             if let Some(v) = inputs.get(i) {
                 if let Some(m) = v.clone() {
                     activity_constants.push(Some(ActivityBind {
@@ -253,12 +282,14 @@ impl Group1 {
                     ActivityRight::Group(1),
                     ActivityRight::Group(1),
                     ActivityRight::Group(1),
+                    ActivityRight::Group(1),
                 ],
                 vec![],
                 vec![],
                 vec![],
                 vec![],
                 vec![],
+                vec![],
             ],
             transition_limits: vec![
                 vec![
@@ -268,6 +299,7 @@ impl Group1 {
                     TransitionLimit { to: 4, limit: 1 },
                     TransitionLimit { to: 5, limit: 1 },
                     TransitionLimit { to: 6, limit: 1 },
+                    TransitionLimit { to: 7, limit: 1 },
                 ],
                 vec![],
                 vec![],
@@ -275,6 +307,7 @@ impl Group1 {
                 vec![],
                 vec![],
                 vec![],
+                vec![],
             ],
             scenario: VoteScenario::Democratic,
             duration: duration.unwrap_or(DEFAULT_VOTING_DURATION),
@@ -72,6 +72,27 @@ impl Media1 {
                     terminal: Terminality::User,
                     is_sync: true,
                 }),
+                Activity::Activity(TemplateActivity {
+                    code: "media_remove".into(),
+                    postprocessing: None,
+                    actions: vec![TemplateAction {
+                        exec_condition: None,
+                        validators: vec![],
+                        action_data: ActionData::Action(DaoActionData {
+                            code: None,
+                            expected_input: None,
+                            required_deposit: None,
+                            binds: vec![],
+                            name: DaoActionIdent::MediaRemove,
+                        }),
+                        postprocessing: None,
+                        optional: false,
+                        input_source: InputSource::PropSettings,
+                    }],
+                    automatic: false,
+                    terminal: Terminality::User,
+                    is_sync: true,
+                }),
             ],
             expressions: vec![],
             transitions: vec![
@@ -89,6 +110,12 @@ impl Media1 {
                         time_from_cond: None,
                         time_to_cond: None,
                     },
+                    Transition {
+                        activity_id: 3,
+                        cond: None,
+                        time_from_cond: None,
+                        time_to_cond: None,
+                    },
                 ],
                 // From 1.
                 vec![Transition {
@@ -99,9 +126,11 @@ impl Media1 {
                 }],
                 // From 2.
                 vec![],
+                // From 3.
+                vec![],
             ],
             constants: SourceDataVariant::Map(HashMap::new()),
-            end: vec![1, 2],
+            end: vec![1, 2, 3],
         };
         (template, vec![], vec![], vec![])
     }
@@ -109,6 +138,7 @@ impl Media1 {
         storage_key: Option<&str>,
         inputs_activity_1: HashMap<String, Value>,
         inputs_activity_2: HashMap<String, Value>,
+        inputs_activity_3: Option<HashMap<String, Value>>,
     ) -> ProposeSettings {
         let settings = ProposeSettings {
             constants: None,
@@ -122,6 +152,10 @@ impl Media1 {
                     constants: None,
                     actions_constants: vec![Some(SourceDataVariant::Map(inputs_activity_2))],
                 }),
+                inputs_activity_3.map(|inputs| ActivityBind {
+                    constants: None,
+                    actions_constants: vec![Some(SourceDataVariant::Map(inputs))],
+                }),
             ],
             storage_key: storage_key.map(|k| k.to_string()),
         };
@@ -136,14 +170,17 @@ impl Media1 {
                 vec![],
                 vec![ActivityRight::Group(1)],
                 vec![ActivityRight::Group(1)],
+                vec![ActivityRight::Group(1)],
             ],
             transition_limits: vec![
                 vec![
                     TransitionLimit { to: 1, limit: 1 },
                     TransitionLimit { to: 2, limit: 1 },
+                    TransitionLimit { to: 3, limit: 1 },
                 ],
                 vec![TransitionLimit { to: 2, limit: 1 }],
                 vec![],
+                vec![],
             ],
             scenario: VoteScenario::Democratic,
             duration: duration.unwrap_or(DEFAULT_VOTING_DURATION),
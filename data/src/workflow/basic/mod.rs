@@ -1,8 +1,12 @@
 pub mod basic_package;
 pub mod bounty;
+pub mod distribution;
+pub mod election;
 pub mod group;
 pub mod group_package;
 pub mod lock;
 pub mod media;
 pub mod reward;
+pub mod settings;
+pub mod tag;
 pub mod trade;
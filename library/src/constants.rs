@@ -1,3 +1,5 @@
+use near_sdk::Gas;
+
 pub const DURATION_1Y_S: u32 = 31_536_000;
 pub const DURATION_2Y_S: u32 = 63_072_000;
 pub const DURATION_3Y_S: u32 = 94_608_000;
@@ -7,3 +9,8 @@ pub const DURATION_ONE_WEEK: u64 = 604_800_000_000_000;
 pub const DURATION_1Y: u64 = 31_536_000_000_000_000;
 pub const DURATION_2Y: u64 = 63_072_000_000_000_000;
 pub const DURATION_3Y: u64 = 94_608_000_000_000_000;
+
+/// Max count of `ForEach` iterations done during single postprocessing execution.
+pub const MAX_FOREACH_ITERATIONS: u32 = 100;
+/// Min gas which must remain before next `ForEach` iteration is started.
+pub const GAS_MIN_FOREACH_ITERATION: Gas = Gas(5_000_000_000_000);
@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+
+use crate::types::Value;
 use crate::workflow::error::{BindingError, ProcessingError};
 use crate::workflow::runtime::activity_input::ActivityInput;
 use crate::workflow::runtime::source::Source;
@@ -18,7 +21,7 @@ pub fn bind_input(
         match &def.collection_data {
             None => {
                 let value = eval(&def.value, sources, expressions, Some(input))?;
-                input.set(def.key.as_str(), value);
+                set_value(input, def.key.as_str(), value);
             }
             Some(data) => {
                 // Version 1.0 does not support nested collections.
@@ -27,6 +30,18 @@ pub fn bind_input(
                     .get(0)
                     .ok_or(BindingError::CollectionPrefixMissing(0))?;
                 let value = eval(&def.value, sources, expressions, Some(input))?;
+                // Collection provided as vector of maps.
+                if let Some(Value::Vec(_)) = input.get(prefix) {
+                    let mut items = input.take(prefix).unwrap().try_into_vec()?;
+                    if let ForceSame(number) = data.collection_binding_type {
+                        items.resize(number as usize, Value::Map(BTreeMap::new()));
+                    }
+                    for item in items.iter_mut() {
+                        insert_into_map(item, def.key.as_str(), value.clone());
+                    }
+                    input.set(prefix, Value::Vec(items));
+                    continue;
+                }
                 let mut counter: u32 = 0;
                 let mut key = object_key(prefix, counter.to_string().as_str(), def.key.as_str());
                 match data.collection_binding_type {
@@ -55,3 +70,36 @@ pub fn bind_input(
 
     Ok(())
 }
+
+/// Sets `value` under `key` in `input`.
+/// In case `input` contains `Value::Map` under the first part of the key,
+/// the value is inserted into the map instead.
+fn set_value(input: &mut dyn ActivityInput, key: &str, value: Value) {
+    if let Some((prefix, path)) = key.split_once('.') {
+        if let Some(Value::Map(_)) = input.get(prefix) {
+            let mut map = input.take(prefix).unwrap();
+            insert_into_map(&mut map, path, value);
+            input.set(prefix, map);
+            return;
+        }
+    }
+    input.set(key, value);
+}
+
+/// Inserts `value` into nested map under the `path` separated by dots.
+/// Missing nested maps are created. Does nothing if `target` is not a map.
+fn insert_into_map(target: &mut Value, path: &str, value: Value) {
+    if let Value::Map(map) = target {
+        match path.split_once('.') {
+            Some((key, rest)) => {
+                let inner = map
+                    .entry(key.to_string())
+                    .or_insert_with(|| Value::Map(BTreeMap::new()));
+                insert_into_map(inner, rest, value);
+            }
+            None => {
+                map.insert(path.to_string(), value);
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@
 //! TODO: Currently invalid metadata still cause panic.
 
 use crate::{
-    types::Datatype,
+    types::{Datatype, Value},
     workflow::{
         error::ProcessingError, runtime::activity_input::ActivityInput, types::ObjectMetadata,
     },
@@ -134,6 +134,11 @@ fn object_to_json(
     obj_prefix: &str,
     skip_arg_prefix: usize,
 ) -> Result<(), ProcessingError> {
+    // Object provided as nested map does not have to be flattened.
+    if let Some(Value::Map(_)) = input.get(obj_prefix) {
+        input.take(obj_prefix).unwrap().write_json(buf);
+        return Ok(());
+    }
     buf.push('{');
     for i in 0..metadata[meta_pos].arg_names.len() {
         buf.push('"');
@@ -515,6 +520,26 @@ fn primitive_to_json(
             buf.pop();
             buf.push(']');
         }
+        Datatype::AccountId(opt) => match (opt, value.is_null()) {
+            (true, true) => buf.push_str(JSON_NULL),
+            (false, true) => return Err(ProcessingError::MissingInputKey(key.into())),
+            _ => Value::AccountId(value.try_into_account_id()?).write_json(buf),
+        },
+        Datatype::Timestamp(opt) => match (opt, value.is_null()) {
+            (true, true) => buf.push_str(JSON_NULL),
+            (false, true) => return Err(ProcessingError::MissingInputKey(key.into())),
+            _ => buf.push_str(value.try_into_timestamp()?.to_string().as_str()),
+        },
+        Datatype::Vec(opt) => match (opt, value.is_null()) {
+            (true, true) => buf.push_str(JSON_NULL),
+            (false, true) => return Err(ProcessingError::MissingInputKey(key.into())),
+            _ => Value::Vec(value.try_into_vec()?).write_json(buf),
+        },
+        Datatype::Map(opt) => match (opt, value.is_null()) {
+            (true, true) => buf.push_str(JSON_NULL),
+            (false, true) => return Err(ProcessingError::MissingInputKey(key.into())),
+            _ => Value::Map(value.try_into_map()?).write_json(buf),
+        },
         _ => (),
     }
     Ok(())
@@ -1194,3 +1194,44 @@ fn serialize_tuple_optional_missing() {
     assert_eq!(actual_json, expected_json);
     assert_eq!(actual_obj, expected_obj);
 }
+
+#[test]
+fn bind_and_serialize_nested_map() {
+    let metadata = vec![
+        ObjectMetadata {
+            arg_names: vec!["name".into(), "obj".into(), "owner".into()],
+            arg_types: vec![
+                Datatype::String(false),
+                Datatype::Object(1),
+                Datatype::AccountId(false),
+            ],
+        },
+        ObjectMetadata {
+            arg_names: vec!["info".into(), "timestamp".into()],
+            arg_types: vec![Datatype::String(false), Datatype::U64(false)],
+        },
+    ];
+    let mut obj = std::collections::BTreeMap::new();
+    obj.insert("info".to_string(), Value::String("info".into()));
+    let mut input: HashMap<String, Value> = HashMap::new();
+    input.insert("name".into(), Value::String("test".into()));
+    input.insert("obj".into(), Value::Map(obj));
+    input.insert(
+        "owner".into(),
+        Value::AccountId(AccountId::try_from("owner.testnet".to_string()).unwrap()),
+    );
+    let bind_definitions = vec![BindDefinition {
+        key: "obj.timestamp".into(),
+        value: ValueSrc::Value(Value::U64(42)),
+        collection_data: None,
+    }];
+    let source = SourceMock { tpls: vec![] };
+    bind_input(&source, &bind_definitions, &[], &mut input).unwrap();
+    assert!(!input.contains_key("obj.timestamp"));
+
+    let actual_json = serialize_to_json(Box::new(input), metadata.as_slice()).unwrap();
+    assert_eq!(
+        actual_json,
+        r#"{"name":"test","obj":{"info":"info","timestamp":42},"owner":"owner.testnet"}"#
+    );
+}
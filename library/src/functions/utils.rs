@@ -119,3 +119,45 @@ pub fn calculate_percent_u128(value: u128, total: u128) -> u8 {
     }
     (((value * 10_000) / total) as f64 / 100.0).round() as u8
 }
+
+/// Calculates `a * b / c` with full 256-bit intermediate precision.
+/// Returns `None` if `c` is zero or the result does not fit into `u128`.
+pub fn mul_div_u128(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    let (hi, lo) = full_mul_u128(a, b);
+    if hi == 0 {
+        return Some(lo / c);
+    } else if hi >= c {
+        return None;
+    }
+    // Long division of (hi, lo) by c. Remainder is always lower than c.
+    let mut rem = hi;
+    let mut quot: u128 = 0;
+    for i in (0..128).rev() {
+        let carry = rem >> 127;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        quot <<= 1;
+        if carry == 1 || rem >= c {
+            rem = rem.wrapping_sub(c);
+            quot |= 1;
+        }
+    }
+    Some(quot)
+}
+
+/// Multiplies two u128 values into 256-bit result as (high, low) parts.
+fn full_mul_u128(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+    let ll = a_lo * b_lo;
+    let lh = a_lo * b_hi;
+    let hl = a_hi * b_lo;
+    let hh = a_hi * b_hi;
+    let mid = (ll >> 64) + (lh & MASK) + (hl & MASK);
+    let lo = (ll & MASK) | (mid << 64);
+    let hi = hh + (lh >> 64) + (hl >> 64) + (mid >> 64);
+    (hi, lo)
+}
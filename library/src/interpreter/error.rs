@@ -16,4 +16,44 @@ pub enum EvalError {
     InvalidOperands(String),
     #[error("Missing arg atpos: `{0}`")]
     MissingArg(u64),
+    #[error("Arithmetic overflow: `{0}`")]
+    Overflow(String),
+    #[error("Division by zero")]
+    DivisionByZero,
+}
+
+#[derive(Error, Debug, PartialEq)]
+#[error("Parse error at column `{column}`: {kind}")]
+pub struct ParseError {
+    /// Column in the parsed text, starting from 1.
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub fn new(column: usize, kind: ParseErrorKind) -> Self {
+        Self { column, kind }
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ParseErrorKind {
+    #[error("Unexpected character: `{0}`")]
+    UnexpectedChar(char),
+    #[error("Unexpected token: `{0}`")]
+    UnexpectedToken(String),
+    #[error("Unexpected end of input")]
+    UnexpectedEnd,
+    #[error("Unknown function: `{0}`")]
+    UnknownFunction(String),
+    #[error("Unknown source: `{0}`")]
+    UnknownSource(String),
+    #[error("Invalid number: `{0}`")]
+    InvalidNumber(String),
+    #[error("Invalid arg: `{0}`")]
+    InvalidArg(String),
+    #[error("Unsupported expression: `{0}`")]
+    Unsupported(String),
+    #[error("Expression exceeds max: `{0}` operands")]
+    TooManyOperands(usize),
 }
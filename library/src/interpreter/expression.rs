@@ -1,11 +1,14 @@
+use std::convert::TryFrom;
+
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
     json_types::U128,
     serde::{Deserialize, Serialize},
 };
 // TODO: Remove all Debug in production!
 
-use crate::types::Value;
+use crate::{functions::utils::mul_div_u128, types::Value};
 
 use super::{error::EvalError, ArgId};
 
@@ -97,6 +100,71 @@ pub enum FnName {
     /// Return: `Value::Bool`
     /// Return Error if no value is at the pos.
     ValueIsNull,
+    /// Return value in the map under the key if exists, otherwise `Value::Null`.
+    /// Expect 2..N args where:
+    /// - 0th arg is map
+    /// - 1..N args are keys of nested maps
+    ///
+    /// Return: `Value`
+    MapGet,
+    /// Return true if key exists in the map.
+    /// Expect 2 args where:
+    /// - 0th arg is map
+    /// - 1th arg is key
+    ///
+    /// Return: `Value::Bool`
+    MapHasKey,
+    /// Return the lowest value.
+    /// Expect 1..N numeric args.
+    ///
+    /// Return: `Value::U128` if any arg is `Value::U128`, otherwise `Value::U64`
+    Min,
+    /// Return the highest value.
+    /// Expect 1..N numeric args.
+    ///
+    /// Return: `Value::U128` if any arg is `Value::U128`, otherwise `Value::U64`
+    Max,
+    /// Return base raised to the power of exponent.
+    /// Expect 2 args where:
+    /// - 0th arg is base
+    /// - 1th arg is exponent
+    ///
+    /// Return: Value of the same type as base
+    Pow,
+    /// Return `a * b / c` computed with full precision.
+    /// Expect 3 args where:
+    /// - 0th arg is a
+    /// - 1th arg is b
+    /// - 2th arg is c
+    ///
+    /// Return: `Value::U128`
+    MulDiv,
+    /// Return percent share of the value rounded down.
+    /// Expect 2 args where:
+    /// - 0th arg is value
+    /// - 1th arg is percent
+    ///
+    /// Return: Value of the same type as value
+    Percent,
+    /// Return current block timestamp in seconds.
+    /// Expect 0 args.
+    ///
+    /// Return: `Value::Timestamp`
+    Now,
+    /// Add seconds to the timestamp.
+    /// Expect 2 args where:
+    /// - 0th arg is timestamp
+    /// - 1th arg is seconds
+    ///
+    /// Return: `Value::Timestamp`
+    TimestampAdd,
+    /// Return count of seconds between two timestamps.
+    /// Expect 2 args where:
+    /// - 0th arg is later timestamp
+    /// - 1th arg is earlier timestamp
+    ///
+    /// Return: `Value::U64`
+    TimestampDiff,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
@@ -186,6 +254,8 @@ impl EExpr {
                         Value::String(ref v) => result.push_str(v),
                         Value::U64(ref v) => result.push_str(v.to_string().as_str()),
                         Value::U128(ref v) => result.push_str(v.0.to_string().as_str()),
+                        Value::AccountId(ref v) => result.push_str(v.as_str()),
+                        Value::Timestamp(ref v) => result.push_str(v.to_string().as_str()),
                         _ => return Err(EvalError::InvalidDatatype),
                     };
                 }
@@ -224,6 +294,7 @@ impl EExpr {
                         let result = vec.iter().any(|v| v.0 == needle);
                         Ok(Value::Bool(result))
                     }
+                    Value::Vec(vec) => Ok(Value::Bool(vec.contains(&args[1]))),
                     _ => Err(EvalError::InvalidDatatype),
                 }
             }
@@ -256,6 +327,10 @@ impl EExpr {
                         };
                         Ok(value)
                     }
+                    Value::Vec(vec) => {
+                        let index = args[1].try_into_u64()?;
+                        Ok(vec.get(index as usize).cloned().unwrap_or_default())
+                    }
                     _ => Err(EvalError::InvalidDatatype),
                 }
             }
@@ -282,6 +357,11 @@ impl EExpr {
                         new_vec.push(target_value.into());
                         Ok(Value::VecU128(new_vec))
                     }
+                    Value::Vec(vec) => {
+                        let mut new_vec = vec.to_owned();
+                        new_vec.push(args[1].clone());
+                        Ok(Value::Vec(new_vec))
+                    }
                     _ => Err(EvalError::InvalidDatatype),
                 }
             }
@@ -305,6 +385,11 @@ impl EExpr {
                         new_vec.pop();
                         Ok(Value::VecU128(new_vec))
                     }
+                    Value::Vec(vec) => {
+                        let mut new_vec = vec.to_owned();
+                        new_vec.pop();
+                        Ok(Value::Vec(new_vec))
+                    }
                     _ => Err(EvalError::InvalidDatatype),
                 }
             }
@@ -355,6 +440,10 @@ impl EExpr {
                             .collect();
                         Ok(Value::VecString(v))
                     }
+                    Value::Vec(v) => {
+                        let v = v.iter().filter(|v| **v != args[1]).cloned().collect();
+                        Ok(Value::Vec(v))
+                    }
                     _ => Err(EvalError::Unimplemented),
                 }
             }
@@ -423,6 +512,8 @@ impl EExpr {
                     Value::VecU64(v) => Ok(Value::U64(v.len() as u64)),
                     Value::VecU128(v) => Ok(Value::U64(v.len() as u64)),
                     Value::VecString(v) => Ok(Value::U64(v.len() as u64)),
+                    Value::Vec(v) => Ok(Value::U64(v.len() as u64)),
+                    Value::Map(v) => Ok(Value::U64(v.len() as u64)),
                     _ => Err(EvalError::InvalidDatatype),
                 }
             }
@@ -444,6 +535,126 @@ impl EExpr {
                     None => Err(EvalError::MissingArg(pos)),
                 }
             }
+            FnName::MapGet => {
+                if args.len() < 2 {
+                    return Err(EvalError::InvalidArgCount(2));
+                }
+                let mut path = Vec::with_capacity(args.len() - 1);
+                for arg in args[1..].iter() {
+                    path.push(arg.try_into_str()?);
+                }
+                match &args[0] {
+                    Value::Map(_) => Ok(args[0].get_path(&path).cloned().unwrap_or_default()),
+                    _ => Err(EvalError::InvalidDatatype),
+                }
+            }
+            FnName::MapHasKey => {
+                if args.len() < 2 {
+                    return Err(EvalError::InvalidArgCount(2));
+                }
+                let key = args[1].try_into_str()?;
+                match &args[0] {
+                    Value::Map(map) => Ok(Value::Bool(map.contains_key(key))),
+                    _ => Err(EvalError::InvalidDatatype),
+                }
+            }
+            FnName::Min | FnName::Max => {
+                if args.is_empty() {
+                    return Err(EvalError::InvalidArgCount(1));
+                }
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args.iter() {
+                    values.push(arg.try_into_u128()?);
+                }
+                let result = match fn_name {
+                    FnName::Min => values.into_iter().min(),
+                    _ => values.into_iter().max(),
+                }
+                .unwrap();
+                if args.iter().any(|v| matches!(v, Value::U128(_))) {
+                    Ok(Value::U128(result.into()))
+                } else {
+                    let result = u64::try_from(result).map_err(|_| {
+                        EvalError::Overflow(
+                            match fn_name {
+                                FnName::Min => "min",
+                                _ => "max",
+                            }
+                            .into(),
+                        )
+                    })?;
+                    Ok(Value::U64(result))
+                }
+            }
+            FnName::Pow => {
+                if args.len() < 2 {
+                    return Err(EvalError::InvalidArgCount(2));
+                }
+                let exp = u32::try_from(args[1].try_into_u128()?)
+                    .map_err(|_| EvalError::Overflow("pow".into()))?;
+                let overflow = || EvalError::Overflow("pow".into());
+                match &args[0] {
+                    Value::U64(base) => Ok(Value::U64(base.checked_pow(exp).ok_or_else(overflow)?)),
+                    Value::U128(base) => Ok(Value::U128(
+                        base.0.checked_pow(exp).ok_or_else(overflow)?.into(),
+                    )),
+                    _ => Err(EvalError::InvalidDatatype),
+                }
+            }
+            FnName::MulDiv => {
+                if args.len() < 3 {
+                    return Err(EvalError::InvalidArgCount(3));
+                }
+                let (a, b, c) = (
+                    args[0].try_into_u128()?,
+                    args[1].try_into_u128()?,
+                    args[2].try_into_u128()?,
+                );
+                if c == 0 {
+                    return Err(EvalError::DivisionByZero);
+                }
+                let result =
+                    mul_div_u128(a, b, c).ok_or_else(|| EvalError::Overflow("mul_div".into()))?;
+                Ok(Value::U128(result.into()))
+            }
+            FnName::Percent => {
+                if args.len() < 2 {
+                    return Err(EvalError::InvalidArgCount(2));
+                }
+                let percent = args[1].try_into_u128()?;
+                let overflow = || EvalError::Overflow("percent".into());
+                match &args[0] {
+                    Value::U64(v) => {
+                        let result = mul_div_u128(*v as u128, percent, 100).ok_or_else(overflow)?;
+                        Ok(Value::U64(u64::try_from(result).map_err(|_| overflow())?))
+                    }
+                    Value::U128(v) => Ok(Value::U128(
+                        mul_div_u128(v.0, percent, 100).ok_or_else(overflow)?.into(),
+                    )),
+                    _ => Err(EvalError::InvalidDatatype),
+                }
+            }
+            FnName::Now => Ok(Value::Timestamp(env::block_timestamp() / 10u64.pow(9))),
+            FnName::TimestampAdd => {
+                if args.len() < 2 {
+                    return Err(EvalError::InvalidArgCount(2));
+                }
+                let result = args[0]
+                    .try_into_timestamp()?
+                    .checked_add(args[1].try_into_u64()?)
+                    .ok_or_else(|| EvalError::Overflow("timestamp_add".into()))?;
+                Ok(Value::Timestamp(result))
+            }
+            FnName::TimestampDiff => {
+                if args.len() < 2 {
+                    return Err(EvalError::InvalidArgCount(2));
+                }
+                let result = args[0]
+                    .try_into_timestamp()?
+                    .checked_sub(args[1].try_into_timestamp()?)
+                    .ok_or_else(|| EvalError::Overflow("timestamp_diff".into()))?;
+                Ok(Value::U64(result))
+            }
             _ => Err(EvalError::Unimplemented),
         }
     }
@@ -461,6 +672,34 @@ pub enum AriOp {
     Modulo,
 }
 
+impl AriOp {
+    /// Checked evaluation of the operation.
+    pub fn eval_u64(&self, lhs: u64, rhs: u64) -> Result<u64, EvalError> {
+        let result = match self {
+            AriOp::Divide | AriOp::Modulo if rhs == 0 => return Err(EvalError::DivisionByZero),
+            AriOp::Add => lhs.checked_add(rhs),
+            AriOp::Subtract => lhs.checked_sub(rhs),
+            AriOp::Multiply => lhs.checked_mul(rhs),
+            AriOp::Divide => lhs.checked_div(rhs),
+            AriOp::Modulo => lhs.checked_rem(rhs),
+        };
+        result.ok_or_else(|| EvalError::Overflow(format!("{:?}", self)))
+    }
+
+    /// Checked evaluation of the operation.
+    pub fn eval_u128(&self, lhs: u128, rhs: u128) -> Result<u128, EvalError> {
+        let result = match self {
+            AriOp::Divide | AriOp::Modulo if rhs == 0 => return Err(EvalError::DivisionByZero),
+            AriOp::Add => lhs.checked_add(rhs),
+            AriOp::Subtract => lhs.checked_sub(rhs),
+            AriOp::Multiply => lhs.checked_mul(rhs),
+            AriOp::Divide => lhs.checked_div(rhs),
+            AriOp::Modulo => lhs.checked_rem(rhs),
+        };
+        result.ok_or_else(|| EvalError::Overflow(format!("{:?}", self)))
+    }
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(PartialEq))]
 #[serde(crate = "near_sdk::serde")]
@@ -497,28 +736,20 @@ impl EOp {
     pub fn eval(&self, arg1: &Value, arg2: &Value) -> Result<Value, EvalError> {
         match self {
             EOp::Ari(o) => match (arg1, arg2) {
-                (Value::U64(lhs), Value::U64(rhs)) => {
-                    let result = match o {
-                        AriOp::Add => lhs + rhs,
-                        AriOp::Subtract => lhs - rhs,
-                        AriOp::Multiply => lhs * rhs,
-                        AriOp::Divide => lhs / rhs,
-                        AriOp::Modulo => lhs % rhs,
-                    };
-
-                    Ok(Value::U64(result))
-                }
-                (Value::U128(lhs), Value::U128(rhs)) => {
-                    let result = match o {
-                        AriOp::Add => lhs.0 + rhs.0,
-                        AriOp::Subtract => lhs.0 - rhs.0,
-                        AriOp::Multiply => lhs.0 * rhs.0,
-                        AriOp::Divide => lhs.0 / rhs.0,
-                        AriOp::Modulo => lhs.0 % rhs.0,
-                    };
-
+                (Value::U64(lhs), Value::U64(rhs)) => Ok(Value::U64(o.eval_u64(*lhs, *rhs)?)),
+                // Mixed operands are promoted to U128.
+                (Value::U64(_) | Value::U128(_), Value::U64(_) | Value::U128(_)) => {
+                    let result = o.eval_u128(arg1.try_into_u128()?, arg2.try_into_u128()?)?;
                     Ok(Value::U128(result.into()))
                 }
+                (Value::Timestamp(lhs), Value::U64(rhs)) => match o {
+                    AriOp::Add | AriOp::Subtract => Ok(Value::Timestamp(o.eval_u64(*lhs, *rhs)?)),
+                    _ => Err(EvalError::InvalidOperands("timestamp operation".into())),
+                },
+                (Value::Timestamp(lhs), Value::Timestamp(rhs)) => match o {
+                    AriOp::Subtract => Ok(Value::U64(o.eval_u64(*lhs, *rhs)?)),
+                    _ => Err(EvalError::InvalidOperands("timestamp operation".into())),
+                },
                 _ => Err(EvalError::InvalidOperands("aritmetic operation".into())),
             },
             EOp::Rel(o) => match (arg1, arg2) {
@@ -551,6 +782,30 @@ impl EOp {
                     RelOp::GtE => Ok(Value::Bool(*lhs >= *rhs)),
                     RelOp::LtE => Ok(Value::Bool(*lhs <= *rhs)),
                 },
+                (Value::Timestamp(lhs), Value::Timestamp(rhs))
+                | (Value::Timestamp(lhs), Value::U64(rhs))
+                | (Value::U64(lhs), Value::Timestamp(rhs)) => match o {
+                    RelOp::Eqs => Ok(Value::Bool(lhs == rhs)),
+                    RelOp::NEqs => Ok(Value::Bool(lhs != rhs)),
+                    RelOp::Gt => Ok(Value::Bool(lhs > rhs)),
+                    RelOp::Lt => Ok(Value::Bool(lhs < rhs)),
+                    RelOp::GtE => Ok(Value::Bool(lhs >= rhs)),
+                    RelOp::LtE => Ok(Value::Bool(lhs <= rhs)),
+                },
+                (Value::AccountId(_), Value::AccountId(_) | Value::String(_))
+                | (Value::String(_), Value::AccountId(_)) => {
+                    let (lhs, rhs) = (arg1.try_into_str()?, arg2.try_into_str()?);
+                    match o {
+                        RelOp::Eqs => Ok(Value::Bool(lhs == rhs)),
+                        RelOp::NEqs => Ok(Value::Bool(lhs != rhs)),
+                        _ => Err(EvalError::Unimplemented),
+                    }
+                }
+                (Value::Vec(_), Value::Vec(_)) | (Value::Map(_), Value::Map(_)) => match o {
+                    RelOp::Eqs => Ok(Value::Bool(arg1 == arg2)),
+                    RelOp::NEqs => Ok(Value::Bool(arg1 != arg2)),
+                    _ => Err(EvalError::Unimplemented),
+                },
                 _ => Err(EvalError::InvalidDatatype),
             },
             EOp::Log(o) => match (arg1, arg2) {
@@ -566,10 +821,15 @@ impl EOp {
 
 #[cfg(test)]
 mod test {
-    use crate::interpreter::expression::{LogOp, Op};
+    use near_sdk::{test_utils::VMContextBuilder, testing_env};
+
+    use crate::interpreter::{
+        error::EvalError,
+        expression::{LogOp, Op},
+    };
     use crate::types::Value;
 
-    use super::{EExpr, EOp, ExprTerm, FnName, RelOp, TExpr};
+    use super::{AriOp, EExpr, EOp, ExprTerm, FnName, RelOp, TExpr};
 
     #[test]
     pub fn expr_gt() {
@@ -803,4 +1063,160 @@ mod test {
         let result = expr.eval(&args).unwrap();
         assert_eq!(result, Value::Bool(true));
     }
+
+    #[test]
+    pub fn expr_fn_map_get() {
+        let mut inner = std::collections::BTreeMap::new();
+        inner.insert("b".to_string(), Value::U64(1));
+        let mut map = std::collections::BTreeMap::new();
+        map.insert("a".to_string(), Value::Map(inner));
+        let map = Value::Map(map);
+        let expr = EExpr::Fn(FnName::MapGet);
+        let args = vec![
+            map.clone(),
+            Value::String("a".into()),
+            Value::String("b".into()),
+        ];
+        assert_eq!(expr.eval(&args).unwrap(), Value::U64(1));
+        let args = vec![map.clone(), Value::String("b".into())];
+        assert_eq!(expr.eval(&args).unwrap(), Value::Null);
+        let expr = EExpr::Fn(FnName::MapHasKey);
+        let args = vec![map, Value::String("a".into())];
+        assert_eq!(expr.eval(&args).unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    pub fn expr_timestamp_ops() {
+        let created = Value::Timestamp(10);
+        let sum = EOp::Ari(AriOp::Add)
+            .eval(&created, &Value::U64(10))
+            .unwrap();
+        assert_eq!(sum, Value::Timestamp(20));
+        let diff = EOp::Ari(AriOp::Subtract).eval(&sum, &created).unwrap();
+        assert_eq!(diff, Value::U64(10));
+        assert_eq!(
+            EOp::Rel(RelOp::Gt).eval(&sum, &Value::U64(15)).unwrap(),
+            Value::Bool(true)
+        );
+    }
+
+    #[test]
+    pub fn expr_ari_checked() {
+        let add = EOp::Ari(AriOp::Add);
+        assert!(matches!(
+            add.eval(&Value::U64(u64::MAX), &Value::U64(1)),
+            Err(EvalError::Overflow(_))
+        ));
+        assert!(matches!(
+            EOp::Ari(AriOp::Subtract).eval(&Value::U128(1.into()), &Value::U128(2.into())),
+            Err(EvalError::Overflow(_))
+        ));
+        assert!(matches!(
+            EOp::Ari(AriOp::Divide).eval(&Value::U64(1), &Value::U64(0)),
+            Err(EvalError::DivisionByZero)
+        ));
+        assert!(matches!(
+            EOp::Ari(AriOp::Modulo).eval(&Value::U128(1.into()), &Value::U64(0)),
+            Err(EvalError::DivisionByZero)
+        ));
+        // Mixed operands are promoted.
+        assert_eq!(
+            add.eval(&Value::U64(u64::MAX), &Value::U128(1.into()))
+                .unwrap(),
+            Value::U128((u64::MAX as u128 + 1).into())
+        );
+    }
+
+    #[test]
+    pub fn expr_fn_math() {
+        let args = vec![Value::U64(5), Value::U64(2), Value::U64(9)];
+        assert_eq!(EExpr::Fn(FnName::Min).eval(&args).unwrap(), Value::U64(2));
+        assert_eq!(EExpr::Fn(FnName::Max).eval(&args).unwrap(), Value::U64(9));
+        let args = vec![Value::U64(5), Value::U128(7.into())];
+        assert_eq!(
+            EExpr::Fn(FnName::Max).eval(&args).unwrap(),
+            Value::U128(7.into())
+        );
+        // String values are parsed as u128 and must fit into u64 result.
+        let args = vec![Value::U64(5), Value::String(u128::MAX.to_string())];
+        assert!(matches!(
+            EExpr::Fn(FnName::Max).eval(&args),
+            Err(EvalError::Overflow(_))
+        ));
+
+        let pow = EExpr::Fn(FnName::Pow);
+        assert_eq!(
+            pow.eval(&[Value::U128(10.into()), Value::U64(24)]).unwrap(),
+            Value::U128(10u128.pow(24).into())
+        );
+        assert!(matches!(
+            pow.eval(&[Value::U64(10), Value::U64(20)]),
+            Err(EvalError::Overflow(_))
+        ));
+        assert!(matches!(
+            pow.eval(&[Value::U64(1), Value::U128((1u128 << 64).into())]),
+            Err(EvalError::Overflow(_))
+        ));
+
+        // 10^30 * 10^30 overflows u128 during multiplication.
+        let big = Value::U128(10u128.pow(30).into());
+        let mul_div = EExpr::Fn(FnName::MulDiv);
+        assert_eq!(
+            mul_div
+                .eval(&[big.clone(), big.clone(), Value::U128(10u128.pow(24).into())])
+                .unwrap(),
+            Value::U128(10u128.pow(36).into())
+        );
+        assert!(matches!(
+            mul_div.eval(&[big.clone(), big.clone(), Value::U64(1)]),
+            Err(EvalError::Overflow(_))
+        ));
+        assert!(matches!(
+            mul_div.eval(&[big.clone(), big, Value::U64(0)]),
+            Err(EvalError::DivisionByZero)
+        ));
+        assert_eq!(
+            mul_div
+                .eval(&[Value::U128(u128::MAX.into()), Value::U64(3), Value::U64(7)])
+                .unwrap(),
+            Value::U128((u128::MAX / 7 * 3 + (u128::MAX % 7) * 3 / 7).into())
+        );
+
+        let percent = EExpr::Fn(FnName::Percent);
+        assert_eq!(
+            percent.eval(&[Value::U64(999), Value::U64(10)]).unwrap(),
+            Value::U64(99)
+        );
+        assert_eq!(
+            percent
+                .eval(&[Value::U128(u128::MAX.into()), Value::U64(100)])
+                .unwrap(),
+            Value::U128(u128::MAX.into())
+        );
+    }
+
+    #[test]
+    pub fn expr_fn_timestamp() {
+        testing_env!(VMContextBuilder::new()
+            .block_timestamp(1_000 * 10u64.pow(9))
+            .build());
+        assert_eq!(
+            EExpr::Fn(FnName::Now).eval(&[]).unwrap(),
+            Value::Timestamp(1_000)
+        );
+        let later = EExpr::Fn(FnName::TimestampAdd)
+            .eval(&[Value::Timestamp(1_000), Value::U64(60)])
+            .unwrap();
+        assert_eq!(later, Value::Timestamp(1_060));
+        assert_eq!(
+            EExpr::Fn(FnName::TimestampDiff)
+                .eval(&[later, Value::Timestamp(1_000)])
+                .unwrap(),
+            Value::U64(60)
+        );
+        assert!(matches!(
+            EExpr::Fn(FnName::TimestampDiff).eval(&[Value::Timestamp(1), Value::Timestamp(2)]),
+            Err(EvalError::Overflow(_))
+        ));
+    }
 }
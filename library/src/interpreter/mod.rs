@@ -3,5 +3,8 @@
 pub mod condition;
 pub mod error;
 pub mod expression;
+/// Parser is tooling for template authors and is not needed on chain.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod parser;
 
 type ArgId = u8;
@@ -0,0 +1,904 @@
+//! Textual expression syntax compiled into `EExpr`.
+//!
+//! Example: `input.amount >= tpl.min_amount && in_array(storage.members, caller)`
+//!
+//! Supported terms:
+//! - literals: `true`, `false`, `null`, `"string"`, `42`, `42u128`
+//! - sources: `input.<key>`, `tpl.<key>`, `tpl_settings.<key>`, `prop_settings.<key>`,
+//!   `activity.<key>`, `action.<key>`, `storage.<key>`, `global_storage.<key>`, `runtime.<id>`
+//! - runtime constants aliases: `dao_id`, `current_timestamp`, `caller`
+//! - already bound args: `$<id>`
+//! - function calls: snake_case `FnName` with terms as arguments, eg. `array_len(input.items)`
+//!
+//! Operators: `+ - * / %`, `== != > < >= <=`, `&&`, `||` and parentheses for logical groups.
+//! As `TExpr` cannot nest operations, operands of binary operators must be terms
+//! and arithmetic operation is allowed only as the whole expression.
+//! Boolean term in logical operation is compiled as `term == true`.
+
+use near_sdk::serde_json;
+
+use crate::{
+    types::Value,
+    workflow::types::{Src, ValueSrc},
+};
+
+use super::{
+    error::{ParseError, ParseErrorKind},
+    expression::{AriOp, EExpr, EOp, ExprTerm, FnName, LogOp, Op, RelOp, TExpr},
+    ArgId,
+};
+
+const RUNTIME_ALIASES: [(&str, u8); 3] = [("dao_id", 0), ("current_timestamp", 1), ("caller", 2)];
+
+/// Parses `input` and compiles it into expression.
+/// Returns the expression with sources its `ExprTerm::Arg` terms are bound to.
+/// Sources are bound after the highest `$<id>` used in `input`.
+pub fn compile(input: &str) -> Result<(EExpr, Vec<ValueSrc>), ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: input.chars().count() + 1,
+    };
+    let node = parser.parse_or()?;
+    if let Some(token) = parser.tokens.get(parser.pos) {
+        return Err(ParseError::new(
+            token.column,
+            ParseErrorKind::UnexpectedToken(token.kind.to_string()),
+        ));
+    }
+    let offset = match node.max_arg() {
+        Some(id) => id.checked_add(1).ok_or_else(|| {
+            ParseError::new(
+                parser.end,
+                ParseErrorKind::TooManyOperands(u8::MAX as usize + 1),
+            )
+        })?,
+        None => 0,
+    };
+    let mut compiler = Compiler {
+        offset,
+        end: parser.end,
+        args: vec![],
+        terms: vec![],
+        operators: vec![],
+    };
+    let expr = compiler.compile_root(node)?;
+    Ok((expr, compiler.args))
+}
+
+/// Renders `expr` back to the text.
+/// Args are rendered as their sources in `args` if provided, otherwise as `$<id>`.
+pub fn decompile(expr: &EExpr, args: &[ValueSrc]) -> String {
+    let mut buf = String::with_capacity(64);
+    match expr {
+        EExpr::Value(v) => write_value(&mut buf, v),
+        EExpr::Fn(name) => {
+            write_fn_name(&mut buf, name);
+            buf.push('(');
+            for id in 0..args.len() {
+                if id > 0 {
+                    buf.push_str(", ");
+                }
+                write_arg(&mut buf, id as ArgId, args);
+            }
+            buf.push(')');
+        }
+        EExpr::Aritmetic(e) | EExpr::Boolean(e) | EExpr::String(e) => {
+            if !e.operators.is_empty() {
+                write_op(&mut buf, e, e.operators.len() - 1, args);
+            }
+        }
+    }
+    buf
+}
+
+fn write_op(buf: &mut String, expr: &TExpr, op_id: usize, args: &[ValueSrc]) {
+    let op = &expr.operators[op_id];
+    match &op.op_type {
+        EOp::Log(log_op) => {
+            for (i, id) in op.operands_ids.iter().enumerate() {
+                if i == 1 {
+                    buf.push_str(if *log_op == LogOp::And {
+                        " && "
+                    } else {
+                        " || "
+                    });
+                }
+                let nested_other =
+                    matches!(&expr.operators[*id as usize].op_type, EOp::Log(o) if o != log_op);
+                if nested_other {
+                    buf.push('(');
+                }
+                write_op(buf, expr, *id as usize, args);
+                if nested_other {
+                    buf.push(')');
+                }
+            }
+        }
+        op_type => {
+            write_term(buf, &expr.terms[op.operands_ids[0] as usize], args);
+            buf.push(' ');
+            buf.push_str(op_symbol(op_type));
+            buf.push(' ');
+            write_term(buf, &expr.terms[op.operands_ids[1] as usize], args);
+        }
+    }
+}
+
+fn write_term(buf: &mut String, term: &ExprTerm, args: &[ValueSrc]) {
+    match term {
+        ExprTerm::Value(v) => write_value(buf, v),
+        ExprTerm::Arg(id) => write_arg(buf, *id, args),
+        ExprTerm::Fn(name, (from, to)) => {
+            write_fn_name(buf, name);
+            buf.push('(');
+            // Zero args functions are bound to single null value.
+            let no_args = *from == *to
+                && matches!(args.get(*from as usize), Some(ValueSrc::Value(Value::Null)));
+            if !no_args {
+                for id in *from..=*to {
+                    if id > *from {
+                        buf.push_str(", ");
+                    }
+                    write_arg(buf, id, args);
+                }
+            }
+            buf.push(')');
+        }
+    }
+}
+
+fn write_arg(buf: &mut String, id: ArgId, args: &[ValueSrc]) {
+    match args.get(id as usize) {
+        Some(ValueSrc::Src(src)) => write_src(buf, src),
+        Some(ValueSrc::Value(v)) => write_value(buf, v),
+        _ => {
+            buf.push('$');
+            buf.push_str(id.to_string().as_str());
+        }
+    }
+}
+
+fn write_src(buf: &mut String, src: &Src) {
+    let (prefix, key) = match src {
+        Src::Input(k) => ("input", k),
+        Src::Tpl(k) => ("tpl", k),
+        Src::TplSettings(k) => ("tpl_settings", k),
+        Src::PropSettings(k) => ("prop_settings", k),
+        Src::Activity(k) => ("activity", k),
+        Src::Action(k) => ("action", k),
+        Src::Storage(k) => ("storage", k),
+        Src::GlobalStorage(k) => ("global_storage", k),
+        Src::Runtime(id) => {
+            match RUNTIME_ALIASES.iter().find(|(_, alias_id)| alias_id == id) {
+                Some((alias, _)) => buf.push_str(alias),
+                None => {
+                    buf.push_str("runtime.");
+                    buf.push_str(id.to_string().as_str());
+                }
+            }
+            return;
+        }
+    };
+    buf.push_str(prefix);
+    buf.push('.');
+    buf.push_str(key);
+}
+
+fn write_value(buf: &mut String, value: &Value) {
+    match value {
+        Value::Bool(v) => buf.push_str(if *v { "true" } else { "false" }),
+        Value::U64(v) => buf.push_str(v.to_string().as_str()),
+        Value::U128(v) => {
+            buf.push_str(v.0.to_string().as_str());
+            buf.push_str("u128");
+        }
+        Value::Null => buf.push_str("null"),
+        Value::String(v) => buf.push_str(serde_json::to_string(v).unwrap().as_str()),
+        // Not supported by the parser, rendered only for debugging.
+        v => buf.push_str(format!("{:?}", v).as_str()),
+    }
+}
+
+fn write_fn_name(buf: &mut String, name: &FnName) {
+    if let Ok(serde_json::Value::String(name)) = serde_json::to_value(name) {
+        buf.push_str(name.as_str());
+    }
+}
+
+fn op_symbol(op: &EOp) -> &'static str {
+    match op {
+        EOp::Ari(AriOp::Add) => "+",
+        EOp::Ari(AriOp::Subtract) => "-",
+        EOp::Ari(AriOp::Multiply) => "*",
+        EOp::Ari(AriOp::Divide) => "/",
+        EOp::Ari(AriOp::Modulo) => "%",
+        EOp::Rel(RelOp::Eqs) => "==",
+        EOp::Rel(RelOp::NEqs) => "!=",
+        EOp::Rel(RelOp::Gt) => ">",
+        EOp::Rel(RelOp::Lt) => "<",
+        EOp::Rel(RelOp::GtE) => ">=",
+        EOp::Rel(RelOp::LtE) => "<=",
+        EOp::Log(LogOp::And) => "&&",
+        EOp::Log(LogOp::Or) => "||",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Number(String),
+    Str(String),
+    Arg(ArgId),
+    Op(EOp),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl std::fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Ident(v) | TokenKind::Number(v) => write!(f, "{}", v),
+            TokenKind::Str(v) => write!(f, "{:?}", v),
+            TokenKind::Arg(id) => write!(f, "${}", id),
+            TokenKind::Op(op) => write!(f, "{}", op_symbol(op)),
+            TokenKind::LParen => write!(f, "("),
+            TokenKind::RParen => write!(f, ")"),
+            TokenKind::Comma => write!(f, ","),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    /// Column of the first char, starting from 1.
+    column: usize,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::with_capacity(16);
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+        let op = match two.as_str() {
+            "==" => Some(EOp::Rel(RelOp::Eqs)),
+            "!=" => Some(EOp::Rel(RelOp::NEqs)),
+            ">=" => Some(EOp::Rel(RelOp::GtE)),
+            "<=" => Some(EOp::Rel(RelOp::LtE)),
+            "&&" => Some(EOp::Log(LogOp::And)),
+            "||" => Some(EOp::Log(LogOp::Or)),
+            _ => None,
+        };
+        if let Some(op) = op {
+            tokens.push(Token {
+                kind: TokenKind::Op(op),
+                column,
+            });
+            i += 2;
+            continue;
+        }
+        let kind = match c {
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            ',' => TokenKind::Comma,
+            '>' => TokenKind::Op(EOp::Rel(RelOp::Gt)),
+            '<' => TokenKind::Op(EOp::Rel(RelOp::Lt)),
+            '+' => TokenKind::Op(EOp::Ari(AriOp::Add)),
+            '-' => TokenKind::Op(EOp::Ari(AriOp::Subtract)),
+            '*' => TokenKind::Op(EOp::Ari(AriOp::Multiply)),
+            '/' => TokenKind::Op(EOp::Ari(AriOp::Divide)),
+            '%' => TokenKind::Op(EOp::Ari(AriOp::Modulo)),
+            '"' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        Some('"') => break,
+                        Some('\\') => {
+                            let escaped = match chars.get(i + 1) {
+                                Some('n') => '\n',
+                                Some('"') => '"',
+                                Some('\\') => '\\',
+                                Some(c) => {
+                                    return Err(ParseError::new(
+                                        i + 2,
+                                        ParseErrorKind::UnexpectedChar(*c),
+                                    ))
+                                }
+                                None => {
+                                    return Err(ParseError::new(
+                                        i + 2,
+                                        ParseErrorKind::UnexpectedEnd,
+                                    ))
+                                }
+                            };
+                            value.push(escaped);
+                            i += 2;
+                        }
+                        Some(c) => {
+                            value.push(*c);
+                            i += 1;
+                        }
+                        None => return Err(ParseError::new(i + 1, ParseErrorKind::UnexpectedEnd)),
+                    }
+                }
+                TokenKind::Str(value)
+            }
+            '$' => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && chars[end].is_ascii_digit() {
+                    end += 1;
+                }
+                let id: String = chars[start..end].iter().collect();
+                let id = id
+                    .parse::<ArgId>()
+                    .map_err(|_| ParseError::new(column, ParseErrorKind::InvalidArg(id)))?;
+                i = end - 1;
+                TokenKind::Arg(id)
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = i;
+                while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_')
+                {
+                    end += 1;
+                }
+                let number = chars[i..end].iter().collect();
+                i = end - 1;
+                TokenKind::Number(number)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = i;
+                while end < chars.len()
+                    && (chars[end].is_ascii_alphanumeric()
+                        || chars[end] == '_'
+                        || chars[end] == '.')
+                {
+                    end += 1;
+                }
+                let ident = chars[i..end].iter().collect();
+                i = end - 1;
+                TokenKind::Ident(ident)
+            }
+            c => return Err(ParseError::new(column, ParseErrorKind::UnexpectedChar(c))),
+        };
+        tokens.push(Token { kind, column });
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum TermNode {
+    Value(Value),
+    Src(Src),
+    Arg(ArgId),
+    Fn(FnName, Vec<TermNode>, usize),
+}
+
+impl TermNode {
+    fn max_arg(&self) -> Option<ArgId> {
+        match self {
+            TermNode::Arg(id) => Some(*id),
+            TermNode::Fn(_, params, _) => params.iter().filter_map(|p| p.max_arg()).max(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Term(TermNode, usize),
+    Binary(EOp, TermNode, TermNode, usize),
+    Log(LogOp, Box<Node>, Box<Node>),
+}
+
+impl Node {
+    fn max_arg(&self) -> Option<ArgId> {
+        match self {
+            Node::Term(t, _) => t.max_arg(),
+            Node::Binary(_, lhs, rhs, _) => lhs.max_arg().max(rhs.max_arg()),
+            Node::Log(_, lhs, rhs) => lhs.max_arg().max(rhs.max_arg()),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Column after the last char.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.pos).map(|t| &t.kind)
+    }
+
+    fn next(&mut self) -> Result<&Token, ParseError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .ok_or_else(|| ParseError::new(self.end, ParseErrorKind::UnexpectedEnd))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), ParseError> {
+        let token = self.next()?;
+        if token.kind != kind {
+            return Err(ParseError::new(
+                token.column,
+                ParseErrorKind::UnexpectedToken(token.kind.to_string()),
+            ));
+        }
+        Ok(())
+    }
+
+    fn parse_or(&mut self) -> Result<Node, ParseError> {
+        let mut node = self.parse_and()?;
+        while self.peek() == Some(&TokenKind::Op(EOp::Log(LogOp::Or))) {
+            self.pos += 1;
+            let rhs = self.parse_and()?;
+            node = Node::Log(LogOp::Or, Box::new(node), Box::new(rhs));
+        }
+        Ok(node)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, ParseError> {
+        let mut node = self.parse_cmp()?;
+        while self.peek() == Some(&TokenKind::Op(EOp::Log(LogOp::And))) {
+            self.pos += 1;
+            let rhs = self.parse_cmp()?;
+            node = Node::Log(LogOp::And, Box::new(node), Box::new(rhs));
+        }
+        Ok(node)
+    }
+
+    fn parse_cmp(&mut self) -> Result<Node, ParseError> {
+        if self.peek() == Some(&TokenKind::LParen) {
+            self.pos += 1;
+            let node = self.parse_or()?;
+            self.expect(TokenKind::RParen)?;
+            return Ok(node);
+        }
+        let column = self
+            .tokens
+            .get(self.pos)
+            .map(|t| t.column)
+            .unwrap_or(self.end);
+        let lhs = self.parse_term()?;
+        match self.tokens.get(self.pos) {
+            Some(Token {
+                kind: TokenKind::Op(op @ (EOp::Ari(_) | EOp::Rel(_))),
+                column,
+            }) => {
+                let (op, column) = (op.clone(), *column);
+                self.pos += 1;
+                let rhs = self.parse_term()?;
+                Ok(Node::Binary(op, lhs, rhs, column))
+            }
+            _ => Ok(Node::Term(lhs, column)),
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<TermNode, ParseError> {
+        let token = self.next()?;
+        let column = token.column;
+        let term = match token.kind.clone() {
+            TokenKind::Str(v) => TermNode::Value(Value::String(v)),
+            TokenKind::Arg(id) => TermNode::Arg(id),
+            TokenKind::Number(v) => TermNode::Value(parse_number(&v, column)?),
+            TokenKind::Ident(ident) => {
+                if self.peek() == Some(&TokenKind::LParen) {
+                    self.pos += 1;
+                    let name: FnName = serde_json::from_value(serde_json::Value::String(
+                        ident.clone(),
+                    ))
+                    .map_err(|_| ParseError::new(column, ParseErrorKind::UnknownFunction(ident)))?;
+                    let mut params = vec![];
+                    if self.peek() == Some(&TokenKind::RParen) {
+                        self.pos += 1;
+                    } else {
+                        loop {
+                            params.push(self.parse_term()?);
+                            let token = self.next()?;
+                            match token.kind {
+                                TokenKind::Comma => continue,
+                                TokenKind::RParen => break,
+                                _ => {
+                                    return Err(ParseError::new(
+                                        token.column,
+                                        ParseErrorKind::UnexpectedToken(token.kind.to_string()),
+                                    ))
+                                }
+                            }
+                        }
+                    }
+                    TermNode::Fn(name, params, column)
+                } else {
+                    parse_ident(ident, column)?
+                }
+            }
+            kind => {
+                return Err(ParseError::new(
+                    column,
+                    ParseErrorKind::UnexpectedToken(kind.to_string()),
+                ))
+            }
+        };
+        Ok(term)
+    }
+}
+
+fn parse_number(number: &str, column: usize) -> Result<Value, ParseError> {
+    let invalid = || ParseError::new(column, ParseErrorKind::InvalidNumber(number.into()));
+    let digits = number.replace('_', "");
+    if let Some(digits) = digits.strip_suffix("u128") {
+        return Ok(Value::U128(
+            digits.parse::<u128>().map_err(|_| invalid())?.into(),
+        ));
+    }
+    match digits.parse::<u64>() {
+        Ok(v) => Ok(Value::U64(v)),
+        Err(_) => Ok(Value::U128(
+            digits.parse::<u128>().map_err(|_| invalid())?.into(),
+        )),
+    }
+}
+
+fn parse_ident(ident: String, column: usize) -> Result<TermNode, ParseError> {
+    match ident.as_str() {
+        "true" => return Ok(TermNode::Value(Value::Bool(true))),
+        "false" => return Ok(TermNode::Value(Value::Bool(false))),
+        "null" => return Ok(TermNode::Value(Value::Null)),
+        _ => (),
+    }
+    if let Some((_, id)) = RUNTIME_ALIASES.iter().find(|(alias, _)| *alias == ident) {
+        return Ok(TermNode::Src(Src::Runtime(*id)));
+    }
+    let unknown = || ParseError::new(column, ParseErrorKind::UnknownSource(ident.clone()));
+    let (prefix, key) = ident.split_once('.').ok_or_else(unknown)?;
+    if key.is_empty() {
+        return Err(unknown());
+    }
+    let key = key.to_string();
+    let src = match prefix {
+        "input" => Src::Input(key),
+        "tpl" => Src::Tpl(key),
+        "tpl_settings" => Src::TplSettings(key),
+        "prop_settings" => Src::PropSettings(key),
+        "activity" => Src::Activity(key),
+        "action" => Src::Action(key),
+        "storage" => Src::Storage(key),
+        "global_storage" => Src::GlobalStorage(key),
+        "runtime" => Src::Runtime(key.parse().map_err(|_| unknown())?),
+        _ => return Err(unknown()),
+    };
+    Ok(TermNode::Src(src))
+}
+
+struct Compiler {
+    /// Id of the first arg bound to compiled sources.
+    offset: ArgId,
+    /// Column after the last char, reported when limits are exceeded.
+    end: usize,
+    args: Vec<ValueSrc>,
+    terms: Vec<ExprTerm>,
+    operators: Vec<Op>,
+}
+
+impl Compiler {
+    fn compile_root(&mut self, node: Node) -> Result<EExpr, ParseError> {
+        match node {
+            Node::Term(TermNode::Value(v), _) => Ok(EExpr::Value(v)),
+            Node::Term(TermNode::Fn(name, params, column), _) => {
+                // Function expression is evaluated with all args.
+                if self.offset > 0 && !is_arg_range(&params, 0) {
+                    return Err(ParseError::new(
+                        column,
+                        ParseErrorKind::Unsupported("function args must be $0..$N".into()),
+                    ));
+                } else if self.offset == 0 {
+                    for param in params {
+                        self.bind_new(param)?;
+                    }
+                }
+                Ok(EExpr::Fn(name))
+            }
+            Node::Term(_, column) => Err(ParseError::new(
+                column,
+                ParseErrorKind::Unsupported("missing operation".into()),
+            )),
+            node @ Node::Binary(EOp::Ari(_), ..) => {
+                self.compile_node(node)?;
+                Ok(EExpr::Aritmetic(self.take_expr()))
+            }
+            node => {
+                self.compile_node(node)?;
+                Ok(EExpr::Boolean(self.take_expr()))
+            }
+        }
+    }
+
+    fn take_expr(&mut self) -> TExpr {
+        TExpr {
+            operators: std::mem::take(&mut self.operators),
+            terms: std::mem::take(&mut self.terms),
+        }
+    }
+
+    /// Compiles node into operators and returns id of the result.
+    fn compile_node(&mut self, node: Node) -> Result<u8, ParseError> {
+        let (op_type, lhs, rhs) = match node {
+            Node::Log(op, lhs, rhs) => {
+                let lhs = self.compile_log_operand(*lhs)?;
+                let rhs = self.compile_log_operand(*rhs)?;
+                return self.push_op(EOp::Log(op), lhs, rhs);
+            }
+            Node::Binary(op, lhs, rhs, _) => {
+                let lhs = self.compile_term(lhs)?;
+                let rhs = self.compile_term(rhs)?;
+                (op, lhs, rhs)
+            }
+            Node::Term(term, _) => {
+                let lhs = self.compile_term(term)?;
+                let rhs = self.push_term(ExprTerm::Value(Value::Bool(true)))?;
+                (EOp::Rel(RelOp::Eqs), lhs, rhs)
+            }
+        };
+        self.push_op(op_type, lhs, rhs)
+    }
+
+    fn compile_log_operand(&mut self, node: Node) -> Result<u8, ParseError> {
+        if let Node::Binary(EOp::Ari(_), _, _, column) = node {
+            return Err(ParseError::new(
+                column,
+                ParseErrorKind::Unsupported("aritmetic operation in logical operation".into()),
+            ));
+        }
+        self.compile_node(node)
+    }
+
+    fn push_op(&mut self, op_type: EOp, lhs: u8, rhs: u8) -> Result<u8, ParseError> {
+        let id = self.operand_id(self.operators.len())?;
+        self.operators.push(Op {
+            operands_ids: [lhs, rhs],
+            op_type,
+        });
+        Ok(id)
+    }
+
+    fn push_term(&mut self, term: ExprTerm) -> Result<u8, ParseError> {
+        let id = self.operand_id(self.terms.len())?;
+        self.terms.push(term);
+        Ok(id)
+    }
+
+    /// Converts index into operand or arg id.
+    /// Returns error if the id does not fit into `u8`.
+    fn operand_id(&self, index: usize) -> Result<u8, ParseError> {
+        u8::try_from(index).map_err(|_| {
+            ParseError::new(
+                self.end,
+                ParseErrorKind::TooManyOperands(u8::MAX as usize + 1),
+            )
+        })
+    }
+
+    fn compile_term(&mut self, term: TermNode) -> Result<u8, ParseError> {
+        let term = match term {
+            TermNode::Value(v) => ExprTerm::Value(v),
+            TermNode::Arg(id) => ExprTerm::Arg(id),
+            TermNode::Src(src) => {
+                let pos = self
+                    .args
+                    .iter()
+                    .position(|a| matches!(a, ValueSrc::Src(s) if *s == src));
+                match pos {
+                    Some(pos) => ExprTerm::Arg(self.operand_id(self.offset as usize + pos)?),
+                    None => ExprTerm::Arg(self.bind_new(TermNode::Src(src))?),
+                }
+            }
+            TermNode::Fn(name, params, column) => {
+                if params.iter().any(|p| matches!(p, TermNode::Arg(_))) {
+                    match params.first() {
+                        Some(TermNode::Arg(first)) if is_arg_range(&params, *first) => {
+                            let last = self.operand_id(*first as usize + params.len() - 1)?;
+                            ExprTerm::Fn(name, (*first, last))
+                        }
+                        _ => {
+                            return Err(ParseError::new(
+                                column,
+                                ParseErrorKind::Unsupported(
+                                    "function args must be consecutive $<id> or without them"
+                                        .into(),
+                                ),
+                            ))
+                        }
+                    }
+                } else if params.is_empty() {
+                    // Args range cannot be empty.
+                    let id = self.bind_new(TermNode::Value(Value::Null))?;
+                    ExprTerm::Fn(name, (id, id))
+                } else {
+                    let mut range = (0, 0);
+                    for (i, param) in params.into_iter().enumerate() {
+                        let id = self.bind_new(param)?;
+                        if i == 0 {
+                            range.0 = id;
+                        }
+                        range.1 = id;
+                    }
+                    ExprTerm::Fn(name, range)
+                }
+            }
+        };
+        self.push_term(term)
+    }
+
+    /// Binds term to new arg and returns its id.
+    fn bind_new(&mut self, term: TermNode) -> Result<ArgId, ParseError> {
+        let src = match term {
+            TermNode::Value(v) => ValueSrc::Value(v),
+            TermNode::Src(src) => ValueSrc::Src(src),
+            TermNode::Fn(_, _, column) => {
+                return Err(ParseError::new(
+                    column,
+                    ParseErrorKind::Unsupported("nested function call".into()),
+                ))
+            }
+            TermNode::Arg(_) => unreachable!(),
+        };
+        let id = self.operand_id(self.offset as usize + self.args.len())?;
+        self.args.push(src);
+        Ok(id)
+    }
+}
+
+/// Returns true if all params are consecutive args starting at `first`.
+fn is_arg_range(params: &[TermNode], first: ArgId) -> bool {
+    params
+        .iter()
+        .enumerate()
+        .all(|(i, p)| matches!(p, TermNode::Arg(id) if *id as usize == first as usize + i))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        interpreter::{
+            error::ParseErrorKind,
+            expression::{EExpr, EOp, ExprTerm, FnName, LogOp, Op, RelOp, TExpr},
+        },
+        types::Value,
+        workflow::types::{Src, ValueSrc},
+    };
+
+    use super::{compile, decompile};
+
+    #[test]
+    fn parser_compile_example() {
+        let (expr, args) =
+            compile("input.amount >= tpl.min_amount && in_array(storage.members, caller)").unwrap();
+        let expected = EExpr::Boolean(TExpr {
+            operators: vec![
+                Op {
+                    operands_ids: [0, 1],
+                    op_type: EOp::Rel(RelOp::GtE),
+                },
+                Op {
+                    operands_ids: [2, 3],
+                    op_type: EOp::Rel(RelOp::Eqs),
+                },
+                Op {
+                    operands_ids: [0, 1],
+                    op_type: EOp::Log(LogOp::And),
+                },
+            ],
+            terms: vec![
+                ExprTerm::Arg(0),
+                ExprTerm::Arg(1),
+                ExprTerm::Fn(FnName::InArray, (2, 3)),
+                ExprTerm::Value(Value::Bool(true)),
+            ],
+        });
+        assert_eq!(expr, expected);
+        assert_eq!(
+            args,
+            vec![
+                ValueSrc::Src(Src::Input("amount".into())),
+                ValueSrc::Src(Src::Tpl("min_amount".into())),
+                ValueSrc::Src(Src::Storage("members".into())),
+                ValueSrc::Src(Src::Runtime(2)),
+            ]
+        );
+        assert_eq!(
+            decompile(&expr, &args),
+            "input.amount >= tpl.min_amount && in_array(storage.members, caller) == true"
+        );
+        let args_values = vec![
+            Value::U64(10),
+            Value::U64(5),
+            Value::VecString(vec!["alice.near".into()]),
+            Value::String("alice.near".into()),
+        ];
+        assert_eq!(expr.eval(&args_values).unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn parser_round_trip() {
+        let inputs = [
+            "input.a + 10",
+            "$0 == \"a\\\"b\" || ($1 > 1000000000000000000000000u128 && $2 != null)",
+            "(input.a > 1 || input.b < 2) && storage.c == false",
+            "concat(input.a, \"_group\") == tpl.name",
+            "array_len($1, $2) <= 5",
+            "now() > prop_settings.deadline",
+            "percent(input.amount, 10)",
+            "42",
+        ];
+        for input in inputs {
+            let (expr, args) = compile(input).unwrap();
+            let text = decompile(&expr, &args);
+            let (expr_2, args_2) = compile(&text).unwrap();
+            assert_eq!(expr, expr_2, "{}", input);
+            assert_eq!(args, args_2, "{}", input);
+        }
+        let (expr, _) = compile("input.a + 10").unwrap();
+        assert_eq!(decompile(&expr, &[]), "$0 + 10");
+    }
+
+    #[test]
+    fn parser_errors() {
+        let cases = [
+            ("input.a >", 10, ParseErrorKind::UnexpectedEnd),
+            ("input.a # 1", 9, ParseErrorKind::UnexpectedChar('#')),
+            (
+                "input.a > 1 2",
+                13,
+                ParseErrorKind::UnexpectedToken("2".into()),
+            ),
+            (
+                "foo(input.a)",
+                1,
+                ParseErrorKind::UnknownFunction("foo".into()),
+            ),
+            (
+                "unknown.a > 1",
+                1,
+                ParseErrorKind::UnknownSource("unknown.a".into()),
+            ),
+            (
+                "input.a > 1x",
+                11,
+                ParseErrorKind::InvalidNumber("1x".into()),
+            ),
+            ("(input.a > 1", 13, ParseErrorKind::UnexpectedEnd),
+        ];
+        for (input, column, kind) in cases {
+            let err = compile(input).unwrap_err();
+            assert_eq!((err.column, err.kind), (column, kind), "{}", input);
+        }
+        let err = compile("input.a > 1 && input.b + 1").unwrap_err();
+        assert_eq!(err.column, 24);
+        assert!(matches!(err.kind, ParseErrorKind::Unsupported(_)));
+
+        // Operand ids must fit into u8.
+        let input = vec!["$0 == 1"; 130].join(" && ");
+        let err = compile(&input).unwrap_err();
+        assert_eq!(err.column, input.len() + 1);
+        assert_eq!(err.kind, ParseErrorKind::TooManyOperands(256));
+        assert!(compile(&vec!["$0 == 1"; 120].join(" && ")).is_ok());
+    }
+}
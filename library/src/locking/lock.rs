@@ -72,6 +72,14 @@ impl UnlockingDB {
     pub fn init_unlocked(&self) -> u32 {
         self.amount_init_unlocked
     }
+    /// Return total amount unlocked so far from inner lock.
+    pub fn total_unlocked(&self) -> u32 {
+        if let Some(ref lock) = self.lock {
+            lock.amount_total_unlocked
+        } else {
+            0
+        }
+    }
 }
 
 impl TryFrom<UnlockingInput> for UnlockingDB {
@@ -22,6 +22,15 @@ impl StorageBucket {
         self.data.to_vec()
     }
 
+    /// Return at most `limit` entries starting at index `from_index`.
+    pub fn get_data_list(&self, from_index: u64, limit: u64) -> Vec<(String, Value)> {
+        let keys = self.data.keys_as_vector();
+        let values = self.data.values_as_vector();
+        (from_index..std::cmp::min(from_index.saturating_add(limit), self.data.len()))
+            .map(|index| (keys.get(index).unwrap(), values.get(index).unwrap()))
+            .collect()
+    }
+
     pub fn get_data(&self, key: &String) -> Option<Value> {
         self.data.get(key)
     }
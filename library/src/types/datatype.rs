@@ -29,12 +29,27 @@ pub enum Datatype {
     Tuple(ObjectId),
     OptionalTuple(ObjectId),
     VecTuple(ObjectId),
+    AccountId(bool),
+    Timestamp(bool),
+    /// Nested values without metadata definition.
+    /// Serialized as JSON array.
+    Vec(bool),
+    /// Nested values without metadata definition.
+    /// Serialized as JSON object.
+    Map(bool),
 }
 
 impl Datatype {
     pub fn is_optional(&self) -> bool {
         match self {
-            Self::Bool(v) | Self::U64(v) | Self::U128(v) | Self::String(v) => *v,
+            Self::Bool(v)
+            | Self::U64(v)
+            | Self::U128(v)
+            | Self::String(v)
+            | Self::AccountId(v)
+            | Self::Timestamp(v)
+            | Self::Vec(v)
+            | Self::Map(v) => *v,
             Self::OptionalObject(_) => true,
             _ => false,
         }
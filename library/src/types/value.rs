@@ -1,13 +1,22 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
 use near_sdk::{
-    borsh::{self, BorshDeserialize, BorshSerialize},
+    borsh::{BorshDeserialize, BorshSerialize},
     json_types::U128,
     serde::{Deserialize, Serialize},
+    serde_json, AccountId,
 };
 
-use crate::error::CastError;
+use crate::{error::CastError, TimestampSec};
 
 // TODO: Remove debug in production.
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// Borsh encoding is implemented manually as derive does not support recursive types.
+/// Variant tag works as version of the encoding.
+/// New variants must be always appended at the end so already stored values keep loading.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[cfg_attr(not(target_arch = "wasm32"), derive())]
 #[serde(crate = "near_sdk::serde")]
 #[serde(rename_all = "snake_case")]
@@ -21,6 +30,93 @@ pub enum Value {
     VecU64(Vec<u64>),
     VecU128(Vec<U128>),
     VecString(Vec<String>),
+    AccountId(AccountId),
+    Timestamp(TimestampSec),
+    Vec(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+}
+
+impl BorshSerialize for Value {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Value::Bool(v) => {
+                BorshSerialize::serialize(&0u8, writer)?;
+                BorshSerialize::serialize(v, writer)
+            }
+            Value::U64(v) => {
+                BorshSerialize::serialize(&1u8, writer)?;
+                BorshSerialize::serialize(v, writer)
+            }
+            Value::U128(v) => {
+                BorshSerialize::serialize(&2u8, writer)?;
+                BorshSerialize::serialize(v, writer)
+            }
+            Value::Null => BorshSerialize::serialize(&3u8, writer),
+            Value::String(v) => {
+                BorshSerialize::serialize(&4u8, writer)?;
+                BorshSerialize::serialize(v, writer)
+            }
+            Value::VecBool(v) => {
+                BorshSerialize::serialize(&5u8, writer)?;
+                BorshSerialize::serialize(v, writer)
+            }
+            Value::VecU64(v) => {
+                BorshSerialize::serialize(&6u8, writer)?;
+                BorshSerialize::serialize(v, writer)
+            }
+            Value::VecU128(v) => {
+                BorshSerialize::serialize(&7u8, writer)?;
+                BorshSerialize::serialize(v, writer)
+            }
+            Value::VecString(v) => {
+                BorshSerialize::serialize(&8u8, writer)?;
+                BorshSerialize::serialize(v, writer)
+            }
+            Value::AccountId(v) => {
+                BorshSerialize::serialize(&9u8, writer)?;
+                BorshSerialize::serialize(v, writer)
+            }
+            Value::Timestamp(v) => {
+                BorshSerialize::serialize(&10u8, writer)?;
+                BorshSerialize::serialize(v, writer)
+            }
+            Value::Vec(v) => {
+                BorshSerialize::serialize(&11u8, writer)?;
+                BorshSerialize::serialize(v, writer)
+            }
+            Value::Map(v) => {
+                BorshSerialize::serialize(&12u8, writer)?;
+                BorshSerialize::serialize(v, writer)
+            }
+        }
+    }
+}
+
+impl BorshDeserialize for Value {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let value = match <u8 as BorshDeserialize>::deserialize(buf)? {
+            0 => Value::Bool(BorshDeserialize::deserialize(buf)?),
+            1 => Value::U64(BorshDeserialize::deserialize(buf)?),
+            2 => Value::U128(BorshDeserialize::deserialize(buf)?),
+            3 => Value::Null,
+            4 => Value::String(BorshDeserialize::deserialize(buf)?),
+            5 => Value::VecBool(BorshDeserialize::deserialize(buf)?),
+            6 => Value::VecU64(BorshDeserialize::deserialize(buf)?),
+            7 => Value::VecU128(BorshDeserialize::deserialize(buf)?),
+            8 => Value::VecString(BorshDeserialize::deserialize(buf)?),
+            9 => Value::AccountId(BorshDeserialize::deserialize(buf)?),
+            10 => Value::Timestamp(BorshDeserialize::deserialize(buf)?),
+            11 => Value::Vec(BorshDeserialize::deserialize(buf)?),
+            12 => Value::Map(BorshDeserialize::deserialize(buf)?),
+            tag => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown value variant: {}", tag),
+                ))
+            }
+        };
+        Ok(value)
+    }
 }
 
 impl Default for Value {
@@ -41,6 +137,10 @@ impl Value {
             Value::VecU128(_) => "vec_u128",
             Value::VecString(_) => "vec_string",
             Value::Null => "null",
+            Value::AccountId(_) => "account_id",
+            Value::Timestamp(_) => "timestamp",
+            Value::Vec(_) => "vec",
+            Value::Map(_) => "map",
         }
     }
 }
@@ -73,7 +173,7 @@ impl Value {
 
     pub fn try_into_u64(&self) -> Result<u64, CastError> {
         match self {
-            Value::U64(n) => Ok(*n),
+            Value::U64(n) | Value::Timestamp(n) => Ok(*n),
             Value::U128(n) => Ok(n.0 as u64),
             Value::String(v) => Ok(v
                 .parse::<u64>()
@@ -105,11 +205,213 @@ impl Value {
     pub fn try_into_str(&self) -> Result<&str, CastError> {
         match self {
             Value::String(v) => Ok(v.as_str()),
+            Value::AccountId(v) => Ok(v.as_str()),
             _ => Err(CastError::new(self.datatype(), "&str")),
         }
     }
 
+    pub fn try_into_account_id(self) -> Result<AccountId, CastError> {
+        match self {
+            Value::AccountId(v) => Ok(v),
+            Value::String(v) => v
+                .try_into()
+                .map_err(|_| CastError::new("string", "account_id")),
+            _ => Err(CastError::new(self.datatype(), "account_id")),
+        }
+    }
+
+    pub fn try_into_timestamp(&self) -> Result<TimestampSec, CastError> {
+        match self {
+            Value::Timestamp(v) | Value::U64(v) => Ok(*v),
+            _ => Err(CastError::new(self.datatype(), "timestamp")),
+        }
+    }
+
+    pub fn try_into_vec(self) -> Result<Vec<Value>, CastError> {
+        match self {
+            Value::Vec(v) => Ok(v),
+            Value::VecBool(v) => Ok(v.into_iter().map(Value::Bool).collect()),
+            Value::VecU64(v) => Ok(v.into_iter().map(Value::U64).collect()),
+            Value::VecU128(v) => Ok(v.into_iter().map(Value::U128).collect()),
+            Value::VecString(v) => Ok(v.into_iter().map(Value::String).collect()),
+            _ => Err(CastError::new(self.datatype(), "vec")),
+        }
+    }
+
+    pub fn try_into_map(self) -> Result<BTreeMap<String, Value>, CastError> {
+        match self {
+            Value::Map(v) => Ok(v),
+            _ => Err(CastError::new(self.datatype(), "map")),
+        }
+    }
+
     pub fn is_null(&self) -> bool {
         matches!(self, Value::Null)
     }
+
+    /// Returns value nested in maps under the `path` of keys.
+    pub fn get_path(&self, path: &[&str]) -> Option<&Value> {
+        let mut value = self;
+        for key in path {
+            match value {
+                Value::Map(map) => value = map.get(*key)?,
+                _ => return None,
+            }
+        }
+        Some(value)
+    }
+
+    /// Writes value as plain JSON into `buf`.
+    /// Maps are written as objects and vectors as arrays.
+    /// `U128` is written as string same as `near_sdk::json_types::U128`.
+    pub fn write_json(&self, buf: &mut String) {
+        match self {
+            Value::Bool(v) => buf.push_str(if *v { "true" } else { "false" }),
+            Value::U64(v) | Value::Timestamp(v) => buf.push_str(v.to_string().as_str()),
+            Value::U128(v) => {
+                buf.push('"');
+                buf.push_str(v.0.to_string().as_str());
+                buf.push('"');
+            }
+            Value::Null => buf.push_str("null"),
+            Value::String(v) => buf.push_str(serde_json::to_string(v).unwrap().as_str()),
+            Value::AccountId(v) => buf.push_str(serde_json::to_string(v).unwrap().as_str()),
+            Value::VecBool(_) | Value::VecU64(_) | Value::VecU128(_) | Value::VecString(_) => {
+                Value::Vec(self.clone().try_into_vec().unwrap_or_default()).write_json(buf)
+            }
+            Value::Vec(v) => {
+                buf.push('[');
+                for (i, e) in v.iter().enumerate() {
+                    if i > 0 {
+                        buf.push(',');
+                    }
+                    e.write_json(buf);
+                }
+                buf.push(']');
+            }
+            Value::Map(v) => {
+                buf.push('{');
+                for (i, (k, e)) in v.iter().enumerate() {
+                    if i > 0 {
+                        buf.push(',');
+                    }
+                    buf.push_str(serde_json::to_string(k).unwrap().as_str());
+                    buf.push(':');
+                    e.write_json(buf);
+                }
+                buf.push('}');
+            }
+        }
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(v) => Value::Bool(v),
+            serde_json::Value::Number(v) => match v.as_u64() {
+                Some(v) => Value::U64(v),
+                None => Value::String(v.to_string()),
+            },
+            serde_json::Value::String(v) => Value::String(v),
+            serde_json::Value::Array(v) => Value::Vec(v.into_iter().map(Value::from).collect()),
+            serde_json::Value::Object(v) => {
+                Value::Map(v.into_iter().map(|(k, v)| (k, Value::from(v))).collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use near_sdk::{
+        borsh::{BorshDeserialize, BorshSerialize},
+        json_types::U128,
+        serde_json, AccountId,
+    };
+
+    use super::Value;
+
+    fn nested_value() -> Value {
+        let mut inner = BTreeMap::new();
+        inner.insert(
+            "account".to_string(),
+            Value::AccountId(AccountId::new_unchecked("neardao.testnet".into())),
+        );
+        inner.insert("created".to_string(), Value::Timestamp(1_000));
+        let mut map = BTreeMap::new();
+        map.insert("inner".to_string(), Value::Map(inner));
+        map.insert(
+            "items".to_string(),
+            Value::Vec(vec![
+                Value::U64(1),
+                Value::String("a\"b".into()),
+                Value::Null,
+            ]),
+        );
+        Value::Map(map)
+    }
+
+    #[test]
+    fn value_borsh_legacy_encoding() {
+        // Encoding of variants existing before nested values were added.
+        assert_eq!(Value::Bool(true).try_to_vec().unwrap(), vec![0, 1]);
+        assert_eq!(
+            Value::U64(1).try_to_vec().unwrap(),
+            vec![1, 1, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(Value::Null.try_to_vec().unwrap(), vec![3]);
+        assert_eq!(
+            Value::VecString(vec!["a".into()]).try_to_vec().unwrap(),
+            vec![8, 1, 0, 0, 0, 1, 0, 0, 0, b'a']
+        );
+        let legacy = [
+            Value::U128(U128(5)),
+            Value::String("test".into()),
+            Value::VecBool(vec![true]),
+            Value::VecU64(vec![1, 2]),
+            Value::VecU128(vec![U128(3)]),
+        ];
+        for value in legacy {
+            let bytes = value.try_to_vec().unwrap();
+            assert_eq!(Value::try_from_slice(&bytes).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn value_nested_round_trip() {
+        let value = nested_value();
+        let bytes = value.try_to_vec().unwrap();
+        assert_eq!(Value::try_from_slice(&bytes).unwrap(), value);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), value);
+        assert!(Value::try_from_slice(&[13]).is_err());
+    }
+
+    #[test]
+    fn value_write_json() {
+        let mut buf = String::new();
+        nested_value().write_json(&mut buf);
+        assert_eq!(
+            buf,
+            r#"{"inner":{"account":"neardao.testnet","created":1000},"items":[1,"a\"b",null]}"#
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&buf).unwrap();
+        let mut expected = nested_value();
+        // Typed values are not recognized when parsed from plain JSON.
+        if let Value::Map(ref mut map) = expected {
+            if let Some(Value::Map(inner)) = map.get_mut("inner") {
+                inner.insert("account".into(), Value::String("neardao.testnet".into()));
+                inner.insert("created".into(), Value::U64(1_000));
+            }
+        }
+        assert_eq!(Value::from(parsed), expected);
+        assert_eq!(
+            nested_value().get_path(&["inner", "created"]),
+            Some(&Value::Timestamp(1_000))
+        );
+    }
 }
@@ -9,6 +9,7 @@ use crate::{types::Datatype, MethodName};
 use super::{
     postprocessing::Postprocessing,
     runtime::activity_input::UserInput,
+    settings::ProposeSettings,
     types::{BindDefinition, DaoActionIdent, ValueSrc},
     validator::Validator,
 };
@@ -46,6 +47,8 @@ pub enum ActionData {
     Action(DaoActionData),
     SendNear(ValueSrc, ValueSrc),
     Stake(ValueSrc, ValueSrc),
+    /// Spawns child workflow instance linked to the parent proposal.
+    SubWorkflow(SubWorkflowData),
     None,
 }
 
@@ -73,6 +76,16 @@ impl ActionData {
             _ => None,
         }
     }
+
+    pub fn try_into_sub_workflow_data(self) -> Option<SubWorkflowData> {
+        match self {
+            Self::SubWorkflow(data) => Some(data),
+            _ => None,
+        }
+    }
+    pub fn is_sub_workflow(&self) -> bool {
+        matches!(self, Self::SubWorkflow(_))
+    }
 }
 
 // TODO: Remove Debug and Clone in production.
@@ -104,6 +117,26 @@ pub struct FnCallData {
     pub must_succeed: bool,
 }
 
+// TODO: Remove Debug and Clone in production.
+/// Child workflow definition.
+/// Parent activity awaits until the child instance is finished.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Debug)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(PartialEq, Clone))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SubWorkflowData {
+    /// Id of template the child instance is created from.
+    pub template_id: u16,
+    /// Id of the template settings used by the child proposal.
+    pub settings_id: u8,
+    /// Propose settings of the child proposal.
+    /// Binded values are merged into its `constants`.
+    /// Storage key is suffixed with the parent proposal id, eg. "key-1".
+    pub propose_settings: ProposeSettings,
+    pub binds: Vec<BindDefinition>,
+    /// Child proposal is accepted immediately without voting.
+    pub skip_vote: bool,
+}
+
 // TODO: Remove Debug and Clone in production.
 #[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Debug)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(PartialEq))]
@@ -142,6 +175,7 @@ pub enum ActionInputType {
     Event(String),
     SendNear,
     Stake,
+    SubWorkflow,
 }
 
 impl PartialEq<ActionData> for ActionInputType {
@@ -161,6 +195,7 @@ impl PartialEq<ActionData> for ActionInputType {
                 d.name == DaoActionIdent::Event && code == d.code.as_ref().unwrap_or(&"".into())
             }
             (Self::Stake, ActionData::Stake(_, _)) => true,
+            (Self::SubWorkflow, ActionData::SubWorkflow(_)) => true,
             _ => unimplemented!(),
         }
     }
@@ -41,6 +41,14 @@ pub enum PostprocessingError {
     Deserialize(#[from] Error),
     #[error("unsupported function call result type: `{0}`")]
     UnsupportedFnCallResult(String),
+    #[error("invalid foreach body range")]
+    InvalidForEachRange,
+    #[error("foreach collection must be vector, found: `{0}`")]
+    InvalidForEachCollection(String),
+    #[error("foreach iteration limit reached")]
+    IterationLimit,
+    #[error("not enough gas for next foreach iteration")]
+    IterationGas,
 }
 
 #[derive(Error, Debug)]
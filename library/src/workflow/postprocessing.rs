@@ -1,12 +1,16 @@
+use std::ops::Range;
+
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
     json_types::U128,
     serde::{Deserialize, Serialize},
-    serde_json,
+    serde_json, AccountId,
 };
 
 use crate::{
-    functions::evaluation::eval,
+    constants::{GAS_MIN_FOREACH_ITERATION, MAX_FOREACH_ITERATIONS},
+    functions::{evaluation::eval, utils::object_key},
     interpreter::expression::EExpr,
     storage::StorageBucket,
     types::{Datatype, Value},
@@ -17,7 +21,7 @@ use super::{
     error::{PostprocessingError, ProcessingError},
     runtime::activity_input::ActivityInput,
     runtime::source::Source,
-    types::Instruction,
+    types::{FnCallResultType, Instruction},
 };
 
 // TODO: Remove Debug in production.
@@ -95,76 +99,80 @@ impl Postprocessing {
     pub fn execute(
         mut self,
         fn_result_val: Vec<u8>,
-        mut storage: Option<&mut StorageBucket>,
+        storage: Option<&mut StorageBucket>,
         global_storage: &mut StorageBucket,
         new_template: &mut Option<ProviderTemplateData>,
     ) -> Result<(), ProcessingError> {
-        let mut i = 0;
-        while i < self.instructions.len() {
+        let mut ctx = ExecutionContext {
+            fn_result_val: fn_result_val.as_slice(),
+            storage,
+            global_storage,
+            new_template,
+            iterations: 0,
+        };
+        let end = self.instructions.len();
+        self.execute_range(&mut ctx, 0..end, &[])?;
+        Ok(())
+    }
+
+    /// Executes instructions in `range` until jump outside of the range happens.
+    /// `loop_args` are appended to values of evaluated instructions.
+    /// Returns index of the next instruction outside of the range.
+    fn execute_range(
+        &mut self,
+        ctx: &mut ExecutionContext,
+        range: Range<usize>,
+        loop_args: &[Value],
+    ) -> Result<usize, ProcessingError> {
+        let mut i = range.start;
+        while range.contains(&i) {
             // Replace/Swap to avoid cloning.
             let mut ins = std::mem::replace(&mut self.instructions[i], Instruction::None);
             match &mut ins {
                 Instruction::DeleteKey(key) => {
-                    storage
+                    ctx.storage
                         .as_mut()
                         .ok_or(PostprocessingError::StorageMissing)?
                         .remove_data(key);
                 }
                 Instruction::DeleteKeyGlobal(key) => {
-                    global_storage.remove_data(key);
+                    ctx.global_storage.remove_data(key);
                 }
-                Instruction::StoreValue(key, value) => storage
+                Instruction::StoreValue(key, value) => ctx
+                    .storage
                     .as_mut()
                     .ok_or(PostprocessingError::StorageMissing)?
                     .add_data(key, value),
-                Instruction::StoreValueGlobal(key, value) => global_storage.add_data(key, value),
+                Instruction::StoreValueGlobal(key, value) => {
+                    ctx.global_storage.add_data(key, value)
+                }
                 Instruction::StoreFnCallResult(key, type_def) => {
-                    let result = self.deser_datatype_from_slice(
-                        type_def.into_datatype_ref().ok_or_else(|| {
-                            PostprocessingError::UnsupportedFnCallResult(
-                                "custom types are not suported yet".into(),
-                            )
-                        })?,
-                        &fn_result_val,
-                    )?;
-                    storage
+                    let result = self.deser_fncall_result(type_def, ctx.fn_result_val)?;
+                    ctx.storage
                         .as_mut()
                         .ok_or(PostprocessingError::StorageMissing)?
                         .add_data(key, &result);
                 }
                 Instruction::StoreFnCallResultGlobal(key, type_def) => {
-                    let result = self.deser_datatype_from_slice(
-                        type_def.into_datatype_ref().ok_or_else(|| {
-                            PostprocessingError::UnsupportedFnCallResult(
-                                "custom types are not suported yet".into(),
-                            )
-                        })?,
-                        &fn_result_val,
-                    )?;
-                    global_storage.add_data(key, &result);
+                    let result = self.deser_fncall_result(type_def, ctx.fn_result_val)?;
+                    ctx.global_storage.add_data(key, &result);
                 }
                 Instruction::StoreWorkflow => {
                     let (workflow, fncalls, fncall_metadata): ProviderTemplateData =
-                        serde_json::from_slice(&fn_result_val).unwrap();
+                        serde_json::from_slice(ctx.fn_result_val).unwrap();
 
-                    *new_template = Some((workflow, fncalls, fncall_metadata))
+                    *ctx.new_template = Some((workflow, fncalls, fncall_metadata))
                 }
                 Instruction::CondBinded(values, cond, required_fncall_result) => {
+                    let values_len = values.len();
                     // Bind FnCall result to values in condition.
                     if let Some(type_def) = required_fncall_result {
-                        let result = self.deser_datatype_from_slice(
-                            type_def.into_datatype_ref().ok_or_else(|| {
-                                PostprocessingError::UnsupportedFnCallResult(
-                                    "custom types are not suported yet".into(),
-                                )
-                            })?,
-                            &fn_result_val,
-                        )?;
-                        values.push(result);
+                        values.push(self.deser_fncall_result(type_def, ctx.fn_result_val)?);
                     }
+                    values.extend_from_slice(loop_args);
                     let next_ins = cond.eval(values.as_slice())? as usize;
                     // In case this condition is evaluated again we want to restore it back.
-                    values.pop();
+                    values.truncate(values_len);
                     std::mem::swap(&mut self.instructions[i], &mut ins);
                     i = next_ins;
                     continue;
@@ -175,23 +183,40 @@ impl Postprocessing {
                     i = next_ins;
                     continue;
                 }
-                Instruction::None => continue,
+                Instruction::ForEach(key, from, to) => {
+                    let body = *from as usize..*to as usize;
+                    if body.start <= i || body.end < body.start || body.end > range.end {
+                        return Err(PostprocessingError::InvalidForEachRange.into());
+                    }
+                    let items = Self::collection_items(ctx.storage.as_deref(), key)?;
+                    let mut next_ins = body.end;
+                    for (idx, item) in items.into_iter().enumerate() {
+                        ctx.next_iteration()?;
+                        let args = [item, Value::U64(idx as u64)];
+                        let exit = self.execute_range(ctx, body.clone(), &args)?;
+                        if exit != body.end {
+                            next_ins = exit;
+                            break;
+                        }
+                    }
+                    std::mem::swap(&mut self.instructions[i], &mut ins);
+                    i = next_ins;
+                    continue;
+                }
+                Instruction::None => {}
                 Instruction::StoreExpressionBinded(key, values, expr, required_fncall_result) => {
+                    let values_len = values.len();
                     // Bind FnCall result to values in condition.
                     if let Some(type_def) = required_fncall_result {
-                        let result = self.deser_datatype_from_slice(
-                            type_def.into_datatype_ref().ok_or_else(|| {
-                                PostprocessingError::UnsupportedFnCallResult(
-                                    "custom types are not suported yet".into(),
-                                )
-                            })?,
-                            &fn_result_val,
-                        )?;
-                        values.push(result);
+                        values.push(self.deser_fncall_result(type_def, ctx.fn_result_val)?);
                     }
+                    values.extend_from_slice(loop_args);
                     let result = expr.eval(values.as_slice())?;
-                    storage.as_mut().unwrap().add_data(key, &result);
-                    values.pop();
+                    ctx.storage
+                        .as_mut()
+                        .ok_or(PostprocessingError::StorageMissing)?
+                        .add_data(key, &result);
+                    values.truncate(values_len);
                 }
                 Instruction::StoreExpressionGlobalBinded(
                     key,
@@ -199,21 +224,15 @@ impl Postprocessing {
                     expr,
                     required_fncall_result,
                 ) => {
+                    let values_len = values.len();
                     // Bind FnCall result to values in condition.
                     if let Some(type_def) = required_fncall_result {
-                        let result = self.deser_datatype_from_slice(
-                            type_def.into_datatype_ref().ok_or_else(|| {
-                                PostprocessingError::UnsupportedFnCallResult(
-                                    "custom types are not suported yet".into(),
-                                )
-                            })?,
-                            &fn_result_val,
-                        )?;
-                        values.push(result);
+                        values.push(self.deser_fncall_result(type_def, ctx.fn_result_val)?);
                     }
+                    values.extend_from_slice(loop_args);
                     let result = expr.eval(values.as_slice())?;
-                    global_storage.add_data(key, &result);
-                    values.pop();
+                    ctx.global_storage.add_data(key, &result);
+                    values.truncate(values_len);
                 }
                 _ => return Err(PostprocessingError::NonBindedInstruction).map_err(|e| e.into()),
             }
@@ -222,7 +241,54 @@ impl Postprocessing {
             i += 1;
         }
 
-        Ok(())
+        Ok(i)
+    }
+
+    /// Returns items of the collection stored under `key`.
+    /// Reading of indexed values stops once there are more items than iterations allowed.
+    fn collection_items(
+        storage: Option<&StorageBucket>,
+        key: &str,
+    ) -> Result<Vec<Value>, PostprocessingError> {
+        let storage = storage.ok_or(PostprocessingError::StorageMissing)?;
+        let items = match storage.get_data(&key.to_string()) {
+            Some(Value::VecBool(v)) => v.into_iter().map(Value::Bool).collect(),
+            Some(Value::VecU64(v)) => v.into_iter().map(Value::U64).collect(),
+            Some(Value::VecU128(v)) => v.into_iter().map(Value::U128).collect(),
+            Some(Value::VecString(v)) => v.into_iter().map(Value::String).collect(),
+            Some(Value::Vec(v)) => v,
+            Some(v) => {
+                return Err(PostprocessingError::InvalidForEachCollection(
+                    v.datatype().into(),
+                ))
+            }
+            None => {
+                let mut items = vec![];
+                while items.len() as u32 <= MAX_FOREACH_ITERATIONS {
+                    match storage.get_data(&object_key(key, items.len().to_string().as_str(), "")) {
+                        Some(item) => items.push(item),
+                        None => break,
+                    }
+                }
+                items
+            }
+        };
+        Ok(items)
+    }
+
+    fn deser_fncall_result(
+        &self,
+        type_def: &FnCallResultType,
+        promise_result_data: &[u8],
+    ) -> Result<Value, PostprocessingError> {
+        self.deser_datatype_from_slice(
+            type_def.into_datatype_ref().ok_or_else(|| {
+                PostprocessingError::UnsupportedFnCallResult(
+                    "custom types are not suported yet".into(),
+                )
+            })?,
+            promise_result_data,
+        )
     }
 
     fn deser_datatype_from_slice(
@@ -259,6 +325,18 @@ impl Postprocessing {
                 let value = serde_json::from_slice::<Vec<U128>>(promise_result_data);
                 Ok(Value::VecU128(value?))
             }
+            Datatype::AccountId(_) => {
+                let value = serde_json::from_slice::<AccountId>(promise_result_data);
+                Ok(Value::AccountId(value?))
+            }
+            Datatype::Timestamp(_) => {
+                let value = serde_json::from_slice::<u64>(promise_result_data);
+                Ok(Value::Timestamp(value?))
+            }
+            Datatype::Vec(_) | Datatype::Map(_) => {
+                let value = serde_json::from_slice::<serde_json::Value>(promise_result_data);
+                Ok(Value::from(value?))
+            }
             _ => Err(PostprocessingError::UnsupportedFnCallResult(
                 "unknown type".into(),
             )),
@@ -266,6 +344,30 @@ impl Postprocessing {
     }
 }
 
+/// State shared by instructions during single postprocessing execution.
+struct ExecutionContext<'a> {
+    fn_result_val: &'a [u8],
+    storage: Option<&'a mut StorageBucket>,
+    global_storage: &'a mut StorageBucket,
+    new_template: &'a mut Option<ProviderTemplateData>,
+    /// Count of `ForEach` iterations done so far.
+    iterations: u32,
+}
+
+impl<'a> ExecutionContext<'a> {
+    /// Checks iteration limit and remaining gas before next `ForEach` iteration.
+    fn next_iteration(&mut self) -> Result<(), PostprocessingError> {
+        self.iterations += 1;
+        if self.iterations > MAX_FOREACH_ITERATIONS {
+            return Err(PostprocessingError::IterationLimit);
+        }
+        if env::prepaid_gas() - env::used_gas() < GAS_MIN_FOREACH_ITERATION {
+            return Err(PostprocessingError::IterationGas);
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::collections::HashMap;
@@ -273,6 +375,7 @@ mod test {
     use near_sdk::{serde_json, test_utils::VMContextBuilder, testing_env};
 
     use crate::{
+        constants::MAX_FOREACH_ITERATIONS,
         interpreter::{
             condition::Condition,
             expression::{AriOp, EExpr, EOp, ExprTerm, Op, RelOp, TExpr},
@@ -280,6 +383,7 @@ mod test {
         storage::StorageBucket,
         types::{Datatype, Value},
         workflow::{
+            error::{PostprocessingError, ProcessingError},
             postprocessing::Postprocessing,
             runtime::activity_input::ActivityInput,
             runtime::source::SourceMock,
@@ -451,4 +555,154 @@ mod test {
             )]
         );
     }
+
+    /// Multiplies items until item equal to 2 is found.
+    fn foreach_postprocessing() -> Postprocessing {
+        Postprocessing {
+            instructions: vec![
+                Instruction::ForEach("items".into(), 1, 3),
+                Instruction::CondBinded(
+                    vec![],
+                    Condition {
+                        expr: EExpr::Boolean(TExpr {
+                            operators: vec![Op {
+                                operands_ids: [0, 1],
+                                op_type: EOp::Rel(RelOp::Eqs),
+                            }],
+                            terms: vec![ExprTerm::Arg(0), ExprTerm::Value(Value::U64(2))],
+                        }),
+                        true_path: 4,
+                        false_path: 2,
+                    },
+                    None,
+                ),
+                Instruction::StoreExpressionBinded(
+                    "last".into(),
+                    vec![],
+                    EExpr::Aritmetic(TExpr {
+                        operators: vec![Op {
+                            operands_ids: [0, 1],
+                            op_type: EOp::Ari(AriOp::Multiply),
+                        }],
+                        terms: vec![ExprTerm::Arg(0), ExprTerm::Value(Value::U64(10))],
+                    }),
+                    None,
+                ),
+                Instruction::StoreValue("finished".into(), Value::Bool(true)),
+                Instruction::Jump(u8::MAX),
+            ],
+        }
+    }
+
+    #[test]
+    fn postprocessing_foreach_vec() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut global_storage = StorageBucket::new(b"global".to_vec());
+        let mut storage = StorageBucket::new(b"key".to_vec());
+
+        storage.add_data(&"items".into(), &Value::VecU64(vec![3, 1]));
+        assert!(foreach_postprocessing()
+            .execute(vec![], Some(&mut storage), &mut global_storage, &mut None)
+            .is_ok());
+        assert_eq!(storage.get_data(&"last".into()), Some(Value::U64(10)));
+        assert_eq!(
+            storage.get_data(&"finished".into()),
+            Some(Value::Bool(true))
+        );
+
+        // Loop is broken on the second item.
+        storage.remove_data(&"finished".into());
+        storage.add_data(&"items".into(), &Value::VecU64(vec![5, 2, 7]));
+        assert!(foreach_postprocessing()
+            .execute(vec![], Some(&mut storage), &mut global_storage, &mut None)
+            .is_ok());
+        assert_eq!(storage.get_data(&"last".into()), Some(Value::U64(50)));
+        assert_eq!(storage.get_data(&"finished".into()), None);
+    }
+
+    #[test]
+    fn postprocessing_foreach_nested_vec() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut global_storage = StorageBucket::new(b"global".to_vec());
+        let mut storage = StorageBucket::new(b"key".to_vec());
+
+        storage.add_data(
+            &"items".into(),
+            &Value::Vec(vec![Value::U64(3), Value::U64(1)]),
+        );
+        assert!(foreach_postprocessing()
+            .execute(vec![], Some(&mut storage), &mut global_storage, &mut None)
+            .is_ok());
+        assert_eq!(storage.get_data(&"last".into()), Some(Value::U64(10)));
+        assert_eq!(
+            storage.get_data(&"finished".into()),
+            Some(Value::Bool(true))
+        );
+    }
+
+    #[test]
+    fn postprocessing_foreach_indexed_collection() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut global_storage = StorageBucket::new(b"global".to_vec());
+        let mut storage = StorageBucket::new(b"key".to_vec());
+
+        storage.add_data(&"items.0".into(), &Value::U64(4));
+        storage.add_data(&"items.1".into(), &Value::U64(6));
+        assert!(foreach_postprocessing()
+            .execute(vec![], Some(&mut storage), &mut global_storage, &mut None)
+            .is_ok());
+        assert_eq!(storage.get_data(&"last".into()), Some(Value::U64(60)));
+        assert_eq!(
+            storage.get_data(&"finished".into()),
+            Some(Value::Bool(true))
+        );
+
+        for i in 0..=MAX_FOREACH_ITERATIONS {
+            storage.add_data(&format!("items.{}", i), &Value::U64(1));
+        }
+        assert!(matches!(
+            foreach_postprocessing().execute(
+                vec![],
+                Some(&mut storage),
+                &mut global_storage,
+                &mut None
+            ),
+            Err(ProcessingError::Postprocessing(
+                PostprocessingError::IterationLimit
+            ))
+        ));
+    }
+
+    #[test]
+    fn postprocessing_foreach_invalid() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut global_storage = StorageBucket::new(b"global".to_vec());
+        let mut storage = StorageBucket::new(b"key".to_vec());
+
+        storage.add_data(&"items".into(), &Value::U64(1));
+        assert!(matches!(
+            foreach_postprocessing().execute(
+                vec![],
+                Some(&mut storage),
+                &mut global_storage,
+                &mut None
+            ),
+            Err(ProcessingError::Postprocessing(
+                PostprocessingError::InvalidForEachCollection(_)
+            ))
+        ));
+
+        let pp = Postprocessing {
+            instructions: vec![
+                Instruction::StoreValue("finished".into(), Value::Bool(true)),
+                Instruction::ForEach("items".into(), 0, 2),
+            ],
+        };
+        assert!(matches!(
+            pp.execute(vec![], Some(&mut storage), &mut global_storage, &mut None),
+            Err(ProcessingError::Postprocessing(
+                PostprocessingError::InvalidForEachRange
+            ))
+        ));
+    }
 }
@@ -1,5 +1,8 @@
+use std::io::{self, Write};
+
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::U128,
     serde::{Deserialize, Serialize},
     AccountId,
 };
@@ -38,6 +41,12 @@ pub enum DaoActionIdent {
     MediaUpdate,
     MediaRemove,
     FtDistribute,
+    GroupSetLeader,
+    GroupAddMemberTags,
+    GroupRemoveMemberTags,
+    GroupAddRole,
+    GroupRenameRole,
+    GroupDescribeRole,
 }
 
 // TODO: Remove Debug in production.
@@ -51,7 +60,8 @@ pub enum VoteScenario {
 }
 
 // TODO: Remove Debug in production.
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+/// Borsh encoding is implemented manually as derive does not support recursive types.
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(PartialEq))]
 #[serde(crate = "near_sdk::serde")]
 #[serde(rename_all = "snake_case")]
@@ -72,6 +82,138 @@ pub enum ActivityRight {
     TokenHolder,
     /// Member in any group.
     Member,
+    /// All rights must be satisfied.
+    All(Vec<ActivityRight>),
+    /// Negation of the right.
+    Not(Box<ActivityRight>),
+    /// Any account with at least the amount of staked vote tokens in the DAO.
+    MinStake(U128),
+    /// Group member tagged with the tag id.
+    MinTagged(u16, u16),
+    /// Member in any ancestor group of the group.
+    ParentOf(u16),
+}
+
+impl BorshSerialize for ActivityRight {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            ActivityRight::Anyone => BorshSerialize::serialize(&0u8, writer),
+            ActivityRight::Group(g) => {
+                BorshSerialize::serialize(&1u8, writer)?;
+                BorshSerialize::serialize(g, writer)
+            }
+            ActivityRight::GroupMember(g, account_id) => {
+                BorshSerialize::serialize(&2u8, writer)?;
+                BorshSerialize::serialize(g, writer)?;
+                BorshSerialize::serialize(account_id, writer)
+            }
+            ActivityRight::GroupRole(g, r) => {
+                BorshSerialize::serialize(&3u8, writer)?;
+                BorshSerialize::serialize(g, writer)?;
+                BorshSerialize::serialize(r, writer)
+            }
+            ActivityRight::GroupLeader(g) => {
+                BorshSerialize::serialize(&4u8, writer)?;
+                BorshSerialize::serialize(g, writer)
+            }
+            ActivityRight::Account(account_id) => {
+                BorshSerialize::serialize(&5u8, writer)?;
+                BorshSerialize::serialize(account_id, writer)
+            }
+            ActivityRight::TokenHolder => BorshSerialize::serialize(&6u8, writer),
+            ActivityRight::Member => BorshSerialize::serialize(&7u8, writer),
+            ActivityRight::All(rights) => {
+                BorshSerialize::serialize(&8u8, writer)?;
+                BorshSerialize::serialize(rights, writer)
+            }
+            ActivityRight::Not(right) => {
+                BorshSerialize::serialize(&9u8, writer)?;
+                BorshSerialize::serialize(right.as_ref(), writer)
+            }
+            ActivityRight::MinStake(amount) => {
+                BorshSerialize::serialize(&10u8, writer)?;
+                BorshSerialize::serialize(amount, writer)
+            }
+            ActivityRight::MinTagged(g, t) => {
+                BorshSerialize::serialize(&11u8, writer)?;
+                BorshSerialize::serialize(g, writer)?;
+                BorshSerialize::serialize(t, writer)
+            }
+            ActivityRight::ParentOf(g) => {
+                BorshSerialize::serialize(&12u8, writer)?;
+                BorshSerialize::serialize(g, writer)
+            }
+        }
+    }
+}
+
+impl BorshDeserialize for ActivityRight {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let right = match <u8 as BorshDeserialize>::deserialize(buf)? {
+            0 => ActivityRight::Anyone,
+            1 => ActivityRight::Group(BorshDeserialize::deserialize(buf)?),
+            2 => ActivityRight::GroupMember(
+                BorshDeserialize::deserialize(buf)?,
+                BorshDeserialize::deserialize(buf)?,
+            ),
+            3 => ActivityRight::GroupRole(
+                BorshDeserialize::deserialize(buf)?,
+                BorshDeserialize::deserialize(buf)?,
+            ),
+            4 => ActivityRight::GroupLeader(BorshDeserialize::deserialize(buf)?),
+            5 => ActivityRight::Account(BorshDeserialize::deserialize(buf)?),
+            6 => ActivityRight::TokenHolder,
+            7 => ActivityRight::Member,
+            8 => ActivityRight::All(BorshDeserialize::deserialize(buf)?),
+            9 => ActivityRight::Not(Box::new(BorshDeserialize::deserialize(buf)?)),
+            10 => ActivityRight::MinStake(BorshDeserialize::deserialize(buf)?),
+            11 => ActivityRight::MinTagged(
+                BorshDeserialize::deserialize(buf)?,
+                BorshDeserialize::deserialize(buf)?,
+            ),
+            12 => ActivityRight::ParentOf(BorshDeserialize::deserialize(buf)?),
+            tag => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown activity right variant: {}", tag),
+                ))
+            }
+        };
+        Ok(right)
+    }
+}
+
+impl ActivityRight {
+    /// Returns true if set of accounts satisfying the right can be enumerated from group members.
+    /// Only such composite rights can be used as voters as `max_possible_amount` must be known.
+    pub fn is_enumerable(&self) -> bool {
+        match self {
+            ActivityRight::Group(_)
+            | ActivityRight::GroupMember(_, _)
+            | ActivityRight::GroupRole(_, _)
+            | ActivityRight::GroupLeader(_)
+            | ActivityRight::Account(_)
+            | ActivityRight::MinTagged(_, _)
+            | ActivityRight::ParentOf(_) => true,
+            ActivityRight::All(rights) => rights.iter().any(|r| r.is_enumerable()),
+            ActivityRight::Anyone
+            | ActivityRight::TokenHolder
+            | ActivityRight::Member
+            | ActivityRight::Not(_)
+            | ActivityRight::MinStake(_) => false,
+        }
+    }
+
+    /// Returns true if the right is one of the composite variants.
+    pub fn is_composite(&self) -> bool {
+        matches!(
+            self,
+            ActivityRight::All(_)
+                | ActivityRight::Not(_)
+                | ActivityRight::MinStake(_)
+                | ActivityRight::MinTagged(_, _)
+        )
+    }
 }
 
 // TODO: Remove Debug in production.
@@ -204,6 +346,13 @@ pub enum Instruction {
     Cond(Vec<ValueSrc>, Condition, Option<FnCallResultType>),
     CondBinded(Vec<Value>, Condition, Option<FnCallResultType>),
     Jump(u8),
+    /// Iterates over collection stored in workflow storage under the key.
+    /// Collection is either `Value::Vec*` or indexed values stored as `key.0`, `key.1`, ...
+    /// Body is range of instructions `[from, to)` executed for each item.
+    /// Current item and its index are always appended as last args in values of body instructions.
+    /// Jump to the end of the body continues with next item.
+    /// Any other jump outside the body breaks the loop.
+    ForEach(String, u8, u8),
     None,
 }
 
@@ -133,6 +133,7 @@ async fn workflow_media1_scenario() -> anyhow::Result<()> {
             None,
             ActivityInputMedia1::propose_settings_activity_1_cid(),
             ActivityInputMedia1::propose_settings_activity_2_cid(),
+            None,
         ),
         None,
         vec![(&member, 1)],
@@ -12,6 +12,7 @@ pub struct GroupMembers(pub HashMap<AccountId, Vec<u16>>);
 pub struct Group {
     pub settings: GroupSettings,
     pub members: GroupMembers,
+    pub members_valid_to: HashMap<AccountId, u64>,
     pub rewards: Vec<(u16, u16)>,
 }
 
@@ -35,6 +36,8 @@ pub struct GroupSettings {
 pub struct GroupMember {
     pub account_id: AccountId,
     pub tags: Vec<u16>,
+    #[serde(default)]
+    pub valid_to: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -118,4 +121,6 @@ impl UserRoles {
 pub struct MemberRoles {
     pub name: String,
     pub members: Vec<String>,
+    #[serde(default)]
+    pub valid_to: Option<u64>,
 }
@@ -81,6 +81,10 @@ pub struct Proposal {
     pub workflow_id: u16,
     pub workflow_settings_id: u8,
     pub voting_results: Vec<U128>,
+    #[serde(default)]
+    pub parent: Option<u32>,
+    #[serde(default)]
+    pub media: Vec<u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -99,12 +103,13 @@ pub enum ProposalState {
 #[serde(rename_all = "snake_case")]
 pub enum VersionedProposal {
     V1(Proposal),
+    V2(Proposal),
 }
 
 impl From<VersionedProposal> for Proposal {
     fn from(v: VersionedProposal) -> Self {
         match v {
-            VersionedProposal::V1(v) => v,
+            VersionedProposal::V1(v) | VersionedProposal::V2(v) => v,
         }
     }
 }
@@ -92,6 +92,8 @@ pub struct Reward {
     pub group_id: u16,
     /// Role id in the group.
     pub role_id: u16,
+    /// Tag id of the group members from the group tag category.
+    pub tag_id: u16,
     /// Partition from which the assets are taken.
     /// Partition must have all defined assets.
     pub partition_id: u16,
@@ -25,6 +25,17 @@ pub struct FtSettings {
     /// If not set then upgrade is not allowed.
     /// TODO: Implement.
     upgrade_provider: Option<AccountId>,
+    #[serde(default)]
+    max_supply: Option<U128>,
+    #[serde(default)]
+    mint_rate_limit: Option<MintRateLimit>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintRateLimit {
+    pub amount: U128,
+    pub period: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
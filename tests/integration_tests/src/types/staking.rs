@@ -13,4 +13,36 @@ pub struct User {
     pub delegated_vote_amount: u128,
     /// List of users whom delegated their tokens to this user.
     pub delegators: Vec<workspaces::AccountId>,
+    /// Total vote weight delegated to this user.
+    pub delegated_vote_weight: u128,
+    /// Checkpoints of delegated vote weight as (timestamp, weight).
+    pub vote_weight_checkpoints: Vec<(u64, u128)>,
+    /// Staked amount of each vote token.
+    pub token_amounts: Vec<(workspaces::AccountId, u128)>,
+    /// Undelegated and withdrawn amounts waiting for the end of the unbonding period.
+    pub pending_unbonds: Vec<PendingUnbond>,
+    /// Delegations forwarded to other delegates, recorded per original delegator.
+    pub forwarded_delegations: Vec<(workspaces::AccountId, Vec<(workspaces::AccountId, u128)>)>,
+    /// Owned tokens locked for boosted vote weight.
+    pub vote_lock: Option<VoteLock>,
+    /// Vote weights reported to the DAO for each delegation.
+    pub delegated_weights: Vec<(workspaces::AccountId, u128)>,
+}
+
+/// Staking vote lock structure
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteLock {
+    pub amount: u128,
+    pub unlock_at: u64,
+}
+
+/// Staking pending unbond structure
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingUnbond {
+    pub amount: u128,
+    pub release_at: u64,
+    pub token_id: Option<workspaces::AccountId>,
+    pub delegate_id: Option<workspaces::AccountId>,
 }
@@ -1,5 +1,7 @@
 //! Check functions for DAO workflows.
 
+use std::collections::HashMap;
+
 use library::{types::Value, workflow::instance::InstanceState};
 use serde_json::json;
 use workspaces::{AccountId, DevNetwork, Worker};
@@ -43,6 +45,7 @@ where
                 parent_group: expected_group_parent,
             },
             members: GroupMembers(members),
+            members_valid_to: HashMap::new(),
             rewards: expected_group_rewards,
         },
     );
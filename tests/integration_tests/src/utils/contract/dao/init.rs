@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
 use data::{
     object_metadata::standard_fn_calls::{standard_fn_call_metadatas, standard_fn_call_methods},
@@ -107,6 +107,7 @@ pub fn dao_init_args(
         Group {
             settings: group.settings.clone(),
             members: GroupMembers(members),
+            members_valid_to: HashMap::new(),
             rewards: vec![],
         },
     );
@@ -161,12 +162,14 @@ fn default_group(members: Vec<&AccountId>) -> GroupInput {
         .map(|m| GroupMember {
             account_id: m.clone(),
             tags: vec![],
+            valid_to: None,
         })
         .collect::<Vec<GroupMember>>();
 
     let member_roles = vec![MemberRoles {
         name: "council".into(),
         members: members_accounts,
+        valid_to: None,
     }];
 
     GroupInput {
@@ -131,6 +131,11 @@ where
         let factory_contract = factory.deploy(worker, &std::fs::read(wasm_blob)?).await?;
         let args = json!({
             "type": migration_type,
+            "version_metadata": {
+                "semver": "2.0.0",
+                "changelog_hash": null,
+                "required_version": 1,
+            },
         })
         .to_string()
         .into_bytes();
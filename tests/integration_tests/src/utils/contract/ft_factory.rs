@@ -1,7 +1,4 @@
-use near_sdk::{
-    json_types::{Base64VecU8, U128},
-    ONE_NEAR,
-};
+use near_sdk::{json_types::U128, ONE_NEAR};
 use serde_json::json;
 use workspaces::{network::DevAccountDeployer, Contract, DevNetwork, Worker};
 
@@ -44,7 +41,8 @@ pub async fn create_ft_via_factory<T>(
 where
     T: DevNetwork,
 {
-    let ft_args = json!({
+    let args = json!({
+        "name": ft_name,
         "owner_id": owner_id,
         "total_supply": U128(total_supply * 10u128.pow(decimals)),
         "metadata": metadata.unwrap_or(default_ft_metadata()),
@@ -53,13 +51,6 @@ where
     })
     .to_string()
     .into_bytes();
-    let base64_ft_args = Base64VecU8(ft_args);
-    let args = json!({
-        "name": ft_name,
-        "args": base64_ft_args,
-    })
-    .to_string()
-    .into_bytes();
     let outcome = ft_factory
         .call(&worker, "create")
         .args(args)
@@ -307,6 +307,11 @@ impl Contract {
                     ActivityRight::TokenHolder => {
                         max_possible_amount = self.total_delegators_count as u128;
                     }
+                    ActivityRight::All(_)
+                    | ActivityRight::Not(_)
+                    | ActivityRight::MinStake(_)
+                    | ActivityRight::MinTagged(_, _)
+                    | ActivityRight::ParentOf(_) => panic_str("unsupported activity right"),
                     ActivityRight::Member => {
                         max_possible_amount = self.total_members_count as u128;
                     }
@@ -339,6 +344,11 @@ impl Contract {
                             self.delegations.get(&voter).unwrap_or(0);
                     }
                 }
+                ActivityRight::All(_)
+                | ActivityRight::Not(_)
+                | ActivityRight::MinStake(_)
+                | ActivityRight::MinTagged(_, _)
+                | ActivityRight::ParentOf(_) => panic_str("unsupported activity right"),
                 ActivityRight::Member => {
                     todo!()
                 }
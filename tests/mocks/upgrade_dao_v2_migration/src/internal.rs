@@ -93,6 +93,11 @@ impl Contract {
                         continue;
                     }
                 }
+                ActivityRight::All(_)
+                | ActivityRight::Not(_)
+                | ActivityRight::MinStake(_)
+                | ActivityRight::MinTagged(_, _)
+                | ActivityRight::ParentOf(_) => continue,
                 ActivityRight::Account(a) => match *a == *account_id {
                     true => return true,
                     false => continue,
@@ -19,11 +19,15 @@ use data::{
         basic::{
             basic_package::WfBasicPkg1,
             bounty::Bounty1,
+            distribution::FtDistribute1,
+            election::GroupElection1,
             group::Group1,
             group_package::GroupPackage1,
             lock::Lock1,
             media::Media1,
             reward::{Reward1, Reward2},
+            settings::DaoSettings1,
+            tag::Tag1,
             trade::Trade1,
         },
         integration::skyward::{Skyward1, Skyward1TemplateOptions},
@@ -151,5 +155,9 @@ fn wf_templates() -> Vec<(&'static str, TemplateData)> {
     vec.push(("GROUP1", Group1::template()));
     vec.push(("GROUP_PACKAGE1", GroupPackage1::template()));
     vec.push(("REWARD2", Reward2::template()));
+    vec.push(("DAO_SETTINGS1", DaoSettings1::template()));
+    vec.push(("TAG1", Tag1::template()));
+    vec.push(("FT_DISTRIBUTE1", FtDistribute1::template()));
+    vec.push(("GROUP_ELECTION1", GroupElection1::template()));
     vec
 }